@@ -5,7 +5,7 @@ use std::time::Duration;
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 初始化日志
-    env_logger::init();
+    tracing_subscriber::fmt::init();
     
     println!("启动基础扫描示例...\n");
     
@@ -0,0 +1,23 @@
+use polymarket_scanner::export::export_markets_parquet;
+use polymarket_scanner::{JsonDatabase, Storage};
+
+/// 将 JSON 存储中的市场导出为 Parquet
+///
+/// 运行: cargo run --example export_parquet --features parquet -- [数据目录] [输出文件]
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // 初始化日志
+    tracing_subscriber::fmt::init();
+
+    let mut args = std::env::args().skip(1);
+    let data_dir = args.next().unwrap_or_else(|| "./data".to_string());
+    let output = args.next().unwrap_or_else(|| "markets.parquet".to_string());
+
+    let db = JsonDatabase::new(&data_dir);
+    db.init().await?;
+
+    let count = export_markets_parquet(&db, &output).await?;
+    println!("已导出 {} 个市场到 {}", count, output);
+
+    Ok(())
+}
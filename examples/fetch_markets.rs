@@ -3,7 +3,7 @@ use polymarket_scanner::api::PolymarketClient;
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 初始化日志
-    env_logger::init();
+    tracing_subscriber::fmt::init();
     
     println!("获取 Polymarket 市场列表...\n");
     
@@ -0,0 +1,27 @@
+use polymarket_scanner::{import_markets_ndjson, JsonDatabase, Storage};
+use std::fs::File;
+use std::io::BufReader;
+
+/// 从 NDJSON 文件（每行一个市场 JSON）导入市场到 JSON 存储
+///
+/// 运行: cargo run --example import -- <输入文件> [数据目录]
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // 初始化日志
+    tracing_subscriber::fmt::init();
+
+    let mut args = std::env::args().skip(1);
+    let Some(input) = args.next() else {
+        eprintln!("用法: import <输入文件> [数据目录]");
+        std::process::exit(2);
+    };
+    let data_dir = args.next().unwrap_or_else(|| "./data".to_string());
+
+    let db = JsonDatabase::new(&data_dir);
+    db.init().await?;
+
+    let report = import_markets_ndjson(&db, BufReader::new(File::open(&input)?)).await?;
+    println!("已导入 {} 个市场到 {}，跳过 {} 行无效数据", report.imported, data_dir, report.skipped);
+
+    Ok(())
+}
@@ -3,7 +3,7 @@ use polymarket_scanner::{JsonDatabase, Storage};
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 初始化日志
-    env_logger::init();
+    tracing_subscriber::fmt::init();
 
     println!("查询 Polymarket Redis 数据库...\n");
 
@@ -11,12 +11,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let db = JsonDatabase::new("./data");
     db.init().await?;
     // 获取统计信息
-    let market_count = db.get_all_market_ids().await?.len();
+    let stats = db.get_stats().await?;
 
     println!("═══════════════════════════════════════════");
     println!("数据库统计信息:");
     println!("───────────────────────────────────────────");
-    println!("市场总数: {}", market_count);
+    println!("市场总数: {}", stats.market_count);
+    let status = db.get_market_status_counts().await?;
+    println!(
+        "  活跃: {}, 已停用: {}, 已关闭: {}, 已结算: {}",
+        status["active"], status["inactive"], status["closed"], status["resolved"]
+    );
+    println!("事件总数: {}", stats.event_count);
+    for (event_type, count) in &stats.events_by_type {
+        println!("  {}: {}", event_type, count);
+    }
+    println!("价格历史记录: {}", stats.price_history_points);
+    if let (Some(oldest), Some(newest)) = (stats.oldest_market_update, stats.newest_market_update) {
+        println!("市场更新时间: {} ~ {}", oldest, newest);
+    }
+    println!("───────────────────────────────────────────");
+    println!("成交量前 5 的市场:");
+    for (i, market) in db.get_top_markets_by_volume(5).await?.iter().enumerate() {
+        println!(
+            "  {}. {} (${})",
+            i + 1,
+            market.question,
+            market.volume.as_deref().unwrap_or("0")
+        );
+    }
     println!("═══════════════════════════════════════════\n");
 
     Ok(())
@@ -4,7 +4,7 @@ use std::sync::Arc;
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 初始化日志
-    env_logger::init();
+    tracing_subscriber::fmt::init();
 
     println!("扫描所有 Polymarket 市场数据...\n");
 
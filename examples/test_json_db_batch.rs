@@ -1,7 +1,6 @@
 use polymarket_scanner::json_db::JsonDatabase;
 use polymarket_scanner::storage::Storage;
 use polymarket_scanner::types::Market;
-use std::collections::HashMap;
 use std::path::PathBuf;
 use tokio::fs;
 
@@ -31,6 +30,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             end_date: None,
             active: Some(true),
             closed: Some(false),
+            ..Default::default()
         });
     }
 
@@ -0,0 +1,39 @@
+use polymarket_scanner::{JsonDatabase, MarketScanner, PolymarketClient, Storage};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // 初始化日志
+    tracing_subscriber::fmt::init();
+
+    // 要关注的市场 condition_id，通过命令行参数传入
+    let condition_ids: Vec<String> = std::env::args().skip(1).collect();
+    if condition_ids.is_empty() {
+        eprintln!("用法: cargo run --example watch_markets -- <condition_id> [condition_id...]");
+        return Ok(());
+    }
+
+    let client = PolymarketClient::new()?;
+    let db = JsonDatabase::new("./data");
+    db.init().await?;
+
+    let scanner = MarketScanner::with_database(client, Arc::new(db));
+
+    // 只有被关注的市场发生变化时才会触发回调
+    for condition_id in condition_ids {
+        println!("关注市场: {}", condition_id);
+        scanner.watch(condition_id, |event| {
+            println!(
+                "🔔 [{}] {} - {:?}",
+                event.event_type.name(),
+                event.market.question,
+                event.market.outcome_prices
+            );
+        });
+    }
+
+    scanner.start_scanning(Duration::from_secs(15)).await?;
+
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use crate::error::{Result, ScannerError};
+use crate::types::{CrossDirection, EventType, MarketEvent};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+use tracing::debug;
+
+/// 告警推送目标的统一接口
+///
+/// 扫描器对每个事件依次询问已注册的通知器，匹配的事件在独立任务中推送，
+/// 一个通知器失败不会影响其他通知器
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// 推送事件
+    async fn notify(&self, event: &MarketEvent) -> Result<()>;
+
+    /// 是否关心该类型的事件
+    fn wants(&self, event_type: &EventType) -> bool;
+
+    /// 是否推送该事件，默认只按事件类型过滤，可覆盖以加入其他条件（如成交量）
+    fn accepts(&self, event: &MarketEvent) -> bool {
+        self.wants(&event.event_type)
+    }
+
+    /// 通知器名称，用于日志
+    fn name(&self) -> &str;
+}
+
+/// Slack 单个 section 最多允许 10 个 field
+const SLACK_MAX_FIELDS: usize = 10;
+/// Slack header 文本最大长度
+const SLACK_MAX_HEADER_LEN: usize = 150;
+
+/// 通过 Slack incoming webhook 推送市场事件
+pub struct SlackNotifier {
+    client: Client,
+    webhook_url: String,
+    /// 需要推送的事件类型名称，空表示全部
+    event_types: Vec<String>,
+    /// 成交量低于该值的市场不推送
+    min_volume: f64,
+}
+
+impl SlackNotifier {
+    pub fn new(webhook_url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            webhook_url: webhook_url.into(),
+            event_types: Vec::new(),
+            min_volume: 0.0,
+        }
+    }
+
+    /// 只推送指定类型的事件（如 `["NewMarket", "PriceChange"]`）
+    pub fn with_event_types<I, S>(mut self, event_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.event_types = event_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 只推送成交量不低于 `min_volume` 的市场
+    pub fn with_min_volume(mut self, min_volume: f64) -> Self {
+        self.min_volume = min_volume;
+        self
+    }
+
+    /// 将事件格式化为 Slack Block Kit 消息
+    pub fn build_message(&self, event: &MarketEvent) -> Value {
+        let market = &event.market;
+
+        let mut header: String = market.question.chars().take(SLACK_MAX_HEADER_LEN).collect();
+        if header.is_empty() {
+            header = market.condition_id.clone();
+        }
+
+        let fields: Vec<Value> = match (market.outcome_list(), market.prices()) {
+            (Some(outcomes), Some(prices)) => {
+                let change_of = |outcome: &String| event.changes.iter().find(|c| &c.outcome == outcome);
+                let mut rows: Vec<(&String, f64)> = outcomes.iter().zip(prices).collect();
+                // 多结果市场（如 10 个以上候选人）放不下时，优先展示变化最大的结果，其余按价格从高到低
+                if rows.len() > SLACK_MAX_FIELDS {
+                    let rank = |(outcome, price): &(&String, f64)| {
+                        let delta = change_of(outcome).map_or(-1.0, |c| c.delta().abs());
+                        (delta, *price)
+                    };
+                    rows.sort_by(|a, b| rank(b).partial_cmp(&rank(a)).unwrap_or(Ordering::Equal));
+                }
+                rows.into_iter()
+                    .take(SLACK_MAX_FIELDS)
+                    .map(|(outcome, price)| {
+                        let text = match change_of(outcome) {
+                            Some(change) => {
+                                format!("*{}*\n{:.4} → {:.4}", outcome, change.old, change.new)
+                            }
+                            None => format!("*{}*\n{:.4}", outcome, price),
+                        };
+                        json!({ "type": "mrkdwn", "text": text })
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        let mut context = vec![format!(
+            "{} {}",
+            event_emoji(&event.event_type),
+            event_label(event)
+        )];
+        if let Some(volume) = market.volume_value() {
+            context.push(format!("Volume: ${:.0}", volume));
+        }
+        if let Some(liquidity) = market.liquidity.as_deref() {
+            context.push(format!("Liquidity: ${}", liquidity));
+        }
+        context.push(event.timestamp.to_rfc3339());
+
+        let mut blocks = vec![json!({
+            "type": "header",
+            "text": { "type": "plain_text", "text": header }
+        })];
+        if !fields.is_empty() {
+            blocks.push(json!({ "type": "section", "fields": fields }));
+        }
+        blocks.push(json!({
+            "type": "context",
+            "elements": [{ "type": "mrkdwn", "text": context.join(" | ") }]
+        }));
+
+        json!({
+            "text": format!("{}: {}", event_label(event), market.question),
+            "blocks": blocks,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    /// 推送事件到 Slack
+    async fn notify(&self, event: &MarketEvent) -> Result<()> {
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .json(&self.build_message(event))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(ScannerError::InvalidResponse(format!(
+                "Slack webhook HTTP {}: {}",
+                status, text
+            )));
+        }
+
+        debug!("已推送 Slack 通知: {}", event.market.question);
+        Ok(())
+    }
+
+    fn wants(&self, event_type: &EventType) -> bool {
+        self.event_types.is_empty() || self.event_types.iter().any(|t| t == event_type.name())
+    }
+
+    fn accepts(&self, event: &MarketEvent) -> bool {
+        self.wants(&event.event_type)
+            && event.market.volume_value().unwrap_or(0.0) >= self.min_volume
+    }
+
+    fn name(&self) -> &str {
+        "slack"
+    }
+}
+
+fn event_emoji(event_type: &EventType) -> &'static str {
+    match event_type {
+        EventType::NewMarket => "📊",
+        EventType::PriceChange => "💹",
+        EventType::VolumeUpdate => "📈",
+        EventType::MarketClosed => "🔒",
+        EventType::MarketDelisted => "🗑️",
+        EventType::MarketStale => "💤",
+        EventType::SpreadChange { .. } => "↔️",
+        EventType::HighActivity { .. } => "🔥",
+        EventType::MalformedUpdate { .. } => "⚠️",
+        EventType::ThresholdCrossed { .. } => "🎯",
+    }
+}
+
+fn event_label(event: &MarketEvent) -> String {
+    match &event.event_type {
+        EventType::NewMarket => "新市场上线".to_string(),
+        EventType::PriceChange => "价格变化".to_string(),
+        EventType::VolumeUpdate => "成交量更新".to_string(),
+        EventType::MarketClosed => "市场关闭".to_string(),
+        EventType::MarketDelisted => "市场下架".to_string(),
+        EventType::MarketStale => "市场长时间无变化".to_string(),
+        EventType::SpreadChange { old, new } => format!("价差变化 {:.4} → {:.4}", old, new),
+        EventType::HighActivity { score } => format!("交易活跃 (活跃度 {:.2})", score),
+        EventType::MalformedUpdate { reason } => format!("数据异常: {}", reason),
+        EventType::ThresholdCrossed {
+            outcome_index,
+            threshold,
+            direction,
+            price,
+        } => {
+            let verb = match direction {
+                CrossDirection::Above => "升破",
+                CrossDirection::Below => "跌破",
+            };
+            // 多结果市场中只有下标很难分辨是哪个候选项，能解析时显示结果名称
+            let outcome = event
+                .market
+                .outcome_list()
+                .and_then(|outcomes| outcomes.get(*outcome_index).cloned())
+                .unwrap_or_else(|| format!("#{}", outcome_index));
+            format!(
+                "结果 {} 价格{} {} (当前 {:.4})",
+                outcome, verb, threshold, price
+            )
+        }
+    }
+}
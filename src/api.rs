@@ -1,51 +1,654 @@
 use crate::error::{Result, ScannerError};
-use crate::types::{Market, PriceHistory};
-use log::{info, debug, warn};
-use reqwest::Client;
+use crate::source::{
+    collect_recent_markets, get_markets_page_with_retry, page_limit, stream_markets,
+    truncate_to_total, MarketSource, RECENT_MARKETS_PAGE_SIZE,
+};
+use crate::types::{Market, OrderBook, PriceHistory, Tag};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream};
+use tracing::{info, debug, instrument, warn};
+use lru::LruCache;
+use parking_lot::Mutex;
+use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
 use serde_json::Value;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 const GAMMA_API_BASE: &str = "https://gamma-api.polymarket.com";
-#[allow(dead_code)]
 const CLOB_API_BASE: &str = "https://clob.polymarket.com";
+/// 单次按 ID 批量查询的最大数量，保证 URL 长度安全（condition_id 约 66 个字符）
+const MAX_IDS_PER_REQUEST: usize = 50;
+/// 默认的响应体大小上限（64 MiB），远大于一页 500 个市场的响应
+const DEFAULT_MAX_BODY_SIZE: usize = 64 * 1024 * 1024;
+/// 默认的 `User-Agent`
+pub const DEFAULT_USER_AGENT: &str = concat!("polymarket_scanner/", env!("CARGO_PKG_VERSION"));
+/// 标识部署的请求头，见 `PolymarketClientBuilder::client_id`
+const CLIENT_ID_HEADER: &str = "x-client-id";
+/// `get_tags` 结果的缓存时间，标签很少变化
+const TAGS_CACHE_TTL: Duration = Duration::from_secs(600);
+/// `/tags` 每页请求的数量
+const TAGS_PAGE_SIZE: u32 = 500;
 
 pub struct PolymarketClient {
     client: Client,
+    debug_dump_dir: Option<PathBuf>,
+    market_cache: Option<MarketCache>,
+    breaker: Option<CircuitBreaker>,
+    max_body_size: usize,
+    /// `get_tags` 的结果和获取时间
+    tags_cache: Mutex<Option<(Vec<Tag>, Instant)>>,
+    gamma_api_base: String,
+    clob_api_base: String,
+}
+
+/// 熔断器状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CircuitState {
+    /// 正常放行请求
+    #[default]
+    Closed,
+    /// 连续失败达到阈值，冷却期内的请求直接失败
+    Open,
+    /// 冷却期已过，下一个请求作为探测请求放行
+    HalfOpen,
+}
+
+/// 连续失败达到阈值后在冷却期内拒绝请求，避免在 API 故障期间反复重试
+struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    /// 熔断打开（或最近一次放行探测请求）的时间
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn state(&self) -> CircuitState {
+        match self.state.lock().opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.cooldown => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+    
+    /// 请求前调用：熔断打开时返回错误；冷却期已过时放行一个探测请求，
+    /// 并重新开始计时，探测完成前的其他请求仍然直接失败
+    fn acquire(&self) -> Result<()> {
+        let mut state = self.state.lock();
+        let Some(opened_at) = state.opened_at else {
+            return Ok(());
+        };
+        let remaining = self.cooldown.saturating_sub(opened_at.elapsed());
+        if remaining.is_zero() {
+            state.opened_at = Some(Instant::now());
+            return Ok(());
+        }
+        Err(ScannerError::InvalidResponse(format!(
+            "circuit open: 连续 {} 次请求失败，{:.0?} 后重试",
+            state.consecutive_failures, remaining
+        )))
+    }
+    
+    fn record_success(&self) {
+        *self.state.lock() = BreakerState::default();
+    }
+    
+    fn record_failure(&self) {
+        let mut state = self.state.lock();
+        state.consecutive_failures += 1;
+        // 探测请求失败时重新打开；否则在连续失败达到阈值时打开
+        if state.opened_at.is_some() || state.consecutive_failures >= self.failure_threshold {
+            state.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+/// `get_market` / `get_market_by_slug` 的 LRU 缓存，条目超过 TTL 后视为过期
+struct MarketCache {
+    entries: Mutex<LruCache<String, (Market, Instant)>>,
+    ttl: Duration,
+}
+
+impl MarketCache {
+    fn get(&self, key: &str) -> Option<Market> {
+        let mut entries = self.entries.lock();
+        let fresh = entries
+            .get(key)
+            .map(|(market, fetched_at)| (fetched_at.elapsed() < self.ttl).then(|| market.clone()));
+        match fresh {
+            Some(Some(market)) => Some(market),
+            Some(None) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+    
+    fn put(&self, key: String, market: Market) {
+        self.entries.lock().put(key, (market, Instant::now()));
+    }
+}
+
+/// `PolymarketClient` 构建器
+#[derive(Debug, Clone)]
+pub struct PolymarketClientBuilder {
+    timeout: Duration,
+    debug_dump_dir: Option<PathBuf>,
+    compression: bool,
+    /// (容量, TTL)
+    market_cache: Option<(usize, Duration)>,
+    /// (连续失败阈值, 冷却时间)，阈值为 0 表示关闭熔断
+    circuit_breaker: (u32, Duration),
+    /// 连接池参数，None 表示使用 reqwest 默认值
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    max_body_size: usize,
+    /// 显式代理地址，None 时使用 `HTTPS_PROXY` / `ALL_PROXY` 等环境变量
+    proxy: Option<String>,
+    /// 代理认证（用户名, 密码）
+    proxy_auth: Option<(String, String)>,
+    accept_invalid_certs: bool,
+    user_agent: String,
+    /// 设置后每个请求带上 `X-Client-Id` 头
+    client_id: Option<String>,
+    gamma_api_base: String,
+    clob_api_base: String,
+}
+
+impl Default for PolymarketClientBuilder {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            debug_dump_dir: None,
+            compression: true,
+            market_cache: None,
+            circuit_breaker: (5, Duration::from_secs(30)),
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            proxy: None,
+            proxy_auth: None,
+            accept_invalid_certs: false,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            client_id: None,
+            gamma_api_base: GAMMA_API_BASE.to_string(),
+            clob_api_base: CLOB_API_BASE.to_string(),
+        }
+    }
+}
+
+impl PolymarketClientBuilder {
+    /// 请求超时时间
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    
+    /// 设置后在解析前将原始响应体写入该目录下的带时间戳文件，仅用于调试
+    pub fn debug_dump_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.debug_dump_dir = Some(dir.into());
+        self
+    }
+    
+    /// 是否请求压缩响应（gzip/brotli/deflate，默认开启）
+    ///
+    /// 开启时发送 `Accept-Encoding` 并自动解压，全量扫描可节省大量流量
+    pub fn compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+    
+    /// 为 `get_market` / `get_market_by_slug` 启用 LRU 缓存（默认关闭）
+    ///
+    /// 最多缓存 `capacity` 个市场，超过 `ttl` 的条目会重新请求。
+    /// 持续扫描需要最新数据，只应在查询服务、关注列表刷新等场景开启
+    pub fn market_cache(mut self, capacity: usize, ttl: Duration) -> Self {
+        self.market_cache = Some((capacity, ttl));
+        self
+    }
+    
+    /// 熔断器参数（默认连续失败 5 次后熔断 30 秒）
+    ///
+    /// 网络错误、5xx 和 429 响应计为失败。熔断期间的请求直接返回
+    /// `InvalidResponse("circuit open ...")`；冷却期结束后放行一个探测请求，
+    /// 成功则恢复，失败则再熔断一个冷却期。`failure_threshold` 为 0 时关闭熔断
+    pub fn circuit_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.circuit_breaker = (failure_threshold, cooldown);
+        self
+    }
+    
+    /// 每个主机保留的最大空闲连接数（reqwest 默认不限制）
+    ///
+    /// 并发请求时应不小于并发数，否则请求结束后多出的连接会被关闭，
+    /// 下一批请求需要重新建立 TLS 连接。建议设为并发数的 1～2 倍
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+    
+    /// 空闲连接的保留时间（reqwest 默认 90 秒）
+    ///
+    /// 应大于扫描间隔，使相邻两轮扫描可以复用连接
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+    
+    /// TCP keep-alive 探测间隔（reqwest 默认关闭）
+    ///
+    /// 开启后可以及早发现被中间设备断开的空闲连接，建议 30～60 秒
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+    
+    /// 响应体大小上限（字节，默认 64 MiB）
+    ///
+    /// 响应体按块读取，累计超过上限时立即停止读取并返回 `InvalidResponse`，
+    /// 避免异常或恶意的超大响应耗尽内存。错误响应的片段同样受此限制
+    pub fn max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = bytes;
+        self
+    }
+    
+    /// 通过代理访问 API，例如 `http://proxy.corp:3128`
+    ///
+    /// 地址中的 `user:pass@` 会作为代理认证发送。`socks5://` / `socks5h://` 地址需要启用
+    /// `socks` feature。不设置时 reqwest 会读取 `HTTPS_PROXY` / `HTTP_PROXY` / `ALL_PROXY`
+    /// （以及 `NO_PROXY`）环境变量；设置后忽略这些环境变量
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+    
+    /// 代理的 Basic 认证，优先于代理地址中的用户名和密码
+    pub fn proxy_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.proxy_auth = Some((username.into(), password.into()));
+        self
+    }
+    
+    /// 不校验服务器 TLS 证书（默认校验）
+    ///
+    /// 仅用于会替换证书的拦截式代理且无法安装其根证书的环境。
+    /// 开启后连接可以被中间人窃听和篡改，构建客户端时会输出警告
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+    
+    /// 所有请求的 `User-Agent`（默认 `polymarket_scanner/{版本}`）
+    ///
+    /// 建议带上部署方的名称或联系方式，例如 `polymarket_scanner/0.2.0 (ops@example.com)`，
+    /// 方便 API 方识别流量来源
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+    
+    /// 在所有请求上附加 `X-Client-Id` 头，用于向 API 方标识具体的部署
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+    
+    /// 替换 Gamma 和 CLOB API 的地址，测试中指向本地的模拟服务
+    #[cfg(test)]
+    pub(crate) fn api_base(mut self, gamma: impl Into<String>, clob: impl Into<String>) -> Self {
+        self.gamma_api_base = gamma.into();
+        self.clob_api_base = clob.into();
+        self
+    }
+    
+    pub fn build(self) -> Result<PolymarketClient> {
+        let mut headers = HeaderMap::new();
+        let user_agent = HeaderValue::from_str(&self.user_agent).map_err(|_| {
+            ScannerError::ConfigError(format!("无效的 User-Agent: {:?}", self.user_agent))
+        })?;
+        headers.insert(USER_AGENT, user_agent);
+        if let Some(client_id) = &self.client_id {
+            let value = HeaderValue::from_str(client_id).map_err(|_| {
+                ScannerError::ConfigError(format!("无效的 X-Client-Id: {:?}", client_id))
+            })?;
+            headers.insert(CLIENT_ID_HEADER, value);
+        }
+        let mut client = Client::builder()
+            .timeout(self.timeout)
+            .default_headers(headers)
+            .gzip(self.compression)
+            .brotli(self.compression)
+            .deflate(self.compression)
+            .tcp_keepalive(self.tcp_keepalive);
+        if let Some(url) = &self.proxy {
+            // 不输出原始错误，其中的地址可能带有代理密码
+            let mut proxy = reqwest::Proxy::all(url.as_str()).map_err(|_| {
+                ScannerError::ConfigError(
+                    "无效的代理地址，应为 http://、https:// 或 socks5:// 开头的 URL".to_string(),
+                )
+            })?;
+            if let Some((username, password)) = &self.proxy_auth {
+                proxy = proxy.basic_auth(username, password);
+            }
+            client = client.proxy(proxy);
+        }
+        if self.accept_invalid_certs {
+            warn!("已关闭 TLS 证书校验，API 连接可能被中间人篡改");
+            client = client.danger_accept_invalid_certs(true);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            client = client.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            client = client.pool_idle_timeout(timeout);
+        }
+        let client = client.build()?;
+        
+        let market_cache = self.market_cache.and_then(|(capacity, ttl)| {
+            NonZeroUsize::new(capacity).map(|capacity| MarketCache {
+                entries: Mutex::new(LruCache::new(capacity)),
+                ttl,
+            })
+        });
+        
+        let (failure_threshold, cooldown) = self.circuit_breaker;
+        let breaker = (failure_threshold > 0).then(|| CircuitBreaker {
+            failure_threshold,
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        });
+        
+        Ok(PolymarketClient {
+            client,
+            debug_dump_dir: self.debug_dump_dir,
+            market_cache,
+            breaker,
+            max_body_size: self.max_body_size,
+            tags_cache: Mutex::new(None),
+            gamma_api_base: self.gamma_api_base,
+            clob_api_base: self.clob_api_base,
+        })
+    }
+}
+
+/// CLOB `/prices-history` 的时间范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceInterval {
+    OneHour,
+    SixHours,
+    OneDay,
+    OneWeek,
+    /// 全部历史
+    Max,
+}
+
+impl PriceInterval {
+    /// API 参数值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriceInterval::OneHour => "1h",
+            PriceInterval::SixHours => "6h",
+            PriceInterval::OneDay => "1d",
+            PriceInterval::OneWeek => "1w",
+            PriceInterval::Max => "max",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PriceHistoryResponse {
+    #[serde(default)]
+    history: Vec<PriceHistory>,
+}
+
+/// Gamma `/markets` 查询的状态过滤条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GammaQuery {
+    /// 对应 `active` 参数，None 表示不限制
+    pub active: Option<bool>,
+    /// 对应 `closed` 参数，None 表示不限制
+    pub closed: Option<bool>,
+}
+
+impl GammaQuery {
+    /// 只查询活跃市场（默认行为）
+    pub fn active() -> Self {
+        Self {
+            active: Some(true),
+            closed: None,
+        }
+    }
+
+    /// 只查询已关闭/已结算的市场，用于回填历史数据
+    pub fn closed() -> Self {
+        Self {
+            active: None,
+            closed: Some(true),
+        }
+    }
+
+    /// 不限制市场状态
+    pub fn all() -> Self {
+        Self {
+            active: None,
+            closed: None,
+        }
+    }
+
+    fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        if let Some(active) = self.active {
+            params.push(("active", active.to_string()));
+        }
+        if let Some(closed) = self.closed {
+            params.push(("closed", closed.to_string()));
+        }
+        params
+    }
+}
+
+impl Default for GammaQuery {
+    fn default() -> Self {
+        Self::active()
+    }
+}
+
+/// `StreamOptions::inter_batch_delay` 的默认值
+pub const DEFAULT_INTER_BATCH_DELAY: Duration = Duration::from_millis(100);
+
+/// 流式分页获取选项
+#[derive(Debug, Clone)]
+pub struct StreamOptions {
+    /// 每批获取的市场数量
+    pub batch_size: u32,
+    /// 市场状态过滤条件
+    pub query: GammaQuery,
+    /// 起始偏移量，用于从断点恢复
+    pub start_offset: u32,
+    /// 单页请求失败后的最大重试次数（指数退避）
+    pub max_retries: u32,
+    /// 累计获取到该数量的市场后停止，最后一批会被截断；None 表示获取全部
+    pub max_total: Option<usize>,
+    /// 两页请求之间的固定等待时间，0 表示不等待
+    ///
+    /// 用于粗略地控制请求频率，避免触发 API 速率限制。与重试退避互相独立：
+    /// 单页重试的等待不计入该延迟，重试成功后仍会等待该延迟再请求下一页；
+    /// 熔断器打开时请求直接失败，不会因为该延迟而推迟
+    pub inter_batch_delay: Duration,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 100,
+            query: GammaQuery::default(),
+            start_offset: 0,
+            max_retries: 0,
+            max_total: None,
+            inter_batch_delay: DEFAULT_INTER_BATCH_DELAY,
+        }
+    }
+}
+
+/// 一页市场列表
+///
+/// 逐个解析市场，无法解析的市场被跳过并计数，不影响同一页的其他市场
+#[derive(Debug, Clone, Default)]
+pub struct MarketPage {
+    /// 解析成功的市场
+    pub markets: Vec<Market>,
+    /// 因格式错误被跳过的市场数
+    pub skipped: usize,
+}
+
+impl MarketPage {
+    /// API 返回的原始条目数（含跳过的），用于判断是否为最后一页
+    pub fn received(&self) -> usize {
+        self.markets.len() + self.skipped
+    }
 }
 
 impl PolymarketClient {
     pub fn new() -> Result<Self> {
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
+        Self::builder().build()
+    }
+    
+    pub fn builder() -> PolymarketClientBuilder {
+        PolymarketClientBuilder::default()
+    }
+    
+    /// 当前熔断器状态，未启用熔断时始终为 `Closed`
+    pub fn circuit_state(&self) -> CircuitState {
+        self.breaker
+            .as_ref()
+            .map_or(CircuitState::Closed, CircuitBreaker::state)
+    }
+    
+    /// 经过熔断器发送请求，401/403 响应返回 `Forbidden`
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let response = match &self.breaker {
+            None => request.send().await?,
+            Some(breaker) => {
+                breaker.acquire()?;
+                match request.send().await {
+                    Ok(response) => {
+                        let status = response.status();
+                        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+                            breaker.record_failure();
+                        } else {
+                            breaker.record_success();
+                        }
+                        response
+                    }
+                    Err(e) => {
+                        breaker.record_failure();
+                        return Err(e.into());
+                    }
+                }
+            }
+        };
         
-        Ok(Self { client })
+        // 401/403 在所有接口上都意味着被拒绝访问（通常是地区限制），单独报告而不是当作普通的无效响应
+        let status = response.status();
+        if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            return Err(ScannerError::Forbidden(format!("HTTP {} {}", status, response.url().path())));
+        }
+        Ok(response)
+    }
+    
+    /// 读取响应体，开启调试时先写入 dump 目录
+    ///
+    /// 响应不是 JSON（如 Cloudflare 返回的 HTML 错误页）或响应体为空时返回
+    /// `InvalidResponse`，包含状态码和截断的响应体片段
+    async fn read_body(&self, response: Response, label: &str) -> Result<String> {
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = self.read_text(response).await?;
+        if let Some(dir) = &self.debug_dump_dir {
+            self.dump_body(dir, label, &body).await;
+        }
+        check_json_body(status, content_type.as_deref(), &body)?;
+        Ok(body)
+    }
+    
+    /// 按块读取响应体，超过 `max_body_size` 时返回 `InvalidResponse`
+    ///
+    /// 先检查 Content-Length，声明的长度已超限时不读取响应体
+    async fn read_text(&self, mut response: Response) -> Result<String> {
+        let limit = self.max_body_size;
+        let too_large = |size: u64| {
+            ScannerError::InvalidResponse(format!(
+                "响应体超过上限 {} 字节 (已读取/声明 {} 字节)",
+                limit, size
+            ))
+        };
+        if let Some(length) = response.content_length() {
+            if length > limit as u64 {
+                return Err(too_large(length));
+            }
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > limit {
+                return Err(too_large((body.len() + chunk.len()) as u64));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+    
+    async fn dump_body(&self, dir: &Path, label: &str, body: &str) {
+        let filename = format!(
+            "{}_{}.json",
+            label,
+            chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+        );
+        let path = dir.join(filename);
+        let result = async {
+            tokio::fs::create_dir_all(dir).await?;
+            tokio::fs::write(&path, body).await
+        }
+        .await;
+        match result {
+            Ok(()) => debug!("原始响应已写入 {}", path.display()),
+            Err(e) => warn!("写入原始响应失败 {}: {}", path.display(), e),
+        }
     }
     
     /// 获取活跃市场列表
     pub async fn get_markets(&self, limit: Option<u32>) -> Result<Vec<Market>> {
         let limit = limit.unwrap_or(100);
-        let url = format!("{}/markets", GAMMA_API_BASE);
+        let url = format!("{}/markets", self.gamma_api_base);
         
         debug!("请求市场列表: {}", url);
         
-        let response = self.client
+        let request = self.client
             .get(&url)
-            .query(&[("limit", limit.to_string()), ("active", "true".to_string())])
-            .send()
-            .await?;
+            .query(&[("limit", limit.to_string()), ("active", "true".to_string())]);
+        let response = self.send(request).await?;
         
         let markets: Vec<Market> = if !response.status().is_success() {
             let status = response.status();
-            let text = response.text().await.unwrap_or_default();
+            let text = body_snippet(&self.read_text(response).await.unwrap_or_default());
             warn!("API 请求失败 [{}]: {}", status, text);
             return Err(ScannerError::InvalidResponse(format!("HTTP {}: {}", status, text)));
         } else {
-            let markets = response.json().await.unwrap_or_else(|e| {
-                warn!("JSON 解析错误: {}", e);
-                Vec::new()
-            });
-            debug!("response: {:?}", markets);
-            markets
+            let body = self.read_body(response, "markets").await?;
+            parse_markets(&body)?.markets
         };
         
         debug!("成功获取 {} 个市场", markets.len());
@@ -55,34 +658,83 @@ impl PolymarketClient {
     
     /// 获取市场列表（支持分页）
     pub async fn get_markets_paginated(&self, limit: u32, offset: u32) -> Result<Vec<Market>> {
-        let url = format!("{}/markets", GAMMA_API_BASE);
+        self.get_markets_paginated_with(&GammaQuery::active(), limit, offset).await
+    }
+    
+    /// 按过滤条件分页获取市场列表
+    pub async fn get_markets_paginated_with(
+        &self,
+        query: &GammaQuery,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Market>> {
+        Ok(self.get_markets_page(query, limit, offset).await?.markets)
+    }
+    
+    /// 按过滤条件分页获取市场列表，同时返回因格式错误被跳过的市场数
+    #[instrument(level = "debug", skip(self, query))]
+    pub async fn get_markets_page(
+        &self,
+        query: &GammaQuery,
+        limit: u32,
+        offset: u32,
+    ) -> Result<MarketPage> {
+        debug!("请求市场列表（分页）: query={:?}", query);
         
-        debug!("请求市场列表（分页）: limit={}, offset={}", limit, offset);
+        let mut params = vec![
+            ("limit", limit.to_string()),
+            ("offset", offset.to_string()),
+        ];
+        params.extend(query.to_params());
+        self.request_markets_page(&params, "markets_page").await
+    }
+    
+    /// 按查询参数请求一页 `/markets`，`label` 用于调试时的 dump 文件名
+    async fn request_markets_page(
+        &self,
+        params: &[(&str, String)],
+        label: &str,
+    ) -> Result<MarketPage> {
+        let url = format!("{}/markets", self.gamma_api_base);
         
-        let response = self.client
+        let request = self.client
             .get(&url)
-            .query(&[
-                ("limit", limit.to_string()),
-                ("offset", offset.to_string()),
-                ("active", "true".to_string())
-            ])
-            .send()
-            .await?;
+            .query(params);
+        let response = self.send(request).await?;
         
-        let markets: Vec<Market> = if !response.status().is_success() {
+        let page = if !response.status().is_success() {
             let status = response.status();
-            let text = response.text().await.unwrap_or_default();
+            let text = body_snippet(&self.read_text(response).await.unwrap_or_default());
             warn!("API 请求失败 [{}]: {}", status, text);
             return Err(ScannerError::InvalidResponse(format!("HTTP {}: {}", status, text)));
         } else {
-            response.json().await.unwrap_or_else(|e| {
-                warn!("JSON 解析错误: {}", e);
-                Vec::new()
-            })
+            let body = self.read_body(response, label).await?;
+            parse_markets(&body)?
         };
         
-        debug!("成功获取 {} 个市场", markets.len());
-        Ok(markets)
+        debug!("成功获取 {} 个市场，跳过 {} 个", page.markets.len(), page.skipped);
+        Ok(page)
+    }
+    
+    /// 获取 `since` 之后创建的活跃市场，从新到旧排列
+    ///
+    /// 按 `order=createdAt&ascending=false` 分页请求，遇到早于 `since` 的市场就停止，
+    /// 没有新市场时只需要一次请求。无法确认 API 按创建时间倒序返回（忽略了排序参数、
+    /// 市场缺少 `createdAt`，或第一页不足两个市场）时返回 None，调用方应退回全量扫描，
+    /// 见 `collect_recent_markets`
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_recent_markets(&self, since: DateTime<Utc>) -> Result<Option<Vec<Market>>> {
+        collect_recent_markets(since, RECENT_MARKETS_PAGE_SIZE, |offset| async move {
+            let mut params = vec![
+                ("limit", RECENT_MARKETS_PAGE_SIZE.to_string()),
+                ("offset", offset.to_string()),
+                ("order", "createdAt".to_string()),
+                ("ascending", "false".to_string()),
+            ];
+            params.extend(GammaQuery::active().to_params());
+            self.request_markets_page(&params, "recent_markets").await
+        })
+        .await
     }
     
     /// 获取所有市场（流式处理，使用回调函数）
@@ -106,42 +758,91 @@ impl PolymarketClient {
     pub async fn get_all_markets_stream<F, Fut>(
         &self,
         batch_size: u32,
-        mut callback: F,
+        callback: F,
     ) -> Result<usize>
     where
         F: FnMut(Vec<Market>) -> Fut,
         Fut: std::future::Future<Output = Result<()>>,
     {
-        info!("开始流式获取所有市场数据，批次大小: {}", batch_size);
-        let mut offset = 0;
-        let mut total_count = 0;
-        
-        loop {
-            let markets = self.get_markets_paginated(batch_size, offset).await?;
-            let count = markets.len();
-            
-            // if count == 0 {
-            //     break;
-            // }
-            
-            info!("获取到第 {} - {} 个市场", offset + 1, offset + count as u32);
-            total_count += count;
-            
-            // 调用回调函数处理当前批次，处理完后这批数据就可以被释放
-            callback(markets).await?;
-            
-            if count < batch_size as usize {
-                break; // 最后一页
+        let options = StreamOptions {
+            batch_size,
+            ..Default::default()
+        };
+        self.get_all_markets_stream_with(&options, callback).await
+    }
+    
+    /// 按选项流式获取所有市场，可通过 `options.query` 获取已关闭的市场
+    pub async fn get_all_markets_stream_with<F, Fut>(
+        &self,
+        options: &StreamOptions,
+        callback: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(Vec<Market>) -> Fut,
+        Fut: std::future::Future<Output = Result<()>>,
+    {
+        stream_markets(self, options, callback).await
+    }
+    
+    /// 以 `Stream` 的形式分页获取所有活跃市场，每个元素是一批市场
+    ///
+    /// 与 `get_all_markets_stream` 使用相同的分页逻辑，便于与 `filter`、`for_each`、
+    /// `buffered` 等组合。出错后 Stream 结束
+    ///
+    /// # 示例
+    /// ```ignore
+    /// use futures::TryStreamExt;
+    ///
+    /// let mut batches = std::pin::pin!(client.markets_stream(100));
+    /// while let Some(batch) = batches.try_next().await? {
+    ///     db.save_markets(batch).await?;
+    /// }
+    /// ```
+    pub fn markets_stream(&self, batch_size: u32) -> impl Stream<Item = Result<Vec<Market>>> + '_ {
+        self.markets_stream_with(StreamOptions {
+            batch_size,
+            ..Default::default()
+        })
+    }
+    
+    /// 按选项以 `Stream` 的形式分页获取所有市场
+    pub fn markets_stream_with(
+        &self,
+        options: StreamOptions,
+    ) -> impl Stream<Item = Result<Vec<Market>>> + '_ {
+        // 状态: (下一页偏移量, 已获取的市场数, 是否已结束)
+        let state = (options.start_offset, 0, false);
+        stream::try_unfold(state, move |(offset, total, done)| {
+            let options = options.clone();
+            async move {
+                let limit = match page_limit(&options, total) {
+                    Some(limit) if !done => limit,
+                    _ => return Ok(None),
+                };
+                if offset != options.start_offset && !options.inter_batch_delay.is_zero() {
+                    tokio::time::sleep(options.inter_batch_delay).await;
+                }
+                let page = get_markets_page_with_retry(
+                    self,
+                    &options.query,
+                    limit,
+                    offset,
+                    options.max_retries,
+                )
+                .await?;
+                let received = page.received();
+                if received == 0 {
+                    return Ok(None);
+                }
+                info!("获取到第 {} - {} 个市场", offset + 1, offset + received as u32);
+                
+                let mut markets = page.markets;
+                truncate_to_total(&mut markets, &options, total);
+                let total = total + markets.len();
+                let last_page = received < limit as usize;
+                Ok(Some((markets, (offset + options.batch_size, total, last_page))))
             }
-            
-            offset += batch_size;
-            
-            // 添加延迟避免触发速率限制
-            // tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-        }
-        
-        info!("总共获取 {} 个市场", total_count);
-        Ok(total_count)
+        })
     }
     
     /// 获取所有市场（一次性加载到内存）
@@ -154,15 +855,15 @@ impl PolymarketClient {
         let mut offset = 0;
         
         loop {
-            let markets = self.get_markets_paginated(batch_size, offset).await?;
-            let count = markets.len();
+            let page = self.get_markets_page(&GammaQuery::active(), batch_size, offset).await?;
+            let count = page.received();
             
             if count == 0 {
                 break;
             }
             
             info!("获取到第 {} - {} 个市场", offset + 1, offset + count as u32);
-            all_markets.extend(markets);
+            all_markets.extend(page.markets);
             
             if count < batch_size as usize {
                 break; // 最后一页
@@ -178,17 +879,36 @@ impl PolymarketClient {
         Ok(all_markets)
     }
     
-    /// 获取市场详情
-    #[allow(dead_code)]
+    /// 获取市场详情，启用缓存时优先返回未过期的缓存
     pub async fn get_market(&self, condition_id: &str) -> Result<Market> {
-        let url = format!("{}/markets/{}", CLOB_API_BASE, condition_id);
+        let Some(cache) = &self.market_cache else {
+            return self.fetch_market(condition_id).await;
+        };
+        if let Some(market) = cache.get(condition_id) {
+            debug!("市场缓存命中: {}", condition_id);
+            return Ok(market);
+        }
+        let market = self.fetch_market(condition_id).await?;
+        cache.put(condition_id.to_string(), market.clone());
+        Ok(market)
+    }
+    
+    /// 清空市场缓存
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.market_cache {
+            cache.entries.lock().clear();
+        }
+    }
+    
+    #[instrument(level = "debug", skip(self))]
+    async fn fetch_market(&self, condition_id: &str) -> Result<Market> {
+        let url = format!("{}/markets/{}", self.clob_api_base, condition_id);
         
         debug!("请求市场详情: {}", url);
         
-        let response = self.client
-            .get(&url)
-            .send()
-            .await?;
+        let request = self.client
+            .get(&url);
+        let response = self.send(request).await?;
         
         if !response.status().is_success() {
             return Err(ScannerError::InvalidResponse(
@@ -196,36 +916,209 @@ impl PolymarketClient {
             ));
         }
         
-        let market: Market = response.json().await?;
+        parse_market(&self.read_body(response, "market").await?)
+    }
+    
+    /// 根据 URL slug 获取市场（如 `will-x-happen`），启用缓存时优先返回未过期的缓存
+    pub async fn get_market_by_slug(&self, slug: &str) -> Result<Market> {
+        let Some(cache) = &self.market_cache else {
+            return self.fetch_market_by_slug(slug).await;
+        };
+        let key = format!("slug:{}", slug);
+        if let Some(market) = cache.get(&key) {
+            debug!("市场缓存命中: {}", key);
+            return Ok(market);
+        }
+        let market = self.fetch_market_by_slug(slug).await?;
+        cache.put(key, market.clone());
         Ok(market)
     }
     
-    /// 获取价格历史
-    #[allow(dead_code)]
+    #[instrument(level = "debug", skip(self))]
+    async fn fetch_market_by_slug(&self, slug: &str) -> Result<Market> {
+        let url = format!("{}/markets", self.gamma_api_base);
+        
+        debug!("按 slug 请求市场: {}", slug);
+        
+        let request = self.client
+            .get(&url)
+            .query(&[("slug", slug)]);
+        let response = self.send(request).await?;
+        
+        if !response.status().is_success() {
+            return Err(ScannerError::InvalidResponse(
+                format!("HTTP {}", response.status())
+            ));
+        }
+        
+        let body = self.read_body(response, "market_by_slug").await?;
+        parse_markets(&body)?
+            .markets
+            .into_iter()
+            .next()
+            .ok_or_else(|| ScannerError::NotFound(format!("slug 为 {} 的市场", slug)))
+    }
+    
+    /// 获取市场的结算结果（获胜结果的下标），尚未结算时返回 None
+    ///
+    /// 按 Gamma API 的 `umaResolutionStatus` 和结算后的价格判断（见 `Market::winning_outcome`），
+    /// 不经过市场缓存
+    pub async fn get_market_resolution(&self, condition_id: &str) -> Result<Option<usize>> {
+        let market = self
+            .get_markets_by_ids(&[condition_id.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ScannerError::NotFound(format!("市场 {}", condition_id)))?;
+        Ok(market.resolved_outcome)
+    }
+    
+    /// 按 condition_id 批量获取市场，只返回匹配到的市场
+    ///
+    /// 每个请求最多携带 `MAX_IDS_PER_REQUEST` 个 ID，超出时拆分为多个请求
+    pub async fn get_markets_by_ids(&self, ids: &[String]) -> Result<Vec<Market>> {
+        let url = format!("{}/markets", self.gamma_api_base);
+        let mut markets = Vec::with_capacity(ids.len());
+        
+        for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+            debug!("批量请求 {} 个市场", chunk.len());
+            
+            let mut params = vec![("limit", chunk.len().to_string())];
+            params.extend(chunk.iter().map(|id| ("condition_ids", id.clone())));
+            
+            let request = self.client
+                .get(&url)
+                .query(&params);
+            let response = self.send(request).await?;
+            
+            if !response.status().is_success() {
+                return Err(ScannerError::InvalidResponse(
+                    format!("HTTP {}", response.status())
+                ));
+            }
+            
+            let body = self.read_body(response, "markets_by_ids").await?;
+            markets.extend(parse_markets(&body)?.markets);
+        }
+        
+        debug!("批量获取到 {}/{} 个市场", markets.len(), ids.len());
+        Ok(markets)
+    }
+    
+    /// 获取 token 在指定时间区间内的价格历史（CLOB `/prices-history`）
+    ///
+    /// `token_id` 为 CLOB token id（见 `Market::token_ids`），`fidelity` 为数据点间隔（分钟），
+    /// None 时使用 API 默认值
     pub async fn get_price_history(
         &self,
-        market_id: &str,
-        start_ts: Option<i64>,
-        end_ts: Option<i64>,
+        token_id: &str,
+        interval: PriceInterval,
+        fidelity: Option<u32>,
+    ) -> Result<Vec<PriceHistory>> {
+        let mut params = vec![
+            ("market", token_id.to_string()),
+            ("interval", interval.as_str().to_string()),
+        ];
+        if let Some(fidelity) = fidelity {
+            params.push(("fidelity", fidelity.to_string()));
+        }
+        self.fetch_price_history(&params).await
+    }
+    
+    /// 获取 token 在 `[start_ts, end_ts]`（Unix 秒）内的价格历史，用于回填
+    pub async fn get_price_history_range(
+        &self,
+        token_id: &str,
+        start_ts: i64,
+        end_ts: i64,
+        fidelity: Option<u32>,
     ) -> Result<Vec<PriceHistory>> {
-        let url = format!("{}/prices-history", GAMMA_API_BASE);
+        let mut params = vec![
+            ("market", token_id.to_string()),
+            ("startTs", start_ts.to_string()),
+            ("endTs", end_ts.to_string()),
+        ];
+        if let Some(fidelity) = fidelity {
+            params.push(("fidelity", fidelity.to_string()));
+        }
+        self.fetch_price_history(&params).await
+    }
+    
+    async fn fetch_price_history(&self, params: &[(&str, String)]) -> Result<Vec<PriceHistory>> {
+        let url = format!("{}/prices-history", self.clob_api_base);
         
-        let mut query_params = vec![("market", market_id.to_string())];
+        debug!("请求价格历史: {} {:?}", url, params);
         
-        if let Some(start) = start_ts {
-            query_params.push(("startTs", start.to_string()));
+        let request = self.client
+            .get(&url)
+            .query(params);
+        let response = self.send(request).await?;
+        
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = body_snippet(&self.read_text(response).await.unwrap_or_default());
+            warn!("价格历史请求失败 [{}]: {}", status, text);
+            return Err(ScannerError::InvalidResponse(
+                format!("prices-history HTTP {}: {}", status, text)
+            ));
         }
-        if let Some(end) = end_ts {
-            query_params.push(("endTs", end.to_string()));
+        
+        let body: PriceHistoryResponse = serde_json::from_str(&self.read_body(response, "price_history").await?)?;
+        Ok(body.history)
+    }
+    
+    /// 获取 Gamma 提供的全部标签（分类），结果缓存 10 分钟
+    ///
+    /// 可用于展示分类列表，或在按标签过滤之前用 `Tag::matches` 校验标签名称
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_tags(&self) -> Result<Vec<Tag>> {
+        if let Some((tags, fetched_at)) = &*self.tags_cache.lock() {
+            if fetched_at.elapsed() < TAGS_CACHE_TTL {
+                return Ok(tags.clone());
+            }
         }
         
-        debug!("请求价格历史: {}", url);
+        let url = format!("{}/tags", self.gamma_api_base);
+        let mut tags = Vec::new();
+        let mut offset = 0;
+        loop {
+            debug!("请求标签列表: {} offset={}", url, offset);
+            let request = self.client
+                .get(&url)
+                .query(&[("limit", TAGS_PAGE_SIZE), ("offset", offset)]);
+            let response = self.send(request).await?;
+            
+            if !response.status().is_success() {
+                return Err(ScannerError::InvalidResponse(
+                    format!("HTTP {}", response.status())
+                ));
+            }
+            
+            let page: Vec<Tag> = serde_json::from_str(&self.read_body(response, "tags").await?)?;
+            let received = page.len() as u32;
+            tags.extend(page);
+            if received < TAGS_PAGE_SIZE {
+                break;
+            }
+            offset += TAGS_PAGE_SIZE;
+        }
+        
+        info!("获取到 {} 个标签", tags.len());
+        *self.tags_cache.lock() = Some((tags.clone(), Instant::now()));
+        Ok(tags)
+    }
+    
+    /// 获取指定 token 的订单簿
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_order_book(&self, token_id: &str) -> Result<OrderBook> {
+        let url = format!("{}/book", self.clob_api_base);
         
-        let response = self.client
+        debug!("请求订单簿: {} token_id={}", url, token_id);
+        
+        let request = self.client
             .get(&url)
-            .query(&query_params)
-            .send()
-            .await?;
+            .query(&[("token_id", token_id)]);
+        let response = self.send(request).await?;
         
         if !response.status().is_success() {
             return Err(ScannerError::InvalidResponse(
@@ -233,21 +1126,20 @@ impl PolymarketClient {
             ));
         }
         
-        let history: Vec<PriceHistory> = response.json().await?;
-        Ok(history)
+        let book: OrderBook = serde_json::from_str(&self.read_body(response, "order_book").await?)?;
+        Ok(book)
     }
     
     /// 获取市场统计信息
     #[allow(dead_code)]
     pub async fn get_market_stats(&self, condition_id: &str) -> Result<Value> {
-        let url = format!("{}/markets/{}/stats", CLOB_API_BASE, condition_id);
+        let url = format!("{}/markets/{}/stats", self.clob_api_base, condition_id);
         
         debug!("请求市场统计: {}", url);
         
-        let response = self.client
-            .get(&url)
-            .send()
-            .await?;
+        let request = self.client
+            .get(&url);
+        let response = self.send(request).await?;
         
         if !response.status().is_success() {
             return Err(ScannerError::InvalidResponse(
@@ -255,8 +1147,347 @@ impl PolymarketClient {
             ));
         }
         
-        let stats: Value = response.json().await?;
+        let stats: Value = serde_json::from_str(&self.read_body(response, "market_stats").await?)?;
         Ok(stats)
     }
 }
 
+#[async_trait]
+impl MarketSource for PolymarketClient {
+    async fn get_markets_page(
+        &self,
+        query: &GammaQuery,
+        limit: u32,
+        offset: u32,
+    ) -> Result<MarketPage> {
+        PolymarketClient::get_markets_page(self, query, limit, offset).await
+    }
+    
+    async fn get_markets_by_ids(&self, ids: &[String]) -> Result<Vec<Market>> {
+        PolymarketClient::get_markets_by_ids(self, ids).await
+    }
+    
+    async fn get_order_book(&self, token_id: &str) -> Result<OrderBook> {
+        PolymarketClient::get_order_book(self, token_id).await
+    }
+    
+    async fn get_recent_markets(&self, since: DateTime<Utc>) -> Result<Option<Vec<Market>>> {
+        PolymarketClient::get_recent_markets(self, since).await
+    }
+    
+    fn circuit_state(&self) -> CircuitState {
+        PolymarketClient::circuit_state(self)
+    }
+}
+
+/// 错误信息中保留的响应体最大字符数
+const BODY_SNIPPET_CHARS: usize = 200;
+
+/// 截断响应体，用于错误信息和日志
+fn body_snippet(body: &str) -> String {
+    let body = body.trim();
+    match body.char_indices().nth(BODY_SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+/// 检查响应体是否为 JSON
+///
+/// 未返回 Content-Type 时只检查响应体是否为空，避免误判不规范的服务端
+fn check_json_body(status: reqwest::StatusCode, content_type: Option<&str>, body: &str) -> Result<()> {
+    if body.trim().is_empty() {
+        return Err(ScannerError::InvalidResponse(format!("HTTP {}: 响应体为空", status)));
+    }
+    if let Some(content_type) = content_type {
+        if !content_type.to_ascii_lowercase().contains("json") {
+            warn!("API 返回非 JSON 响应 [{}] ({}): {}", status, content_type, body_snippet(body));
+            return Err(ScannerError::InvalidResponse(format!(
+                "HTTP {}: 非 JSON 响应 ({}): {}",
+                status,
+                content_type,
+                body_snippet(body)
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 解析市场列表响应体
+///
+/// 响应体不是 JSON 数组时返回错误而不是空列表，避免"没有市场"和"响应格式变化"无法区分。
+/// 数组中的市场逐个解析，无法解析的市场记录日志（含位置、conditionId 和 question）后跳过
+fn parse_markets(body: &str) -> Result<MarketPage> {
+    let values: Vec<Value> = serde_json::from_str(body).map_err(|e| {
+        warn!("市场列表 JSON 解析错误: {}", e);
+        debug!("无法解析的响应体长度: {} 字节", body.len());
+        ScannerError::JsonError(e)
+    })?;
+
+    let mut page = MarketPage {
+        markets: Vec::with_capacity(values.len()),
+        skipped: 0,
+    };
+    for (index, value) in values.iter().enumerate() {
+        match Market::deserialize(value) {
+            Ok(mut market) => {
+                market.fill_resolution();
+                page.markets.push(market);
+            }
+            Err(e) => {
+                warn!("跳过无法解析的市场: {}", market_parse_error(value, Some(index), e));
+                page.skipped += 1;
+            }
+        }
+    }
+    if page.skipped > 0 {
+        warn!("本页 {} 个市场中有 {} 个无法解析，已跳过", values.len(), page.skipped);
+    }
+    Ok(page)
+}
+
+/// 解析单个市场响应体，失败时错误中带上 conditionId 和 question
+fn parse_market(body: &str) -> Result<Market> {
+    let mut market: Market = serde_json::from_str(body).map_err(|e| {
+        let error = match serde_json::from_str::<Value>(body) {
+            Ok(value) => market_parse_error(&value, None, e),
+            Err(_) => ScannerError::JsonError(e),
+        };
+        warn!("市场解析错误: {}", error);
+        error
+    })?;
+    market.fill_resolution();
+    Ok(market)
+}
+
+/// 从无法解析的市场 JSON 中宽松提取标识字段，生成带上下文的错误
+fn market_parse_error(value: &Value, index: Option<usize>, e: serde_json::Error) -> ScannerError {
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| value.get(key).and_then(Value::as_str))
+            .unwrap_or("<未知>")
+            .to_string()
+    };
+    let condition_id = field(&["conditionId", "condition_id"]);
+    let question = field(&["question"]);
+    let position = index.map(|i| format!("第 {} 个", i + 1)).unwrap_or_default();
+
+    ScannerError::InvalidResponse(format!(
+        "{}市场解析失败 (conditionId={}, question={:?}): {}",
+        position, condition_id, question, e
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_markets_skips_bad_market_among_good_ones() {
+        let body = r#"[
+            {"conditionId": "0x01", "question": "First?", "outcomes": "[\"Yes\",\"No\"]",
+             "outcomePrices": "[\"0.4\",\"0.6\"]"},
+            {"conditionId": "0x02", "question": "Broken?", "outcomes": ["Yes", "No"]},
+            {"conditionId": "0x03", "question": "Third?", "outcomes": "[\"Yes\",\"No\"]"}
+        ]"#;
+        let page = parse_markets(body).unwrap();
+        let ids: Vec<&str> = page.markets.iter().map(|m| m.condition_id.as_str()).collect();
+        assert_eq!(ids, ["0x01", "0x03"]);
+        assert_eq!(page.skipped, 1);
+
+        let values: Vec<Value> = serde_json::from_str(body).unwrap();
+        let e = Market::deserialize(&values[1]).unwrap_err();
+        match market_parse_error(&values[1], Some(1), e) {
+            ScannerError::InvalidResponse(message) => {
+                assert!(
+                    message.starts_with("第 2 个市场解析失败 (conditionId=0x02, question=\"Broken?\")"),
+                    "{}",
+                    message
+                );
+            }
+            other => panic!("expected InvalidResponse, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_markets_rejects_non_array_body() {
+        assert!(matches!(
+            parse_markets(r#"{"error": "rate limited"}"#),
+            Err(ScannerError::JsonError(_))
+        ));
+    }
+
+    #[test]
+    fn check_json_body_rejects_html_error_page() {
+        let body = "<html><head><title>502 Bad Gateway</title></head><body>cloudflare</body></html>";
+        match check_json_body(reqwest::StatusCode::OK, Some("text/html; charset=utf-8"), body) {
+            Err(ScannerError::InvalidResponse(message)) => {
+                assert!(message.contains("非 JSON 响应 (text/html; charset=utf-8)"), "{}", message);
+                assert!(message.contains("502 Bad Gateway"), "{}", message);
+            }
+            other => panic!("expected InvalidResponse, got {:?}", other),
+        }
+
+        let status = reqwest::StatusCode::OK;
+        assert!(check_json_body(status, Some("Application/JSON"), "[]").is_ok());
+        // 没有 Content-Type 时只拒绝空响应体
+        assert!(check_json_body(status, None, "<html></html>").is_ok());
+        assert!(check_json_body(status, None, "  ").is_err());
+    }
+
+    /// 一页两个市场的 `/markets` 响应体
+    const MARKETS_BODY: &str = r#"[
+        {"conditionId": "0x01", "question": "First?", "outcomes": "[\"Yes\",\"No\"]"},
+        {"conditionId": "0x02", "question": "Second?", "outcomes": "[\"Yes\",\"No\"]"}
+    ]"#;
+
+    /// 带 `Content-Length` 的 HTTP 响应，每个响应后关闭连接
+    fn http_response(headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            body.len()
+        );
+        for (name, value) in headers {
+            response.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        response.push_str("\r\n");
+        let mut response = response.into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    /// 在本地端口上依次处理 `connections` 个连接，返回 API 地址和收到的请求头
+    ///
+    /// `respond` 按连接序号和请求头返回等待时间和响应
+    async fn mock_server<F>(
+        connections: usize,
+        respond: F,
+    ) -> (String, tokio::task::JoinHandle<Vec<String>>)
+    where
+        F: Fn(usize, &str) -> (Duration, Vec<u8>) + Send + 'static,
+    {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for index in 0..connections {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match socket.read(&mut buf).await {
+                        Ok(n) if n > 0 => request.extend_from_slice(&buf[..n]),
+                        _ => break,
+                    }
+                }
+                let request = String::from_utf8_lossy(&request).into_owned();
+                let (delay, response) = respond(index, &request);
+                tokio::time::sleep(delay).await;
+                // 客户端超时后已经断开，写入失败可以忽略
+                let _ = socket.write_all(&response).await;
+                let _ = socket.shutdown().await;
+                requests.push(request);
+            }
+            requests
+        });
+        (base, server)
+    }
+
+    fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+        request.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    #[tokio::test]
+    async fn requests_carry_user_agent_and_client_id() {
+        let (base, server) =
+            mock_server(2, |_, _| (Duration::ZERO, http_response(&[], MARKETS_BODY.as_bytes()))).await;
+
+        let default_client = PolymarketClient::builder().api_base(&base, &base).build().unwrap();
+        default_client.get_markets_page(&GammaQuery::active(), 10, 0).await.unwrap();
+        let custom_client = PolymarketClient::builder()
+            .api_base(&base, &base)
+            .user_agent("polymarket_scanner/test (ops@example.com)")
+            .client_id("deploy-7")
+            .build()
+            .unwrap();
+        custom_client.get_markets_page(&GammaQuery::active(), 10, 0).await.unwrap();
+
+        let requests = server.await.unwrap();
+        let default_user_agent = format!("polymarket_scanner/{}", env!("CARGO_PKG_VERSION"));
+        assert_eq!(header(&requests[0], "user-agent"), Some(default_user_agent.as_str()));
+        assert_eq!(header(&requests[0], CLIENT_ID_HEADER), None);
+        assert_eq!(
+            header(&requests[1], "user-agent"),
+            Some("polymarket_scanner/test (ops@example.com)")
+        );
+        assert_eq!(header(&requests[1], CLIENT_ID_HEADER), Some("deploy-7"));
+    }
+
+    #[tokio::test]
+    async fn slow_response_is_a_retryable_timeout() {
+        // 前两个连接超过客户端超时才响应，第三个立即响应
+        let (base, server) = mock_server(3, |index, _| {
+            let delay = if index < 2 { Duration::from_millis(300) } else { Duration::ZERO };
+            (delay, http_response(&[], MARKETS_BODY.as_bytes()))
+        })
+        .await;
+        let client = PolymarketClient::builder()
+            .api_base(&base, &base)
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+
+        let err = client.get_markets_page(&GammaQuery::active(), 10, 0).await.unwrap_err();
+        assert!(matches!(err, ScannerError::Timeout(_)), "{:?}", err);
+        assert!(err.is_retryable());
+
+        let page = get_markets_page_with_retry(&client, &GammaQuery::active(), 10, 0, 1)
+            .await
+            .unwrap();
+        assert_eq!(page.markets.len(), 2);
+        assert_eq!(server.await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn compression_on_and_off_parse_the_same_markets() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(MARKETS_BODY.as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+        // 请求声明接受 gzip 时返回压缩后的响应体，否则返回原文
+        let (base, server) = mock_server(2, move |_, request| {
+            let accepts_gzip = header(request, "accept-encoding").is_some_and(|v| v.contains("gzip"));
+            let response = if accepts_gzip {
+                http_response(&[("Content-Encoding", "gzip")], &gzipped)
+            } else {
+                http_response(&[], MARKETS_BODY.as_bytes())
+            };
+            (Duration::ZERO, response)
+        })
+        .await;
+
+        let mut counts = Vec::new();
+        for compression in [true, false] {
+            let client = PolymarketClient::builder()
+                .api_base(&base, &base)
+                .compression(compression)
+                .build()
+                .unwrap();
+            let page = client.get_markets_page(&GammaQuery::active(), 10, 0).await.unwrap();
+            counts.push(page.markets.len());
+        }
+        assert_eq!(counts, [2, 2]);
+
+        let requests = server.await.unwrap();
+        assert!(header(&requests[0], "accept-encoding").is_some_and(|v| v.contains("gzip")));
+        assert_eq!(header(&requests[1], "accept-encoding"), None);
+    }
+}
+
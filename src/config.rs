@@ -0,0 +1,542 @@
+use crate::api::{PolymarketClient, PolymarketClientBuilder, DEFAULT_INTER_BATCH_DELAY};
+#[cfg(feature = "sqlite")]
+use crate::db::SqliteConfig;
+use crate::error::{Result, ScannerError};
+#[cfg(feature = "json")]
+use crate::json_db::JsonDbConfig;
+use crate::scanner::{
+    ActivityConfig, PersistPolicy, PriceChangeMode, ScanCadence, ScannerConfig, SpreadConfig,
+};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// 未设置 `CONFIG_FILE` 时读取的配置文件，不存在时忽略
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// 扫描器的全部配置
+///
+/// 优先级从高到低：命令行参数（由调用方覆盖）> 环境变量 > 配置文件 > 默认值。
+/// 配置文件格式见 `config.example.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub storage: StorageSection,
+    pub api: ApiSection,
+    pub scan: ScanSection,
+    pub slack: SlackSection,
+    pub server: ServerSection,
+    pub kafka: KafkaSection,
+}
+
+/// `[storage]`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StorageSection {
+    /// 存储后端: json, sqlite, redis, memory, mongo，用 `+` 组合多个后端（如 `sqlite+redis`）
+    #[serde(rename = "type")]
+    pub storage_type: String,
+    pub redis_url: String,
+    pub database_url: String,
+    pub json_path: String,
+    pub json_pretty: bool,
+    /// JSON 存储每个市场保留的价格历史条数
+    pub json_history_limit: usize,
+    /// JSON 存储按 condition_id 前几位十六进制字符分片，0 表示不分片（单个 markets.json）
+    pub json_shard_prefix_len: usize,
+    pub mongo_url: String,
+    pub sqlite_max_connections: u32,
+    pub sqlite_wal: bool,
+    pub sqlite_busy_timeout_ms: u64,
+    /// SQLite / Redis 保存市场时是否同时写入解析后的字段，原始字符串总会保存
+    pub store_parsed: bool,
+    /// 启动时连接存储失败的重试时长（秒），0 表示不重试
+    pub connect_timeout_secs: u64,
+}
+
+impl Default for StorageSection {
+    fn default() -> Self {
+        // 与 SqliteConfig / JsonDbConfig 的默认值保持一致（对应 feature 未启用时这两个类型不存在）
+        Self {
+            storage_type: "json".to_string(),
+            redis_url: "redis://127.0.0.1:6379".to_string(),
+            database_url: "sqlite:data.db".to_string(),
+            json_path: "data".to_string(),
+            json_pretty: false,
+            json_history_limit: 1000,
+            json_shard_prefix_len: 0,
+            mongo_url: "mongodb://127.0.0.1:27017/polymarket".to_string(),
+            sqlite_max_connections: 5,
+            sqlite_wal: true,
+            sqlite_busy_timeout_ms: 5000,
+            store_parsed: true,
+            connect_timeout_secs: 30,
+        }
+    }
+}
+
+/// `[api]`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ApiSection {
+    /// 请求超时（秒），不设置时使用客户端默认值
+    pub timeout_secs: Option<u64>,
+    pub compression: bool,
+    /// 设置后把原始响应写入该目录，仅用于调试
+    pub debug_dump_dir: Option<PathBuf>,
+    /// 连续失败多少次后熔断，0 表示关闭熔断
+    pub circuit_failure_threshold: u32,
+    /// 熔断冷却时间（秒）
+    pub circuit_cooldown_secs: u64,
+    /// 连接池参数，不设置时使用 reqwest 默认值
+    pub pool_max_idle_per_host: Option<usize>,
+    pub pool_idle_timeout_secs: Option<u64>,
+    pub tcp_keepalive_secs: Option<u64>,
+    /// 响应体大小上限（字节），不设置时使用客户端默认值（64 MiB）
+    pub max_body_bytes: Option<usize>,
+    /// 代理地址，不设置时使用 `HTTPS_PROXY` / `ALL_PROXY` 环境变量
+    pub proxy: Option<String>,
+    pub proxy_username: Option<String>,
+    pub proxy_password: Option<String>,
+    /// 不校验 TLS 证书，仅用于拦截式代理
+    pub danger_accept_invalid_certs: bool,
+    /// 不设置时使用 `polymarket_scanner/{版本}`
+    pub user_agent: Option<String>,
+    /// 设置后每个请求带上 `X-Client-Id` 头
+    pub client_id: Option<String>,
+}
+
+impl Default for ApiSection {
+    fn default() -> Self {
+        Self {
+            timeout_secs: None,
+            compression: true,
+            debug_dump_dir: None,
+            circuit_failure_threshold: 5,
+            circuit_cooldown_secs: 30,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout_secs: None,
+            tcp_keepalive_secs: None,
+            max_body_bytes: None,
+            proxy: None,
+            proxy_username: None,
+            proxy_password: None,
+            danger_accept_invalid_certs: false,
+            user_agent: None,
+            client_id: None,
+        }
+    }
+}
+
+/// `[scan]`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ScanSection {
+    /// 扫描间隔（秒）
+    pub interval: u64,
+    /// 扫描间隔的随机偏移比例（0.1 表示 ±10%），0 表示不偏移
+    pub jitter: f64,
+    /// 每轮扫描的市场数量，0 表示分页扫描全部市场
+    pub max_markets: u32,
+    /// 全量扫描的每批数量
+    pub batch_size: u32,
+    /// 全量扫描两页请求之间的等待时间（毫秒），0 表示不等待
+    pub inter_batch_delay_ms: u64,
+    pub price_threshold: f64,
+    /// "price" 或 "probability"，`price_threshold` 比较原始价格还是隐含概率
+    pub price_change_mode: PriceChangeMode,
+    pub volume_threshold: f64,
+    /// 持续扫描和全量扫描都包含已关闭的市场（查询并保存），见 `ScannerConfig::include_closed`
+    pub include_closed: bool,
+    /// 已废弃，等同于 `include_closed`（旧的 PERSIST_CLOSED / SCAN_ALL_INCLUDE_CLOSED 环境变量设置此项）
+    pub persist_closed: bool,
+    pub scan_all_first: bool,
+    /// 0 表示关闭下架检测
+    pub delist_after_scans: u32,
+    /// 未关闭的市场超过多少秒没有变化时产生 MarketStale 事件，0 表示关闭检测
+    pub stale_after_secs: u64,
+    /// 负数表示不做价格归一化
+    pub price_precision: i32,
+    pub max_concurrent_saves: usize,
+    pub emit_malformed: bool,
+    pub dedupe_price_history: bool,
+    /// 关注市场的价格历史改为每轮记录订单簿中间价
+    pub watchlist_mid_prices: bool,
+    /// 分别控制是否写入市场快照、事件日志和价格历史
+    pub save_snapshots: bool,
+    pub save_events: bool,
+    pub save_price_history: bool,
+    /// 设置后关注的市场按该间隔（秒）单独扫描，常规扫描仍按 `interval`
+    pub watchlist_interval: Option<u64>,
+    /// 内存中最多追踪的市场数量，0 表示不限制
+    pub max_tracked_markets: usize,
+    /// 设置后按该间隔（秒）单独检测新创建的市场
+    pub new_market_interval: Option<u64>,
+    /// 设置后启用价差追踪
+    pub spread_threshold: Option<f64>,
+    pub spread_min_volume: Option<f64>,
+    /// 设置后启用 HighActivity 事件
+    pub activity_threshold: Option<f64>,
+    pub activity_volume_weight: Option<f64>,
+    pub activity_price_weight: Option<f64>,
+}
+
+impl Default for ScanSection {
+    fn default() -> Self {
+        let scanner = ScannerConfig::default();
+        Self {
+            interval: scanner.scan_interval.as_secs(),
+            jitter: scanner.scan_jitter,
+            max_markets: scanner.max_markets.unwrap_or(0),
+            batch_size: 100,
+            inter_batch_delay_ms: DEFAULT_INTER_BATCH_DELAY.as_millis() as u64,
+            price_threshold: scanner.price_threshold,
+            price_change_mode: scanner.price_change_mode,
+            volume_threshold: scanner.volume_threshold,
+            include_closed: scanner.include_closed,
+            persist_closed: false,
+            scan_all_first: false,
+            delist_after_scans: scanner.delist_after_scans.unwrap_or(0),
+            stale_after_secs: scanner.stale_after.map_or(0, |d| d.as_secs()),
+            price_precision: scanner.price_precision.map_or(-1, |p| p as i32),
+            max_concurrent_saves: scanner.max_concurrent_saves,
+            emit_malformed: scanner.emit_malformed,
+            dedupe_price_history: scanner.dedupe_price_history,
+            watchlist_mid_prices: scanner.watchlist_mid_prices,
+            save_snapshots: scanner.persist_policy.save_snapshots,
+            save_events: scanner.persist_policy.save_events,
+            save_price_history: scanner.persist_policy.save_price_history,
+            watchlist_interval: None,
+            max_tracked_markets: scanner.max_tracked_markets.unwrap_or(0),
+            new_market_interval: None,
+            spread_threshold: None,
+            spread_min_volume: None,
+            activity_threshold: None,
+            activity_volume_weight: None,
+            activity_price_weight: None,
+        }
+    }
+}
+
+/// `[slack]`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SlackSection {
+    /// 设置后启用 Slack 通知
+    pub webhook_url: Option<String>,
+    /// 只推送这些事件类型，为空表示全部
+    pub event_types: Vec<String>,
+    pub min_volume: Option<f64>,
+}
+
+/// `[server]`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ServerSection {
+    /// 设置后在该地址提供 SSE 事件推送（需要 `server` feature）
+    pub sse_addr: Option<String>,
+}
+
+/// `[kafka]`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct KafkaSection {
+    /// 设置后向 Kafka 发布事件（需要 `kafka` feature），逗号分隔的 host:port 列表
+    pub brokers: Option<String>,
+    pub topic: String,
+    /// 只发布这些事件类型，为空表示全部
+    pub event_types: Vec<String>,
+    /// 投递失败时最多缓冲的事件数，0 表示不缓冲
+    pub buffer_size: usize,
+}
+
+impl Default for KafkaSection {
+    fn default() -> Self {
+        Self {
+            brokers: None,
+            topic: "polymarket-events".to_string(),
+            event_types: Vec::new(),
+            buffer_size: 1000,
+        }
+    }
+}
+
+impl Config {
+    /// 读取 `CONFIG_FILE` 指定的配置文件（默认 `config.toml`，不存在时忽略），
+    /// 再用环境变量覆盖
+    pub fn load() -> Result<Self> {
+        match std::env::var("CONFIG_FILE") {
+            Ok(path) => Self::load_from(Some(Path::new(&path))),
+            Err(_) => Self::load_from(None),
+        }
+    }
+
+    /// 读取指定的配置文件（必须存在）再用环境变量覆盖；`None` 时与 `load` 相同，
+    /// 但不读取 `CONFIG_FILE`
+    pub fn load_from(path: Option<&Path>) -> Result<Self> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => Self::default(),
+        };
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            ScannerError::ConfigError(format!("读取配置文件 {} 失败: {}", path.display(), e))
+        })?;
+        toml::from_str(&content).map_err(|e| {
+            ScannerError::ConfigError(format!("解析配置文件 {} 失败: {}", path.display(), e))
+        })
+    }
+
+    /// 用环境变量覆盖配置，变量名与 README 中列出的一致
+    fn apply_env(&mut self) -> Result<()> {
+        let storage = &mut self.storage;
+        env_override(&mut storage.storage_type, "STORAGE_TYPE")?;
+        env_override(&mut storage.redis_url, "REDIS_URL")?;
+        env_override(&mut storage.database_url, "DATABASE_URL")?;
+        env_override(&mut storage.json_path, "JSON_DB_PATH")?;
+        env_override(&mut storage.json_pretty, "JSON_DB_PRETTY")?;
+        env_override(&mut storage.json_history_limit, "JSON_HISTORY_LIMIT")?;
+        env_override(&mut storage.json_shard_prefix_len, "JSON_SHARD_PREFIX_LEN")?;
+        env_override(&mut storage.mongo_url, "MONGO_URL")?;
+        env_override(
+            &mut storage.sqlite_max_connections,
+            "SQLITE_MAX_CONNECTIONS",
+        )?;
+        env_override(&mut storage.sqlite_wal, "SQLITE_WAL")?;
+        env_override(
+            &mut storage.sqlite_busy_timeout_ms,
+            "SQLITE_BUSY_TIMEOUT_MS",
+        )?;
+        env_override(&mut storage.store_parsed, "STORE_PARSED")?;
+        env_override(&mut storage.connect_timeout_secs, "STORAGE_CONNECT_TIMEOUT")?;
+
+        let api = &mut self.api;
+        env_override_opt(&mut api.timeout_secs, "API_TIMEOUT")?;
+        env_override(&mut api.compression, "API_COMPRESSION")?;
+        env_override_opt(&mut api.debug_dump_dir, "API_DEBUG_DUMP_DIR")?;
+        env_override(&mut api.circuit_failure_threshold, "API_CIRCUIT_THRESHOLD")?;
+        env_override(&mut api.circuit_cooldown_secs, "API_CIRCUIT_COOLDOWN")?;
+        env_override_opt(&mut api.pool_max_idle_per_host, "API_POOL_MAX_IDLE")?;
+        env_override_opt(&mut api.pool_idle_timeout_secs, "API_POOL_IDLE_TIMEOUT")?;
+        env_override_opt(&mut api.tcp_keepalive_secs, "API_TCP_KEEPALIVE")?;
+        env_override_opt(&mut api.max_body_bytes, "API_MAX_BODY_BYTES")?;
+        env_override_opt(&mut api.proxy, "API_PROXY")?;
+        env_override_opt(&mut api.proxy_username, "API_PROXY_USERNAME")?;
+        env_override_opt(&mut api.proxy_password, "API_PROXY_PASSWORD")?;
+        env_override(
+            &mut api.danger_accept_invalid_certs,
+            "API_DANGER_ACCEPT_INVALID_CERTS",
+        )?;
+        env_override_opt(&mut api.user_agent, "API_USER_AGENT")?;
+        env_override_opt(&mut api.client_id, "API_CLIENT_ID")?;
+
+        let scan = &mut self.scan;
+        env_override(&mut scan.interval, "SCAN_INTERVAL")?;
+        env_override(&mut scan.jitter, "SCAN_JITTER")?;
+        env_override(&mut scan.max_markets, "MAX_MARKETS")?;
+        env_override(&mut scan.batch_size, "BATCH_SIZE")?;
+        env_override(&mut scan.inter_batch_delay_ms, "INTER_BATCH_DELAY_MS")?;
+        env_override(&mut scan.price_threshold, "PRICE_THRESHOLD")?;
+        env_override(&mut scan.price_change_mode, "PRICE_CHANGE_MODE")?;
+        env_override(&mut scan.volume_threshold, "VOLUME_THRESHOLD")?;
+        env_override(&mut scan.include_closed, "INCLUDE_CLOSED")?;
+        env_override(&mut scan.persist_closed, "PERSIST_CLOSED")?;
+        env_override(&mut scan.scan_all_first, "SCAN_ALL_FIRST")?;
+        env_override(&mut scan.persist_closed, "SCAN_ALL_INCLUDE_CLOSED")?;
+        env_override(&mut scan.delist_after_scans, "DELIST_AFTER_SCANS")?;
+        env_override(&mut scan.stale_after_secs, "STALE_AFTER_SECS")?;
+        env_override(&mut scan.price_precision, "PRICE_PRECISION")?;
+        env_override(&mut scan.max_concurrent_saves, "MAX_CONCURRENT_SAVES")?;
+        env_override(&mut scan.emit_malformed, "MALFORMED_EVENTS")?;
+        env_override(&mut scan.dedupe_price_history, "DEDUPE_PRICE_HISTORY")?;
+        env_override(&mut scan.watchlist_mid_prices, "WATCHLIST_MID_PRICES")?;
+        env_override(&mut scan.save_snapshots, "SAVE_SNAPSHOTS")?;
+        env_override(&mut scan.save_events, "SAVE_EVENTS")?;
+        env_override(&mut scan.save_price_history, "SAVE_PRICE_HISTORY")?;
+        env_override_opt(&mut scan.watchlist_interval, "WATCHLIST_INTERVAL")?;
+        env_override(&mut scan.max_tracked_markets, "MAX_TRACKED_MARKETS")?;
+        env_override_opt(&mut scan.new_market_interval, "NEW_MARKET_INTERVAL")?;
+        env_override_opt(&mut scan.spread_threshold, "SPREAD_THRESHOLD")?;
+        env_override_opt(&mut scan.spread_min_volume, "SPREAD_MIN_VOLUME")?;
+        env_override_opt(&mut scan.activity_threshold, "ACTIVITY_THRESHOLD")?;
+        env_override_opt(&mut scan.activity_volume_weight, "ACTIVITY_VOLUME_WEIGHT")?;
+        env_override_opt(&mut scan.activity_price_weight, "ACTIVITY_PRICE_WEIGHT")?;
+
+        let slack = &mut self.slack;
+        env_override_opt(&mut slack.webhook_url, "SLACK_WEBHOOK_URL")?;
+        env_override_list(&mut slack.event_types, "SLACK_EVENT_TYPES");
+        env_override_opt(&mut slack.min_volume, "SLACK_MIN_VOLUME")?;
+
+        env_override_opt(&mut self.server.sse_addr, "SSE_ADDR")?;
+
+        let kafka = &mut self.kafka;
+        env_override_opt(&mut kafka.brokers, "KAFKA_BROKERS")?;
+        env_override(&mut kafka.topic, "KAFKA_TOPIC")?;
+        env_override_list(&mut kafka.event_types, "KAFKA_EVENT_TYPES");
+        env_override(&mut kafka.buffer_size, "KAFKA_BUFFER_SIZE")?;
+        Ok(())
+    }
+
+    /// API 客户端构建器
+    pub fn client_builder(&self) -> PolymarketClientBuilder {
+        let mut builder = PolymarketClient::builder()
+            .compression(self.api.compression)
+            .circuit_breaker(
+                self.api.circuit_failure_threshold,
+                Duration::from_secs(self.api.circuit_cooldown_secs),
+            );
+        if let Some(secs) = self.api.timeout_secs {
+            builder = builder.timeout(Duration::from_secs(secs));
+        }
+        if let Some(dir) = &self.api.debug_dump_dir {
+            builder = builder.debug_dump_dir(dir);
+        }
+        if let Some(max) = self.api.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(secs) = self.api.pool_idle_timeout_secs {
+            builder = builder.pool_idle_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.api.tcp_keepalive_secs {
+            builder = builder.tcp_keepalive(Duration::from_secs(secs));
+        }
+        if let Some(bytes) = self.api.max_body_bytes {
+            builder = builder.max_body_size(bytes);
+        }
+        if let Some(url) = &self.api.proxy {
+            builder = builder.proxy(url);
+        }
+        if let Some(username) = &self.api.proxy_username {
+            let password = self.api.proxy_password.as_deref().unwrap_or_default();
+            builder = builder.proxy_auth(username, password);
+        }
+        if let Some(user_agent) = &self.api.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        if let Some(client_id) = &self.api.client_id {
+            builder = builder.client_id(client_id);
+        }
+        builder.danger_accept_invalid_certs(self.api.danger_accept_invalid_certs)
+    }
+
+    /// 扫描器配置
+    pub fn scanner_config(&self) -> ScannerConfig {
+        let scan = &self.scan;
+        ScannerConfig {
+            scan_interval: Duration::from_secs(scan.interval),
+            scan_jitter: scan.jitter,
+            price_threshold: scan.price_threshold,
+            price_change_mode: scan.price_change_mode,
+            volume_threshold: scan.volume_threshold,
+            max_markets: Some(scan.max_markets).filter(|&n| n > 0),
+            include_closed: scan.include_closed || scan.persist_closed,
+            spread: scan.spread_threshold.map(|threshold| {
+                let mut spread = SpreadConfig {
+                    threshold,
+                    ..Default::default()
+                };
+                if let Some(min_volume) = scan.spread_min_volume {
+                    spread.min_volume = min_volume;
+                }
+                spread
+            }),
+            delist_after_scans: Some(scan.delist_after_scans).filter(|&n| n > 0),
+            stale_after: Some(scan.stale_after_secs)
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+            max_concurrent_saves: scan.max_concurrent_saves,
+            price_precision: u32::try_from(scan.price_precision).ok(),
+            activity: scan.activity_threshold.map(|threshold| {
+                let mut activity = ActivityConfig {
+                    threshold,
+                    ..Default::default()
+                };
+                if let Some(weight) = scan.activity_volume_weight {
+                    activity.volume_weight = weight;
+                }
+                if let Some(weight) = scan.activity_price_weight {
+                    activity.price_weight = weight;
+                }
+                activity
+            }),
+            emit_malformed: scan.emit_malformed,
+            dedupe_price_history: scan.dedupe_price_history,
+            watchlist_mid_prices: scan.watchlist_mid_prices,
+            persist_policy: PersistPolicy {
+                save_snapshots: scan.save_snapshots,
+                save_events: scan.save_events,
+                save_price_history: scan.save_price_history,
+            },
+            cadence: scan.watchlist_interval.map(|secs| ScanCadence {
+                watchlist_interval: Duration::from_secs(secs),
+                full_interval: Duration::from_secs(scan.interval),
+            }),
+            max_tracked_markets: Some(scan.max_tracked_markets).filter(|&n| n > 0),
+            new_market_interval: scan.new_market_interval.map(Duration::from_secs),
+            ..Default::default()
+        }
+    }
+
+    /// SQLite 连接池配置
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite_config(&self) -> SqliteConfig {
+        SqliteConfig {
+            max_connections: self.storage.sqlite_max_connections,
+            wal: self.storage.sqlite_wal,
+            busy_timeout: Duration::from_millis(self.storage.sqlite_busy_timeout_ms),
+            store_parsed: self.storage.store_parsed,
+        }
+    }
+
+    /// JSON 存储配置
+    #[cfg(feature = "json")]
+    pub fn json_db_config(&self) -> JsonDbConfig {
+        JsonDbConfig {
+            pretty: self.storage.json_pretty,
+            history_limit: self.storage.json_history_limit,
+            shard_prefix_len: self.storage.json_shard_prefix_len,
+        }
+    }
+}
+
+/// 环境变量已设置时解析并覆盖 `target`，无法解析时报错而不是静默忽略
+fn env_override<T: FromStr>(target: &mut T, key: &str) -> Result<()> {
+    if let Ok(value) = std::env::var(key) {
+        *target = parse_env(key, &value)?;
+    }
+    Ok(())
+}
+
+/// 同 `env_override`，用于默认未设置的可选项
+fn env_override_opt<T: FromStr>(target: &mut Option<T>, key: &str) -> Result<()> {
+    if let Ok(value) = std::env::var(key) {
+        *target = Some(parse_env(key, &value)?);
+    }
+    Ok(())
+}
+
+/// 逗号分隔的列表，忽略空项
+fn env_override_list(target: &mut Vec<String>, key: &str) {
+    if let Ok(value) = std::env::var(key) {
+        *target = value
+            .split(',')
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+    }
+}
+
+fn parse_env<T: FromStr>(key: &str, value: &str) -> Result<T> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ScannerError::ConfigError(format!("环境变量 {} 的值无效: {}", key, value)))
+}
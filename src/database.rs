@@ -1,14 +1,219 @@
 use crate::error::{Result, ScannerError};
-use crate::storage::Storage;
-use crate::types::Market;
+use crate::storage::{
+    price_volatility, status_counts, top_by_volume, window_start, DatabaseStats, EventBatch,
+    Storage,
+};
+use crate::types::{Market, MarketEvent, ParsedMarket, PriceAlert};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use log::info;
 use redis::aio::ConnectionManager;
-use redis::AsyncCommands;
+use redis::streams::StreamRangeReply;
+use redis::{AsyncCommands, RedisError, RedisResult};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::{debug, info, instrument, warn};
+
+/// 事件日志使用的 Redis Stream
+const EVENTS_STREAM: &str = "events:stream";
+/// 事件日志保留的大致条数（XADD MAXLEN ~）
+const EVENTS_MAX_LEN: usize = 100_000;
+/// 按市场查询事件时每次从 Stream 读取的条目数
+const EVENTS_SCAN_BATCH: usize = 1_000;
+/// 已写入事件 ID 的去重键前缀，每个事件一个 `events:id:{id}` 键
+const EVENT_ID_KEY_PREFIX: &str = "events:id:";
+/// 去重键的过期时间（秒）
+///
+/// 事件 ID 按 300 秒的时间桶计算（见 `MarketEvent::compute_id`），重复写入只会发生在
+/// 同一时间桶内的重试或回放中，保留一天足够去重，同时不会随事件日志无限增长
+const EVENT_ID_TTL_SECS: u64 = 24 * 3600;
+/// 旧版本使用的去重集合，会无限增长，初始化时删除
+const LEGACY_EVENT_IDS_SET: &str = "events:ids";
+/// condition_id -> 最后更新时间（毫秒时间戳）的 Sorted Set
+const MARKETS_UPDATED: &str = "markets:updated";
+/// 提醒 ID -> 价格提醒 JSON 的 Hash
+const PRICE_ALERTS: &str = "price_alerts";
+/// 关注的市场 condition_id 集合
+const WATCHLIST: &str = "watchlist";
+
+/// 连接级错误的最大重试次数
+const MAX_RETRIES: u32 = 3;
 
 pub struct Database {
     conn: ConnectionManager,
+    /// 重试后恢复成功的次数
+    reconnects: AtomicU64,
+    /// 保存市场时是否同时写入解析后的字段
+    store_parsed: bool,
+}
+
+/// 保存时从原始字符串派生的字段，与原始字段保存在同一个 Hash 中
+const PARSED_FIELDS: [&str; 4] = ["outcomes_parsed", "prices_parsed", "volume_num", "liquidity_num"];
+
+/// 由市场 Hash 的字段构建 Market，空字符串视为未设置
+fn fields_to_market(map: &HashMap<String, String>) -> Market {
+    Market {
+        condition_id: map.get("condition_id").cloned().unwrap_or_default(),
+        question_id: map.get("question_id").and_then(|s| {
+            if s.is_empty() {
+                None
+            } else {
+                Some(s.clone())
+            }
+        }),
+        question: map.get("question").cloned().unwrap_or_default(),
+        description: map.get("description").and_then(|s| {
+            if s.is_empty() {
+                None
+            } else {
+                Some(s.clone())
+            }
+        }),
+        market_slug: map.get("market_slug").and_then(|s| {
+            if s.is_empty() {
+                None
+            } else {
+                Some(s.clone())
+            }
+        }),
+        outcomes: map.get("outcomes").cloned().unwrap_or("[]".to_string()),
+        outcome_prices: Some(
+            map.get("outcome_prices")
+                .cloned()
+                .unwrap_or("[]".to_string()),
+        ),
+        volume: map
+            .get("volume")
+            .and_then(|s| if s.is_empty() { None } else { Some(s.clone()) }),
+        liquidity: map.get("liquidity").and_then(|s| {
+            if s.is_empty() {
+                None
+            } else {
+                Some(s.clone())
+            }
+        }),
+        end_date: map.get("end_date").and_then(|s| {
+            if s.is_empty() {
+                None
+            } else {
+                Some(s.clone())
+            }
+        }),
+        active: map.get("active").map(|s| s == "1"),
+        closed: map.get("closed").map(|s| s == "1"),
+        tags: map
+            .get("tags")
+            .and_then(|s| serde_json::from_str(s).ok())
+            .unwrap_or_default(),
+        uma_resolution_status: map
+            .get("uma_resolution_status")
+            .filter(|s| !s.is_empty())
+            .cloned(),
+        resolved_outcome: map.get("resolved_outcome").and_then(|s| s.parse().ok()),
+        ..Default::default()
+    }
+}
+
+/// 事件 ID 的去重键
+fn event_id_key(id: &str) -> String {
+    format!("{}{}", EVENT_ID_KEY_PREFIX, id)
+}
+
+/// 为事件 ID 创建去重键（SET NX EX），键已存在时命令返回 nil
+fn claim_event_id(id: &str) -> redis::Cmd {
+    let mut cmd = redis::cmd("SET");
+    cmd.arg(event_id_key(id))
+        .arg(1)
+        .arg("NX")
+        .arg("EX")
+        .arg(EVENT_ID_TTL_SECS);
+    cmd
+}
+
+/// 是否为连接级错误（Redis 重启、网络中断等），这类错误在 ConnectionManager 重连后可以重试
+fn is_connection_error(e: &RedisError) -> bool {
+    e.is_connection_dropped() || e.is_connection_refusal() || e.is_io_error() || e.is_timeout()
+}
+
+/// 失败的命令是否可能已经在服务端执行：连接级错误中只有连接被拒绝能确定命令没有发出，
+/// 服务端返回的错误说明命令没有执行
+fn may_have_applied(e: &RedisError) -> bool {
+    is_connection_error(e) && !e.is_connection_refusal()
+}
+
+/// 写操作失败后哪些错误可以重试
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RetryPolicy {
+    /// 幂等的写入（HSET、ZADD 等），所有连接级错误都重试
+    Idempotent,
+    /// 非幂等的写入（`XADD *` 重复执行会追加重复的条目），只在命令确定没有发出
+    /// （连接被拒绝）时重试；超时或发送后连接断开时服务端可能已经执行了命令
+    NotSent,
+}
+
+impl RetryPolicy {
+    fn should_retry(self, e: &RedisError) -> bool {
+        match self {
+            RetryPolicy::Idempotent => is_connection_error(e),
+            RetryPolicy::NotSent => e.is_connection_refusal(),
+        }
+    }
+}
+
+/// 为重试循环提供连接，测试中用假的实现模拟断线
+trait Connector {
+    type Conn;
+
+    fn connection(&self) -> Self::Conn;
+}
+
+impl Connector for ConnectionManager {
+    type Conn = ConnectionManager;
+
+    fn connection(&self) -> ConnectionManager {
+        self.clone()
+    }
+}
+
+/// 执行写操作，失败时按 `policy` 有限次重试，重试后成功时累加 `reconnects`
+async fn run_with_retry<C, T, F, Fut>(
+    connector: &C,
+    policy: RetryPolicy,
+    op: &str,
+    reconnects: &AtomicU64,
+    mut f: F,
+) -> RedisResult<T>
+where
+    C: Connector,
+    F: FnMut(C::Conn) -> Fut,
+    Fut: Future<Output = RedisResult<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match f(connector.connection()).await {
+            Ok(value) => {
+                if attempt > 0 {
+                    let total = reconnects.fetch_add(1, Ordering::Relaxed) + 1;
+                    info!(
+                        "Redis 连接已恢复: {} 在第 {} 次重试后成功（累计恢复 {} 次）",
+                        op, attempt, total
+                    );
+                }
+                return Ok(value);
+            }
+            Err(e) if attempt < MAX_RETRIES && policy.should_retry(&e) => {
+                attempt += 1;
+                let delay = Duration::from_millis(100 << attempt);
+                warn!(
+                    "Redis 连接错误（{}），{:?} 后第 {} 次重试: {}",
+                    op, delay, attempt, e
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
 }
 
 impl Database {
@@ -21,10 +226,66 @@ impl Database {
 
         let conn = ConnectionManager::new(client)
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("Redis 连接失败: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("Redis 连接失败: {}", e)))?;
 
         info!("Redis 连接成功");
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            reconnects: AtomicU64::new(0),
+            store_parsed: true,
+        })
+    }
+
+    /// 保存市场时是否同时写入解析后的字段（默认 true）：`outcomes_parsed`、
+    /// `prices_parsed`（JSON 数组）和 `volume_num`、`liquidity_num`，原始字符串总会保存。
+    /// 关闭后保存的市场会删除这些字段，`get_market_parsed` 改为从原始字符串解析
+    pub fn with_store_parsed(mut self, enabled: bool) -> Self {
+        self.store_parsed = enabled;
+        self
+    }
+
+    /// 连接错误后重试成功（即重连恢复）的累计次数
+    pub fn reconnect_count(&self) -> u64 {
+        self.reconnects.load(Ordering::Relaxed)
+    }
+
+    /// 读取市场 Hash 的全部字段，市场不存在时返回 None
+    async fn get_market_fields(&self, condition_id: &str) -> Result<Option<HashMap<String, String>>> {
+        let mut conn = self.conn.clone();
+        let key = format!("market:{}", condition_id);
+
+        let exists: bool = conn
+            .exists(&key)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("Redis 查询失败: {}", e)))?;
+
+        if !exists {
+            return Ok(None);
+        }
+
+        let data: Vec<String> = conn
+            .hgetall(&key)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("获取市场数据失败: {}", e)))?;
+
+        // Redis HGETALL 返回 [key, value, key, value, ...]
+        let mut map = HashMap::new();
+        for i in (0..data.len()).step_by(2) {
+            if i + 1 < data.len() {
+                map.insert(data[i].clone(), data[i + 1].clone());
+            }
+        }
+
+        Ok(Some(map))
+    }
+
+    /// 执行写操作，按 `policy` 对连接级错误等待 ConnectionManager 重连后有限次重试
+    async fn with_retry<T, F, Fut>(&self, op: &str, policy: RetryPolicy, f: F) -> RedisResult<T>
+    where
+        F: FnMut(ConnectionManager) -> Fut,
+        Fut: Future<Output = RedisResult<T>>,
+    {
+        run_with_retry(&self.conn, policy, op, &self.reconnects, f).await
     }
 
     /// 清空所有数据（慎用）
@@ -34,7 +295,7 @@ impl Database {
         redis::cmd("FLUSHDB")
             .query_async::<_, ()>(&mut conn)
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("清空数据库失败: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("清空数据库失败: {}", e)))?;
 
         info!("Redis 数据库已清空");
         Ok(())
@@ -45,22 +306,54 @@ impl Database {
 impl Storage for Database {
     /// 初始化 Redis（可选，Redis 不需要 schema）
     async fn init(&self) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let removed: i64 = conn
+            .del(LEGACY_EVENT_IDS_SET)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("删除旧的事件去重集合失败: {}", e)))?;
+        if removed > 0 {
+            info!("已删除旧版本的事件去重集合 {}", LEGACY_EVENT_IDS_SET);
+        }
         info!("Redis 初始化完成（无需创建表结构）");
         Ok(())
     }
 
+    /// 发送 PING 检查 Redis 是否可用
+    async fn health_check(&self) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let pong: String = redis::cmd("PING")
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("Redis 健康检查失败: {}", e)))?;
+
+        if pong != "PONG" {
+            return Err(ScannerError::StorageError(format!(
+                "Redis 健康检查返回异常: {}",
+                pong
+            )));
+        }
+        Ok(())
+    }
+
     /// 保存或更新市场数据
+    #[instrument(level = "trace", skip_all, fields(condition_id = %market.condition_id))]
     async fn save_market(&self, market: &Market) -> Result<()> {
         self.save_markets(vec![market.clone()]).await
     }
 
+    #[instrument(level = "debug", skip_all, fields(count = markets.len()))]
     async fn save_markets(&self, markets: Vec<Market>) -> Result<()> {
-        let mut conn = self.conn.clone();
         let mut pipe = redis::pipe();
-        let now = Utc::now().to_rfc3339();
+        let updated_at = Utc::now();
+        let now = updated_at.to_rfc3339();
 
         for market in markets {
             let key = format!("market:{}", market.condition_id);
+            let tags = serde_json::to_string(&market.tags)?;
+            let resolved_outcome = market
+                .resolved_outcome
+                .map(|i| i.to_string())
+                .unwrap_or_default();
 
             // Note: In a pipeline, we can't easily check for existence and conditionally update
             // 'first_seen_at' without a Lua script or multiple round trips.
@@ -97,64 +390,324 @@ impl Storage for Database {
                     ("end_date", market.end_date.as_deref().unwrap_or("")),
                     (
                         "active",
-                        &market
+                        market
                             .active
                             .map(|b| if b { "1" } else { "0" })
                             .unwrap_or("0"),
                     ),
                     (
                         "closed",
-                        &market
+                        market
                             .closed
                             .map(|b| if b { "1" } else { "0" })
                             .unwrap_or("0"),
                     ),
+                    ("tags", &tags),
+                    (
+                        "uma_resolution_status",
+                        market.uma_resolution_status.as_deref().unwrap_or(""),
+                    ),
+                    ("resolved_outcome", &resolved_outcome),
                     ("last_updated_at", &now),
                 ],
             );
 
+            // 无法解析的字段存为空字符串，读取时回退到解析原始字符串
+            if self.store_parsed {
+                let outcomes = market
+                    .outcome_list()
+                    .map(|outcomes| serde_json::to_string(&outcomes))
+                    .transpose()?;
+                let prices = market
+                    .prices()
+                    .map(|prices| serde_json::to_string(&prices))
+                    .transpose()?;
+                let number = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+                pipe.hset_multiple(
+                    &key,
+                    &[
+                        (PARSED_FIELDS[0], outcomes.unwrap_or_default()),
+                        (PARSED_FIELDS[1], prices.unwrap_or_default()),
+                        (PARSED_FIELDS[2], number(market.volume_value())),
+                        (PARSED_FIELDS[3], number(market.liquidity_value())),
+                    ],
+                );
+            } else {
+                pipe.hdel(&key, &PARSED_FIELDS[..]);
+            }
+
             // Use HSETNX for first_seen_at to only set it if it doesn't exist
             pipe.hset_nx(&key, "first_seen_at", &now);
 
             // Add to set
             pipe.sadd("markets:all", &market.condition_id);
+            pipe.zadd(
+                MARKETS_UPDATED,
+                &market.condition_id,
+                updated_at.timestamp_millis(),
+            );
         }
 
-        let _: () = pipe
-            .query_async(&mut conn)
-            .await
-            .map_err(|e| ScannerError::ConfigError(format!("Batch save markets failed: {}", e)))?;
+        let pipe = &pipe;
+        self.with_retry("save_markets", RetryPolicy::Idempotent, |mut conn| async move {
+            pipe.query_async::<_, ()>(&mut conn).await
+        })
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("Batch save markets failed: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 保存市场事件到 Redis Stream，事件 ID 的去重键已存在时忽略
+    #[instrument(level = "trace", skip_all, fields(condition_id = %event.market.condition_id, event_id = %event.id))]
+    async fn save_event(&self, event: &MarketEvent) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let payload = serde_json::to_string(event)?;
+
+        if !event.id.is_empty() {
+            let claimed: Option<String> = claim_event_id(&event.id)
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("保存事件 ID 失败: {}", e)))?;
+            if claimed.is_none() {
+                debug!("忽略重复事件: {}", event.id);
+                return Ok(());
+            }
+        }
+
+        let mut cmd = redis::cmd("XADD");
+        cmd.arg(EVENTS_STREAM)
+            .arg("MAXLEN")
+            .arg("~")
+            .arg(EVENTS_MAX_LEN)
+            .arg("*")
+            .arg("condition_id")
+            .arg(&event.market.condition_id)
+            .arg("event_type")
+            .arg(event.event_type.name())
+            .arg("payload")
+            .arg(&payload);
+
+        let cmd = &cmd;
+        let result = self
+            .with_retry("save_event", RetryPolicy::NotSent, |mut conn| async move {
+                cmd.query_async::<_, String>(&mut conn).await
+            })
+            .await;
+
+        if let Err(e) = result {
+            // 确定没有写入时撤销去重记录，以便之后重新保存该事件；
+            // 可能已经写入时保留，宁可记录错误也不产生重复事件
+            if !event.id.is_empty() && !may_have_applied(&e) {
+                let _: RedisResult<i64> = conn.del(event_id_key(&event.id)).await;
+            }
+            return Err(ScannerError::StorageError(format!("保存事件失败: {}", e)));
+        }
+
+        Ok(())
+    }
+
+    /// 用两次 pipeline 批量保存事件：先创建去重键，再只为新事件 XADD
+    #[instrument(level = "debug", skip_all, fields(count = events.len()))]
+    async fn save_events(&self, events: Vec<MarketEvent>) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.conn.clone();
+
+        // 没有 ID 的事件不参与去重
+        let mut dedup = redis::pipe();
+        for event in events.iter().filter(|e| !e.id.is_empty()) {
+            dedup.add_command(claim_event_id(&event.id));
+        }
+        let mut claimed = if dedup.cmd_iter().next().is_some() {
+            dedup
+                .query_async::<_, Vec<Option<String>>>(&mut conn)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("保存事件 ID 失败: {}", e)))?
+        } else {
+            Vec::new()
+        }
+        .into_iter();
+
+        let mut pipe = redis::pipe();
+        let mut new_ids = Vec::new();
+        let mut count = 0;
+        for event in &events {
+            if !event.id.is_empty() && claimed.next().flatten().is_none() {
+                debug!("忽略重复事件: {}", event.id);
+                continue;
+            }
+            pipe.cmd("XADD")
+                .arg(EVENTS_STREAM)
+                .arg("MAXLEN")
+                .arg("~")
+                .arg(EVENTS_MAX_LEN)
+                .arg("*")
+                .arg("condition_id")
+                .arg(&event.market.condition_id)
+                .arg("event_type")
+                .arg(event.event_type.name())
+                .arg("payload")
+                .arg(serde_json::to_string(event)?)
+                .ignore();
+            count += 1;
+            if !event.id.is_empty() {
+                new_ids.push(event.id.as_str());
+            }
+        }
+
+        if count == 0 {
+            return Ok(());
+        }
+
+        let pipe = &pipe;
+        let result = self
+            .with_retry("save_events", RetryPolicy::NotSent, |mut conn| async move {
+                pipe.query_async::<_, ()>(&mut conn).await
+            })
+            .await;
+
+        if let Err(e) = result {
+            // 同 `save_event`，只在确定没有写入时撤销去重记录
+            if !new_ids.is_empty() && !may_have_applied(&e) {
+                let keys: Vec<String> = new_ids.iter().map(|id| event_id_key(id)).collect();
+                let _: RedisResult<i64> = conn.del(keys).await;
+            }
+            return Err(ScannerError::StorageError(format!(
+                "批量保存事件失败: {}",
+                e
+            )));
+        }
 
         Ok(())
     }
 
+    /// 获取最近的事件（新事件在前）
+    async fn get_recent_events(&self, limit: i32) -> Result<Vec<MarketEvent>> {
+        let mut conn = self.conn.clone();
+        let reply: StreamRangeReply = conn
+            .xrevrange_count(EVENTS_STREAM, "+", "-", limit.max(0) as usize)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询事件失败: {}", e)))?;
+
+        let mut events = Vec::new();
+        for entry in reply.ids {
+            if let Some(payload) = entry.get::<String>("payload") {
+                events.push(serde_json::from_str(&payload)?);
+            }
+        }
+        Ok(events)
+    }
+
+    /// 按 Stream ID 读取游标之后的事件，游标为最后一个事件的 Stream ID
+    async fn get_events_after(&self, cursor: Option<&str>, limit: i32) -> Result<EventBatch> {
+        // `(` 表示不包含游标本身
+        let start = match cursor {
+            Some(cursor) => format!("({}", cursor),
+            None => "-".to_string(),
+        };
+        let mut conn = self.conn.clone();
+        let reply: StreamRangeReply = conn
+            .xrange_count(EVENTS_STREAM, start, "+", limit.max(0) as usize)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询事件失败: {}", e)))?;
+
+        let mut batch = EventBatch {
+            events: Vec::with_capacity(reply.ids.len()),
+            cursor: cursor.map(str::to_string),
+        };
+        for entry in reply.ids {
+            if let Some(payload) = entry.get::<String>("payload") {
+                batch.events.push(serde_json::from_str(&payload)?);
+            }
+            // 没有 payload 的条目也推进游标，避免反复读到它
+            batch.cursor = Some(entry.id);
+        }
+        Ok(batch)
+    }
+
+    /// 获取单个市场最近的事件（新事件在前），可按事件类型过滤
+    ///
+    /// Stream 不支持按字段查询，从最新的事件开始分页向前扫描，
+    /// 凑够 `limit` 个或扫描完整个 Stream 后返回
+    async fn get_recent_events_for(
+        &self,
+        condition_id: &str,
+        event_type: Option<&str>,
+        limit: i32,
+    ) -> Result<Vec<MarketEvent>> {
+        let limit = limit.max(0) as usize;
+        let mut conn = self.conn.clone();
+        let mut events = Vec::new();
+        let mut end = "+".to_string();
+
+        while events.len() < limit {
+            let reply: StreamRangeReply = conn
+                .xrevrange_count(EVENTS_STREAM, &end, "-", EVENTS_SCAN_BATCH)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("查询事件失败: {}", e)))?;
+            let Some(last) = reply.ids.last() else {
+                break;
+            };
+            // 下一页从本页最早条目之前开始（排他区间）
+            end = format!("({}", last.id);
+            let exhausted = reply.ids.len() < EVENTS_SCAN_BATCH;
+
+            for entry in &reply.ids {
+                if entry.get::<String>("condition_id").as_deref() != Some(condition_id) {
+                    continue;
+                }
+                if let Some(event_type) = event_type {
+                    if entry.get::<String>("event_type").as_deref() != Some(event_type) {
+                        continue;
+                    }
+                }
+                if let Some(payload) = entry.get::<String>("payload") {
+                    events.push(serde_json::from_str(&payload)?);
+                    if events.len() >= limit {
+                        break;
+                    }
+                }
+            }
+
+            if exhausted {
+                break;
+            }
+        }
+
+        Ok(events)
+    }
+
     /// 保存价格历史
-    async fn save_price_history(
+    #[instrument(level = "trace", skip(self, outcome_prices, volume))]
+    async fn save_price_history_at(
         &self,
         condition_id: &str,
         outcome_prices: Option<&str>,
         volume: Option<&str>,
+        timestamp: DateTime<Utc>,
     ) -> Result<()> {
-        let mut conn = self.conn.clone();
-        let now = Utc::now();
-        let timestamp_ms = now.timestamp_millis() as f64;
+        let timestamp_ms = timestamp.timestamp_millis() as f64;
 
         // 价格历史数据
         let history_data = serde_json::json!({
             "outcome_prices": outcome_prices.unwrap_or(""),
             "volume": volume.unwrap_or(""),
-            "timestamp": now.to_rfc3339(),
+            "timestamp": timestamp.to_rfc3339(),
         });
 
-        let history_json =
-            serde_json::to_string(&history_data).map_err(|e| ScannerError::JsonError(e))?;
+        let history_json = serde_json::to_string(&history_data).map_err(ScannerError::JsonError)?;
 
         // 使用 Sorted Set 存储价格历史（按时间戳排序）
         let key = format!("market:{}:price_history", condition_id);
-        let _: () = conn
-            .zadd(&key, &history_json, timestamp_ms)
-            .await
-            .map_err(|e| ScannerError::ConfigError(format!("保存价格历史失败: {}", e)))?;
+        let (key, history_json) = (&key, &history_json);
+        self.with_retry("save_price_history", RetryPolicy::Idempotent, |mut conn| async move {
+            conn.zadd::<_, _, _, ()>(key, history_json, timestamp_ms)
+                .await
+        })
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("保存价格历史失败: {}", e)))?;
 
         Ok(())
     }
@@ -165,11 +718,96 @@ impl Storage for Database {
         let count: i64 = conn
             .scard("markets:all")
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("查询市场总数失败: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("查询市场总数失败: {}", e)))?;
 
         Ok(count)
     }
 
+    /// 市场数和事件数直接用 SCARD/XLEN；按类型统计需要遍历事件 Stream
+    /// （最多约 `EVENTS_MAX_LEN` 条），价格历史用一次 ZCARD pipeline 汇总
+    async fn get_stats(&self) -> Result<DatabaseStats> {
+        let mut conn = self.conn.clone();
+        let market_count: i64 = conn
+            .scard("markets:all")
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询市场总数失败: {}", e)))?;
+        let event_count: i64 = conn
+            .xlen(EVENTS_STREAM)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询事件总数失败: {}", e)))?;
+
+        let mut events_by_type = std::collections::BTreeMap::new();
+        let mut start = "-".to_string();
+        loop {
+            let reply: StreamRangeReply = conn
+                .xrange_count(EVENTS_STREAM, &start, "+", EVENTS_SCAN_BATCH)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("统计事件失败: {}", e)))?;
+            let Some(last) = reply.ids.last() else {
+                break;
+            };
+            start = format!("({}", last.id);
+            for entry in &reply.ids {
+                if let Some(event_type) = entry.get::<String>("event_type") {
+                    *events_by_type.entry(event_type).or_default() += 1;
+                }
+            }
+            if reply.ids.len() < EVENTS_SCAN_BATCH {
+                break;
+            }
+        }
+
+        let oldest: Vec<(String, f64)> = conn
+            .zrange_withscores(MARKETS_UPDATED, 0, 0)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询市场更新时间失败: {}", e)))?;
+        let newest: Vec<(String, f64)> = conn
+            .zrevrange_withscores(MARKETS_UPDATED, 0, 0)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询市场更新时间失败: {}", e)))?;
+        let to_time = |entries: Vec<(String, f64)>| {
+            entries
+                .first()
+                .and_then(|(_, ms)| DateTime::from_timestamp_millis(*ms as i64))
+        };
+
+        let ids: Vec<String> = conn
+            .smembers("markets:all")
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询市场列表失败: {}", e)))?;
+        let mut pipe = redis::pipe();
+        for id in &ids {
+            pipe.zcard(format!("market:{}:price_history", id));
+        }
+        let sizes: Vec<i64> = if ids.is_empty() {
+            Vec::new()
+        } else {
+            pipe.query_async(&mut conn)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("统计价格历史失败: {}", e)))?
+        };
+
+        Ok(DatabaseStats {
+            market_count,
+            event_count,
+            events_by_type,
+            oldest_market_update: to_time(oldest),
+            newest_market_update: to_time(newest),
+            price_history_points: sizes.iter().sum(),
+        })
+    }
+
+    /// 状态保存在市场 hash 中，遍历全部市场后在内存中统计
+    async fn get_market_status_counts(&self) -> Result<HashMap<String, i64>> {
+        let mut markets = Vec::new();
+        for id in self.get_all_market_ids().await? {
+            if let Some(market) = self.get_market(&id).await? {
+                markets.push(market);
+            }
+        }
+        Ok(status_counts(&markets))
+    }
+
     /// 获取特定市场的价格历史
     async fn get_price_history(
         &self,
@@ -183,7 +821,7 @@ impl Storage for Database {
         let results: Vec<String> = conn
             .zrevrange(&key, 0, (limit - 1) as isize)
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("查询价格历史失败: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("查询价格历史失败: {}", e)))?;
 
         let mut history = Vec::new();
         for json_str in results {
@@ -205,85 +843,87 @@ impl Storage for Database {
         Ok(history)
     }
 
-    /// 获取市场详情
-    async fn get_market(&self, condition_id: &str) -> Result<Option<Market>> {
+    /// 计算时间窗口内第一个结果价格的标准差（按 Sorted Set 的时间戳分数取区间）
+    async fn get_price_volatility(
+        &self,
+        condition_id: &str,
+        window: Duration,
+    ) -> Result<Option<f64>> {
         let mut conn = self.conn.clone();
-        let key = format!("market:{}", condition_id);
+        let key = format!("market:{}:price_history", condition_id);
+        let since_ms = window_start(window).timestamp_millis();
 
-        let exists: bool = conn
-            .exists(&key)
+        let results: Vec<String> = conn
+            .zrangebyscore(&key, since_ms, "+inf")
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("Redis 查询失败: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("查询价格波动失败: {}", e)))?;
 
-        if !exists {
+        let prices: Vec<String> = results
+            .iter()
+            .filter_map(|json_str| serde_json::from_str::<serde_json::Value>(json_str).ok())
+            .filter_map(|data| data["outcome_prices"].as_str().map(str::to_string))
+            .collect();
+
+        Ok(price_volatility(prices.iter().map(String::as_str)))
+    }
+
+    /// 获取市场详情
+    async fn get_market(&self, condition_id: &str) -> Result<Option<Market>> {
+        Ok(self
+            .get_market_fields(condition_id)
+            .await?
+            .map(|fields| fields_to_market(&fields)))
+    }
+
+    /// 读取保存时派生的解析字段，缺失时（旧数据或未保存解析字段）从原始字符串解析
+    async fn get_market_parsed(&self, condition_id: &str) -> Result<Option<ParsedMarket>> {
+        let Some(fields) = self.get_market_fields(condition_id).await? else {
             return Ok(None);
-        }
+        };
+        let stored = |field: &str| fields.get(field).filter(|s| !s.is_empty());
+        Ok(Some(ParsedMarket::from_stored(
+            fields_to_market(&fields),
+            stored("outcomes_parsed").and_then(|s| serde_json::from_str(s).ok()),
+            stored("prices_parsed").and_then(|s| serde_json::from_str(s).ok()),
+            stored("volume_num").and_then(|s| s.parse().ok()),
+            stored("liquidity_num").and_then(|s| s.parse().ok()),
+        )))
+    }
 
-        let data: Vec<String> = conn
-            .hgetall(&key)
+    /// 获取最后更新时间晚于指定时间的市场（按 markets:updated 的分数取区间）
+    async fn get_markets_updated_since(&self, since: DateTime<Utc>) -> Result<Vec<Market>> {
+        let mut conn = self.conn.clone();
+        let min = format!("({}", since.timestamp_millis());
+        let ids: Vec<String> = conn
+            .zrangebyscore(MARKETS_UPDATED, min, "+inf")
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("获取市场数据失败: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("查询更新的市场失败: {}", e)))?;
 
-        // Redis HGETALL 返回 [key, value, key, value, ...]
-        let mut map = std::collections::HashMap::new();
-        for i in (0..data.len()).step_by(2) {
-            if i + 1 < data.len() {
-                map.insert(data[i].clone(), data[i + 1].clone());
+        let mut markets = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(market) = self.get_market(&id).await? {
+                markets.push(market);
             }
         }
+        Ok(markets)
+    }
 
-        let market = Market {
-            condition_id: map.get("condition_id").cloned().unwrap_or_default(),
-            question_id: map.get("question_id").and_then(|s| {
-                if s.is_empty() {
-                    None
-                } else {
-                    Some(s.clone())
-                }
-            }),
-            question: map.get("question").cloned().unwrap_or_default(),
-            description: map.get("description").and_then(|s| {
-                if s.is_empty() {
-                    None
-                } else {
-                    Some(s.clone())
-                }
-            }),
-            market_slug: map.get("market_slug").and_then(|s| {
-                if s.is_empty() {
-                    None
-                } else {
-                    Some(s.clone())
-                }
-            }),
-            outcomes: map.get("outcomes").cloned().unwrap_or("[]".to_string()),
-            outcome_prices: Some(
-                map.get("outcome_prices")
-                    .cloned()
-                    .unwrap_or("[]".to_string()),
-            ),
-            volume: map
-                .get("volume")
-                .and_then(|s| if s.is_empty() { None } else { Some(s.clone()) }),
-            liquidity: map.get("liquidity").and_then(|s| {
-                if s.is_empty() {
-                    None
-                } else {
-                    Some(s.clone())
-                }
-            }),
-            end_date: map.get("end_date").and_then(|s| {
-                if s.is_empty() {
-                    None
-                } else {
-                    Some(s.clone())
-                }
-            }),
-            active: map.get("active").and_then(|s| Some(s == "1")),
-            closed: map.get("closed").and_then(|s| Some(s == "1")),
-        };
+    /// 获取最后更新时间不晚于 `older_than` 之前的市场（按 markets:updated 的分数取区间）
+    async fn get_stale_markets(&self, older_than: Duration) -> Result<Vec<Market>> {
+        let mut conn = self.conn.clone();
+        let max = window_start(older_than).timestamp_millis();
+        let ids: Vec<String> = conn
+            .zrangebyscore(MARKETS_UPDATED, "-inf", max)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询停滞的市场失败: {}", e)))?;
 
-        Ok(Some(market))
+        let mut markets = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(market) = self.get_market(&id).await? {
+                markets.push(market);
+            }
+        }
+        Ok(markets)
     }
 
     /// 获取所有市场 ID
@@ -292,8 +932,217 @@ impl Storage for Database {
         let ids: Vec<String> = conn
             .smembers("markets:all")
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("获取市场列表失败: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("获取市场列表失败: {}", e)))?;
 
         Ok(ids)
     }
+
+    /// 读取全部市场后在客户端按成交量排序
+    /// 标签存放在市场 hash 中，遍历全部市场后在内存中过滤
+    async fn get_markets_by_tag(&self, tag: &str) -> Result<Vec<Market>> {
+        let mut markets = Vec::new();
+        for id in self.get_all_market_ids().await? {
+            if let Some(market) = self.get_market(&id).await? {
+                if market.has_tag(tag) {
+                    markets.push(market);
+                }
+            }
+        }
+        Ok(markets)
+    }
+
+    async fn get_top_markets_by_volume(&self, limit: i32) -> Result<Vec<Market>> {
+        let mut markets = Vec::new();
+        for id in self.get_all_market_ids().await? {
+            if let Some(market) = self.get_market(&id).await? {
+                markets.push(market);
+            }
+        }
+        Ok(top_by_volume(markets, limit))
+    }
+
+    async fn save_scan_checkpoint(&self, name: &str, offset: u32) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let _: () = conn
+            .set(format!("scan_checkpoint:{}", name), offset)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("保存扫描进度失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn get_scan_checkpoint(&self, name: &str) -> Result<Option<u32>> {
+        let mut conn = self.conn.clone();
+        let offset: Option<u32> = conn
+            .get(format!("scan_checkpoint:{}", name))
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("读取扫描进度失败: {}", e)))?;
+
+        Ok(offset)
+    }
+
+    async fn clear_scan_checkpoint(&self, name: &str) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let _: () = conn
+            .del(format!("scan_checkpoint:{}", name))
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("清除扫描进度失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn save_price_alert(&self, alert: &PriceAlert) -> Result<()> {
+        let json = serde_json::to_string(alert)?;
+        let mut conn = self.conn.clone();
+        let _: () = conn
+            .hset(PRICE_ALERTS, alert.id(), json)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("保存价格提醒失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn get_price_alerts(&self) -> Result<Vec<PriceAlert>> {
+        let mut conn = self.conn.clone();
+        let values: Vec<String> = conn
+            .hvals(PRICE_ALERTS)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("读取价格提醒失败: {}", e)))?;
+
+        let mut alerts = Vec::with_capacity(values.len());
+        for value in values {
+            match serde_json::from_str(&value) {
+                Ok(alert) => alerts.push(alert),
+                Err(e) => warn!("跳过无法解析的价格提醒: {}", e),
+            }
+        }
+        Ok(alerts)
+    }
+
+    async fn delete_price_alert(&self, id: &str) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let _: () = conn
+            .hdel(PRICE_ALERTS, id)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("删除价格提醒失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn add_to_watchlist(&self, condition_id: &str) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let _: () = conn
+            .sadd(WATCHLIST, condition_id)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("添加关注市场失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn remove_from_watchlist(&self, condition_id: &str) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let _: () = conn
+            .srem(WATCHLIST, condition_id)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("移除关注市场失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn get_watchlist(&self) -> Result<Vec<String>> {
+        let mut conn = self.conn.clone();
+        let mut ids: Vec<String> = conn
+            .smembers(WATCHLIST)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("读取关注列表失败: {}", e)))?;
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_id_claim_expires() {
+        let packed = String::from_utf8(claim_event_id("abc").get_packed_command()).unwrap();
+        let args: Vec<&str> = packed.split("\r\n").filter(|s| !s.starts_with(['*', '$'])).collect();
+        assert_eq!(
+            args[..6],
+            ["SET", "events:id:abc", "1", "NX", "EX", &EVENT_ID_TTL_SECS.to_string()]
+        );
+    }
+
+    /// 假的连接：每次取连接时返回当前的尝试序号
+    struct FakeConnector {
+        attempts: AtomicU64,
+    }
+
+    impl FakeConnector {
+        fn new() -> Self {
+            Self { attempts: AtomicU64::new(0) }
+        }
+    }
+
+    impl Connector for FakeConnector {
+        type Conn = u64;
+
+        fn connection(&self) -> u64 {
+            self.attempts.fetch_add(1, Ordering::Relaxed)
+        }
+    }
+
+    fn io_error(kind: std::io::ErrorKind) -> RedisError {
+        RedisError::from(std::io::Error::from(kind))
+    }
+
+    #[tokio::test]
+    async fn idempotent_write_retried_after_dropped_connection() {
+        let connector = FakeConnector::new();
+        let reconnects = AtomicU64::new(0);
+        let result = run_with_retry(&connector, RetryPolicy::Idempotent, "test", &reconnects, |attempt| async move {
+            if attempt == 0 {
+                Err(io_error(std::io::ErrorKind::ConnectionReset))
+            } else {
+                Ok(attempt)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(connector.attempts.load(Ordering::Relaxed), 2);
+        assert_eq!(reconnects.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn xadd_not_retried_after_dropped_connection() {
+        let connector = FakeConnector::new();
+        let reconnects = AtomicU64::new(0);
+        let result: RedisResult<()> = run_with_retry(&connector, RetryPolicy::NotSent, "test", &reconnects, |_| async {
+            Err(io_error(std::io::ErrorKind::ConnectionReset))
+        })
+        .await;
+        let e = result.unwrap_err();
+        assert!(e.is_connection_dropped());
+        assert!(may_have_applied(&e));
+        assert_eq!(connector.attempts.load(Ordering::Relaxed), 1);
+        assert_eq!(reconnects.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn xadd_retried_after_refused_connection() {
+        let connector = FakeConnector::new();
+        let reconnects = AtomicU64::new(0);
+        let result = run_with_retry(&connector, RetryPolicy::NotSent, "test", &reconnects, |attempt| async move {
+            if attempt == 0 {
+                Err(io_error(std::io::ErrorKind::ConnectionRefused))
+            } else {
+                Ok(attempt)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(connector.attempts.load(Ordering::Relaxed), 2);
+        assert!(!may_have_applied(&io_error(std::io::ErrorKind::ConnectionRefused)));
+    }
 }
@@ -1,108 +1,669 @@
 use crate::error::{Result, ScannerError};
-use crate::storage::Storage;
-use crate::types::Market;
+use crate::storage::{
+    parse_sequence_cursor, price_volatility, window_start, DatabaseStats, EventBatch, Storage,
+};
+use crate::types::{CrossDirection, Market, MarketEvent, ParsedMarket, PriceAlert};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use log::info;
-use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqlitePool, SqlitePoolOptions,
+    SqliteRow,
+};
 use sqlx::Row;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{info, instrument};
+
+/// 当前代码对应的数据库结构版本，即 `apply_migration` 中最后一个迁移的版本号
+pub const LATEST_SCHEMA_VERSION: u32 = 3;
+
+/// SQLite 连接池配置
+///
+/// 默认开启 WAL，读写可以并发进行；`busy_timeout` 让写入在数据库被锁时等待
+/// 而不是立即返回 "database is locked"
+#[derive(Debug, Clone)]
+pub struct SqliteConfig {
+    /// 连接池最大连接数，应不小于扫描器的 `max_concurrent_saves`
+    pub max_connections: u32,
+    /// 是否使用 WAL 日志模式（`PRAGMA journal_mode=WAL`）
+    pub wal: bool,
+    /// 数据库被锁时的最长等待时间（`PRAGMA busy_timeout`）
+    pub busy_timeout: Duration,
+    /// 保存市场时是否同时写入解析后的字段（`outcomes_parsed`、`prices_parsed`、
+    /// `volume_num`、`liquidity_num`），原始字符串总会保存
+    pub store_parsed: bool,
+}
+
+impl Default for SqliteConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 5,
+            wal: true,
+            busy_timeout: Duration::from_secs(5),
+            store_parsed: true,
+        }
+    }
+}
+
+/// `get_price_series_bucketed` 返回的一个时间桶
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceBucket {
+    /// 桶的起始时间（按桶宽对齐到 Unix 纪元）
+    pub start: DateTime<Utc>,
+    /// 桶内价格的平均值
+    pub average: f64,
+    pub min: f64,
+    pub max: f64,
+    /// 桶内的价格记录数
+    pub samples: i64,
+}
 
 pub struct Database {
     pool: SqlitePool,
+    store_parsed: bool,
 }
 
 impl Database {
-    /// 创建数据库连接
+    /// 使用默认配置创建数据库连接
     pub async fn new(database_url: &str) -> Result<Self> {
-        info!("连接数据库: {}", database_url);
+        Self::with_config(database_url, SqliteConfig::default()).await
+    }
+
+    /// 按配置创建数据库连接
+    pub async fn with_config(database_url: &str, config: SqliteConfig) -> Result<Self> {
+        info!("连接数据库: {} ({:?})", database_url, config);
+
+        let journal_mode = if config.wal {
+            SqliteJournalMode::Wal
+        } else {
+            SqliteJournalMode::Delete
+        };
+        let options = SqliteConnectOptions::from_str(database_url)
+            .map_err(|e| ScannerError::StorageError(format!("数据库地址无效: {}", e)))?
+            .journal_mode(journal_mode)
+            .busy_timeout(config.busy_timeout);
 
         let pool = SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect(database_url)
+            .max_connections(config.max_connections)
+            .connect_with(options)
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("数据库连接失败: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("数据库连接失败: {}", e)))?;
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            store_parsed: config.store_parsed,
+        })
     }
-}
 
-#[async_trait]
-impl Storage for Database {
-    /// 初始化数据库表
-    async fn init(&self) -> Result<()> {
-        info!("初始化数据库表结构");
+    /// 数据库当前的结构版本，尚未执行过迁移时为 0
+    pub async fn schema_version(&self) -> Result<u32> {
+        let exists: bool = sqlx::query(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version')",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map(|row| row.get(0))
+        .map_err(|e| ScannerError::StorageError(format!("检查 schema_version 表失败: {}", e)))?;
+        if !exists {
+            return Ok(0);
+        }
 
-        // 创建市场表
+        let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询数据库版本失败: {}", e)))?;
+        Ok(version.unwrap_or(0) as u32)
+    }
+
+    /// 按顺序应用尚未执行的迁移，返回本次应用的版本号
+    ///
+    /// 每个迁移在独立的事务中执行，并在 `schema_version` 表中记录版本号和执行时间，
+    /// 重复调用是安全的。数据库版本高于 `LATEST_SCHEMA_VERSION`（由更新的版本创建）时返回错误
+    pub async fn migrate(&self) -> Result<Vec<u32>> {
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS markets (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                condition_id TEXT NOT NULL UNIQUE,
-                question_id TEXT,
-                question TEXT NOT NULL,
-                description TEXT,
-                market_slug TEXT,
-                outcomes TEXT NOT NULL,
-                outcome_prices TEXT NOT NULL,
-                volume TEXT,
-                liquidity TEXT,
-                end_date TEXT,
-                active INTEGER,
-                closed INTEGER,
-                first_seen_at TEXT NOT NULL,
-                last_updated_at TEXT NOT NULL,
-                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY,
+                applied_at TEXT NOT NULL
             )
             "#,
         )
         .execute(&self.pool)
         .await
-        .map_err(|e| ScannerError::ConfigError(format!("创建 markets 表失败: {}", e)))?;
+        .map_err(|e| ScannerError::StorageError(format!("创建 schema_version 表失败: {}", e)))?;
 
-        // 创建价格历史表
-        sqlx::query(
+        let current = self.schema_version().await?;
+        if current > LATEST_SCHEMA_VERSION {
+            return Err(ScannerError::StorageError(format!(
+                "数据库结构版本 {} 高于当前支持的版本 {}，请升级程序",
+                current, LATEST_SCHEMA_VERSION
+            )));
+        }
+
+        let mut applied = Vec::new();
+        for version in current + 1..=LATEST_SCHEMA_VERSION {
+            info!("应用数据库迁移 {}", version);
+            let mut tx = self.pool.begin().await.map_err(|e| {
+                ScannerError::StorageError(format!("开始迁移事务失败: {}", e))
+            })?;
+            apply_migration(&mut tx, version).await?;
+            sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+                .bind(version as i64)
+                .bind(Utc::now().to_rfc3339())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("记录数据库版本失败: {}", e)))?;
+            tx.commit().await.map_err(|e| {
+                ScannerError::StorageError(format!("提交数据库迁移 {} 失败: {}", version, e))
+            })?;
+            applied.push(version);
+        }
+
+        Ok(applied)
+    }
+
+    /// 全文搜索市场问题和描述（仅 SQLite 后端支持）
+    ///
+    /// `query` 使用 FTS5 查询语法，例如 `Bitcoin`、`"Super Bowl"`、`Trump OR Biden`，
+    /// 结果按相关度排序。其他后端可以对 `get_market` 的结果做简单的子串匹配。
+    pub async fn search_markets(&self, query: &str, limit: i32) -> Result<Vec<Market>> {
+        let rows = sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS price_history (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                condition_id TEXT NOT NULL,
-                outcome_prices TEXT NOT NULL,
-                volume TEXT,
-                timestamp TEXT NOT NULL,
-                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (condition_id) REFERENCES markets(condition_id)
+            SELECT m.* FROM markets_fts
+            JOIN markets m ON m.id = markets_fts.rowid
+            WHERE markets_fts MATCH ?
+            ORDER BY markets_fts.rank
+            LIMIT ?
+            "#,
+        )
+        .bind(query)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("全文搜索失败: {}", e)))?;
+
+        Ok(rows.iter().map(row_to_market).collect())
+    }
+
+    /// 按时间桶聚合某个结果的价格（仅 SQLite 后端支持），旧桶在前
+    ///
+    /// 分桶和聚合都在 SQL 中完成：用 JSON1 从 `outcome_prices` 取出第
+    /// `outcome_index` 个价格，按 `bucket` 宽度对齐时间后求平均、最小和最大值。
+    /// 没有记录的桶不会出现在结果中，价格缺失或无法解析的记录被忽略。
+    /// 需要临时分析时也可以直接查询 `price_points` 视图（每个结果一行的数值价格）
+    pub async fn get_price_series_bucketed(
+        &self,
+        condition_id: &str,
+        outcome_index: usize,
+        bucket: Duration,
+    ) -> Result<Vec<PriceBucket>> {
+        let width = bucket.as_secs() as i64;
+        if width == 0 {
+            return Err(ScannerError::StorageError(
+                "时间桶宽度不能小于 1 秒".to_string(),
+            ));
+        }
+        let path = format!("$[{}]", outcome_index);
+        let rows: Vec<(i64, f64, f64, f64, i64)> = sqlx::query_as(
+            r#"
+            SELECT bucket, AVG(price), MIN(price), MAX(price), COUNT(*)
+            FROM (
+                SELECT CAST(strftime('%s', timestamp) AS INTEGER) / ?1 * ?1 AS bucket,
+                       CAST(json_extract(outcome_prices, ?2) AS REAL) AS price
+                FROM price_history
+                WHERE condition_id = ?3
+                  AND json_valid(outcome_prices)
+                  AND json_type(outcome_prices, ?2) IN ('text', 'real', 'integer')
             )
+            GROUP BY bucket
+            ORDER BY bucket ASC
             "#,
         )
-        .execute(&self.pool)
+        .bind(width)
+        .bind(&path)
+        .bind(condition_id)
+        .fetch_all(&self.pool)
         .await
-        .map_err(|e| ScannerError::ConfigError(format!("创建 price_history 表失败: {}", e)))?;
+        .map_err(|e| ScannerError::StorageError(format!("查询价格时间序列失败: {}", e)))?;
 
-        // 创建索引
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_markets_condition_id ON markets(condition_id)")
-            .execute(&self.pool)
+        Ok(rows
+            .into_iter()
+            .filter_map(|(start, average, min, max, samples)| {
+                Some(PriceBucket {
+                    start: DateTime::from_timestamp(start, 0)?,
+                    average,
+                    min,
+                    max,
+                    samples,
+                })
+            })
+            .collect())
+    }
+}
+
+/// 执行指定版本的迁移
+///
+/// 已发布的迁移不能再修改；表结构变化时追加新的版本并更新 `LATEST_SCHEMA_VERSION`
+async fn apply_migration(conn: &mut SqliteConnection, version: u32) -> Result<()> {
+    match version {
+        1 => migrate_v1(conn).await,
+        2 => migrate_v2(conn).await,
+        3 => migrate_v3(conn).await,
+        _ => Err(ScannerError::StorageError(format!(
+            "未知的数据库迁移版本: {}",
+            version
+        ))),
+    }
+}
+
+/// 迁移 1：初始表结构
+///
+/// 引入版本表之前的数据库也会执行这一步，所以所有语句都必须可以重复执行
+/// （`IF NOT EXISTS`，补充旧版本缺少的列时忽略列已存在的错误）
+async fn migrate_v1(conn: &mut SqliteConnection) -> Result<()> {
+    // 创建市场表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS markets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            condition_id TEXT NOT NULL UNIQUE,
+            question_id TEXT,
+            question TEXT NOT NULL,
+            description TEXT,
+            market_slug TEXT,
+            outcomes TEXT NOT NULL,
+            outcome_prices TEXT NOT NULL,
+            volume TEXT,
+            liquidity TEXT,
+            end_date TEXT,
+            active INTEGER,
+            closed INTEGER,
+            tags TEXT NOT NULL DEFAULT '[]',
+            first_seen_at TEXT NOT NULL,
+            last_updated_at TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| ScannerError::StorageError(format!("创建 markets 表失败: {}", e)))?;
+
+    // 旧版本创建的 markets 表没有 tags 列（列已存在时忽略错误）
+    sqlx::query("ALTER TABLE markets ADD COLUMN tags TEXT NOT NULL DEFAULT '[]'")
+        .execute(&mut *conn)
+        .await
+        .ok();
+
+    // 创建市场标签表，用于按标签查询（markets.tags 保存原始列表用于还原 Market）
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS market_tags (
+            condition_id TEXT NOT NULL,
+            tag TEXT NOT NULL COLLATE NOCASE,
+            PRIMARY KEY (condition_id, tag),
+            FOREIGN KEY (condition_id) REFERENCES markets(condition_id)
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| ScannerError::StorageError(format!("创建 market_tags 表失败: {}", e)))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_market_tags_tag ON market_tags(tag)")
+        .execute(&mut *conn)
+        .await
+        .ok();
+
+    // 创建价格历史表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS price_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            condition_id TEXT NOT NULL,
+            outcome_prices TEXT NOT NULL,
+            volume TEXT,
+            timestamp TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (condition_id) REFERENCES markets(condition_id)
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| ScannerError::StorageError(format!("创建 price_history 表失败: {}", e)))?;
+
+    // 数值化的价格历史：每条记录的每个结果一行，便于直接用 SQL 做时间序列分析
+    sqlx::query(
+        r#"
+        CREATE VIEW IF NOT EXISTS price_points AS
+        SELECT p.condition_id,
+               p.timestamp,
+               CAST(j.key AS INTEGER) AS outcome_index,
+               CAST(j.value AS REAL) AS price
+        FROM price_history p, json_each(p.outcome_prices) j
+        WHERE json_valid(p.outcome_prices)
+        "#,
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| ScannerError::StorageError(format!("创建 price_points 视图失败: {}", e)))?;
+
+    // 创建索引
+    // 创建事件表（payload 为完整的 MarketEvent JSON）
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS market_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            event_id TEXT UNIQUE,
+            condition_id TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            timestamp TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| ScannerError::StorageError(format!("创建 market_events 表失败: {}", e)))?;
+
+    // 旧版本创建的 market_events 表没有 event_id 列（列已存在时忽略错误）
+    sqlx::query("ALTER TABLE market_events ADD COLUMN event_id TEXT")
+        .execute(&mut *conn)
+        .await
+        .ok();
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_market_events_event_id ON market_events(event_id)",
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| ScannerError::StorageError(format!("创建事件 ID 索引失败: {}", e)))?;
+
+    // 创建问题全文索引（FTS5 外部内容表，通过触发器与 markets 表保持同步）
+    let fts_exists: bool = sqlx::query(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'markets_fts')",
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map(|row| row.get(0))
+    .map_err(|e| ScannerError::StorageError(format!("检查 markets_fts 表失败: {}", e)))?;
+
+    for statement in [
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS markets_fts USING fts5(
+            question, description, content='markets', content_rowid='id'
+        )
+        "#,
+        r#"
+        CREATE TRIGGER IF NOT EXISTS markets_fts_insert AFTER INSERT ON markets BEGIN
+            INSERT INTO markets_fts(rowid, question, description)
+            VALUES (new.id, new.question, new.description);
+        END
+        "#,
+        r#"
+        CREATE TRIGGER IF NOT EXISTS markets_fts_delete AFTER DELETE ON markets BEGIN
+            INSERT INTO markets_fts(markets_fts, rowid, question, description)
+            VALUES ('delete', old.id, old.question, old.description);
+        END
+        "#,
+        r#"
+        CREATE TRIGGER IF NOT EXISTS markets_fts_update AFTER UPDATE ON markets BEGIN
+            INSERT INTO markets_fts(markets_fts, rowid, question, description)
+            VALUES ('delete', old.id, old.question, old.description);
+            INSERT INTO markets_fts(rowid, question, description)
+            VALUES (new.id, new.question, new.description);
+        END
+        "#,
+    ] {
+        sqlx::query(statement)
+            .execute(&mut *conn)
             .await
-            .ok();
+            .map_err(|e| ScannerError::StorageError(format!("创建全文索引失败: {}", e)))?;
+    }
+
+    // 已有数据库首次创建索引时，为现有市场建立索引
+    if !fts_exists {
+        sqlx::query("INSERT INTO markets_fts(markets_fts) VALUES ('rebuild')")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("重建全文索引失败: {}", e)))?;
+    }
+
+    // 创建全量扫描进度表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS scan_checkpoint (
+            name TEXT PRIMARY KEY,
+            next_offset INTEGER NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| ScannerError::StorageError(format!("创建 scan_checkpoint 表失败: {}", e)))?;
+
+    // 创建价格提醒表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS price_alerts (
+            id TEXT PRIMARY KEY,
+            condition_id TEXT NOT NULL,
+            outcome_index INTEGER NOT NULL,
+            threshold REAL NOT NULL,
+            direction TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| ScannerError::StorageError(format!("创建 price_alerts 表失败: {}", e)))?;
+
+    // 旧版本创建的 price_alerts 表没有 outcome_name 列（列已存在时忽略错误）
+    sqlx::query("ALTER TABLE price_alerts ADD COLUMN outcome_name TEXT")
+        .execute(&mut *conn)
+        .await
+        .ok();
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_price_history_condition_id ON price_history(condition_id)")
+    // 创建关注列表表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS watchlist (
+            condition_id TEXT PRIMARY KEY,
+            added_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| ScannerError::StorageError(format!("创建 watchlist 表失败: {}", e)))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_markets_condition_id ON markets(condition_id)")
+        .execute(&mut *conn)
+        .await
+        .ok();
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_markets_last_updated_at ON markets(last_updated_at)",
+    )
+    .execute(&mut *conn)
+    .await
+    .ok();
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_price_history_condition_id ON price_history(condition_id)")
+        .execute(&mut *conn)
+        .await
+        .ok();
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_market_events_condition_id ON market_events(condition_id)")
+        .execute(&mut *conn)
+        .await
+        .ok();
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_market_events_timestamp ON market_events(timestamp)",
+    )
+    .execute(&mut *conn)
+    .await
+    .ok();
+
+    Ok(())
+}
+
+/// 迁移 2：记录市场的结算状态和获胜结果
+async fn migrate_v2(conn: &mut SqliteConnection) -> Result<()> {
+    for statement in [
+        "ALTER TABLE markets ADD COLUMN uma_resolution_status TEXT",
+        "ALTER TABLE markets ADD COLUMN resolved_outcome INTEGER",
+        "CREATE INDEX IF NOT EXISTS idx_markets_resolved ON markets(resolved_outcome, last_updated_at)",
+    ] {
+        sqlx::query(statement)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("添加结算字段失败: {}", e)))?;
+    }
+
+    Ok(())
+}
+
+/// 迁移 3：在原始字符串之外保存解析后的结果、价格和数值字段
+///
+/// 解析字段在保存时派生，已有的行在下次保存前为 NULL，读取时回退到解析原始字符串
+async fn migrate_v3(conn: &mut SqliteConnection) -> Result<()> {
+    for statement in [
+        "ALTER TABLE markets ADD COLUMN outcomes_parsed TEXT",
+        "ALTER TABLE markets ADD COLUMN prices_parsed TEXT",
+        "ALTER TABLE markets ADD COLUMN volume_num REAL",
+        "ALTER TABLE markets ADD COLUMN liquidity_num REAL",
+        "CREATE INDEX IF NOT EXISTS idx_markets_volume_num ON markets(volume_num)",
+    ] {
+        sqlx::query(statement)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("添加解析字段失败: {}", e)))?;
+    }
+
+    Ok(())
+}
+
+/// 保存时从原始字符串派生的解析字段，`prices_parsed` 为数值的 JSON 数组，可直接用 JSON1 查询
+#[derive(Default)]
+struct ParsedColumns {
+    outcomes: Option<String>,
+    prices: Option<String>,
+    volume: Option<f64>,
+    liquidity: Option<f64>,
+}
+
+impl ParsedColumns {
+    fn derive(market: &Market) -> Result<Self> {
+        Ok(Self {
+            outcomes: market
+                .outcome_list()
+                .map(|outcomes| serde_json::to_string(&outcomes))
+                .transpose()?,
+            prices: market
+                .prices()
+                .map(|prices| serde_json::to_string(&prices))
+                .transpose()?,
+            volume: market.volume_value(),
+            liquidity: market.liquidity_value(),
+        })
+    }
+}
+
+/// 将 markets 表的一行转换为 ParsedMarket，解析字段为 NULL 时从原始字符串解析
+fn row_to_parsed_market(row: &SqliteRow) -> ParsedMarket {
+    let json = |column: &str| row.try_get::<Option<String>, _>(column).ok().flatten();
+    let number = |column: &str| row.try_get::<Option<f64>, _>(column).ok().flatten();
+    ParsedMarket::from_stored(
+        row_to_market(row),
+        json("outcomes_parsed").and_then(|s| serde_json::from_str(&s).ok()),
+        json("prices_parsed").and_then(|s| serde_json::from_str(&s).ok()),
+        number("volume_num"),
+        number("liquidity_num"),
+    )
+}
+
+/// 将 markets 表的一行转换为 Market
+fn row_to_market(row: &SqliteRow) -> Market {
+    Market {
+        condition_id: row.get("condition_id"),
+        question_id: row.get("question_id"),
+        question: row.get("question"),
+        description: row.get("description"),
+        market_slug: row.get("market_slug"),
+        outcomes: row.get("outcomes"),
+        outcome_prices: row.get("outcome_prices"),
+        volume: row.get("volume"),
+        liquidity: row.get("liquidity"),
+        end_date: row.get("end_date"),
+        active: row.get::<Option<i32>, _>("active").map(|v| v != 0),
+        closed: row.get::<Option<i32>, _>("closed").map(|v| v != 0),
+        tags: row
+            .try_get::<String, _>("tags")
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        uma_resolution_status: row.try_get("uma_resolution_status").ok().flatten(),
+        resolved_outcome: row
+            .try_get::<Option<i64>, _>("resolved_outcome")
+            .ok()
+            .flatten()
+            .map(|i| i as usize),
+        ..Default::default()
+    }
+}
+
+#[async_trait]
+impl Storage for Database {
+    /// 初始化数据库表
+    async fn init(&self) -> Result<()> {
+        info!("初始化数据库表结构");
+
+        let applied = self.migrate().await?;
+        if !applied.is_empty() {
+            info!("已应用数据库迁移: {:?}", applied);
+        }
+
+        info!("数据库表结构初始化完成（版本 {}）", LATEST_SCHEMA_VERSION);
+        Ok(())
+    }
+
+    /// 执行 `SELECT 1` 检查数据库是否可用
+    async fn health_check(&self) -> Result<()> {
+        sqlx::query("SELECT 1")
             .execute(&self.pool)
             .await
-            .ok();
+            .map_err(|e| ScannerError::StorageError(format!("数据库健康检查失败: {}", e)))?;
 
-        info!("数据库表结构初始化完成");
         Ok(())
     }
 
+    #[instrument(level = "trace", skip_all, fields(condition_id = %market.condition_id))]
     async fn save_market(&self, market: &Market) -> Result<()> {
         self.save_markets(vec![market.clone()]).await
     }
 
+    #[instrument(level = "debug", skip_all, fields(count = markets.len()))]
     async fn save_markets(&self, markets: Vec<Market>) -> Result<()> {
         let mut tx = self.pool.begin().await.map_err(|e| {
-            ScannerError::ConfigError(format!("Failed to start transaction: {}", e))
+            ScannerError::StorageError(format!("Failed to start transaction: {}", e))
         })?;
         let now = Utc::now().to_rfc3339();
 
         for market in markets {
+            let tags = serde_json::to_string(&market.tags)?;
+            let parsed = if self.store_parsed {
+                ParsedColumns::derive(&market)?
+            } else {
+                ParsedColumns::default()
+            };
+
             // 检查市场是否已存在
             let exists: bool =
                 sqlx::query("SELECT EXISTS(SELECT 1 FROM markets WHERE condition_id = ?)")
@@ -128,6 +689,13 @@ impl Storage for Database {
                         end_date = ?,
                         active = ?,
                         closed = ?,
+                        tags = ?,
+                        uma_resolution_status = ?,
+                        resolved_outcome = ?,
+                        outcomes_parsed = ?,
+                        prices_parsed = ?,
+                        volume_num = ?,
+                        liquidity_num = ?,
                         last_updated_at = ?
                     WHERE condition_id = ?
                     "#,
@@ -143,11 +711,18 @@ impl Storage for Database {
                 .bind(&market.end_date)
                 .bind(market.active.map(|b| b as i32))
                 .bind(market.closed.map(|b| b as i32))
+                .bind(&tags)
+                .bind(&market.uma_resolution_status)
+                .bind(market.resolved_outcome.map(|i| i as i64))
+                .bind(&parsed.outcomes)
+                .bind(&parsed.prices)
+                .bind(parsed.volume)
+                .bind(parsed.liquidity)
                 .bind(&now)
                 .bind(&market.condition_id)
                 .execute(&mut *tx)
                 .await
-                .map_err(|e| ScannerError::ConfigError(format!("更新市场失败: {}", e)))?;
+                .map_err(|e| ScannerError::StorageError(format!("更新市场失败: {}", e)))?;
             } else {
                 // 插入新市场
                 sqlx::query(
@@ -155,8 +730,10 @@ impl Storage for Database {
                     INSERT INTO markets (
                         condition_id, question_id, question, description, market_slug,
                         outcomes, outcome_prices, volume, liquidity, end_date,
-                        active, closed, first_seen_at, last_updated_at
-                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                        active, closed, tags, uma_resolution_status, resolved_outcome,
+                        outcomes_parsed, prices_parsed, volume_num, liquidity_num,
+                        first_seen_at, last_updated_at
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
                     "#,
                 )
                 .bind(&market.condition_id)
@@ -171,29 +748,185 @@ impl Storage for Database {
                 .bind(&market.end_date)
                 .bind(market.active.map(|b| b as i32))
                 .bind(market.closed.map(|b| b as i32))
+                .bind(&tags)
+                .bind(&market.uma_resolution_status)
+                .bind(market.resolved_outcome.map(|i| i as i64))
+                .bind(&parsed.outcomes)
+                .bind(&parsed.prices)
+                .bind(parsed.volume)
+                .bind(parsed.liquidity)
                 .bind(&now)
                 .bind(&now)
                 .execute(&mut *tx)
                 .await
-                .map_err(|e| ScannerError::ConfigError(format!("插入市场失败: {}", e)))?;
+                .map_err(|e| ScannerError::StorageError(format!("插入市场失败: {}", e)))?;
+            }
+
+            // 重写该市场的标签
+            sqlx::query("DELETE FROM market_tags WHERE condition_id = ?")
+                .bind(&market.condition_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("更新市场标签失败: {}", e)))?;
+            for tag in &market.tags {
+                sqlx::query("INSERT OR IGNORE INTO market_tags (condition_id, tag) VALUES (?, ?)")
+                    .bind(&market.condition_id)
+                    .bind(tag)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| ScannerError::StorageError(format!("更新市场标签失败: {}", e)))?;
             }
         }
 
         tx.commit().await.map_err(|e| {
-            ScannerError::ConfigError(format!("Failed to commit transaction: {}", e))
+            ScannerError::StorageError(format!("Failed to commit transaction: {}", e))
         })?;
 
         Ok(())
     }
 
+    /// 保存市场事件，event_id 已存在时忽略
+    #[instrument(level = "trace", skip_all, fields(condition_id = %event.market.condition_id, event_id = %event.id))]
+    async fn save_event(&self, event: &MarketEvent) -> Result<()> {
+        let payload = serde_json::to_string(event)?;
+        // 没有 ID 的事件存为 NULL，不参与去重
+        let event_id = Some(event.id.as_str()).filter(|id| !id.is_empty());
+
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO market_events (event_id, condition_id, event_type, payload, timestamp)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(event_id)
+        .bind(&event.market.condition_id)
+        .bind(event.event_type.name())
+        .bind(&payload)
+        .bind(event.timestamp.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("保存事件失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 在一个事务中批量保存事件，event_id 已存在的事件被忽略
+    #[instrument(level = "debug", skip_all, fields(count = events.len()))]
+    async fn save_events(&self, events: Vec<MarketEvent>) -> Result<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("开启事务失败: {}", e)))?;
+
+        for event in &events {
+            let payload = serde_json::to_string(event)?;
+            let event_id = Some(event.id.as_str()).filter(|id| !id.is_empty());
+
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO market_events (event_id, condition_id, event_type, payload, timestamp)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(event_id)
+            .bind(&event.market.condition_id)
+            .bind(event.event_type.name())
+            .bind(&payload)
+            .bind(event.timestamp.to_rfc3339())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("保存事件失败: {}", e)))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("提交事务失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 获取最近的事件（新事件在前）
+    async fn get_recent_events(&self, limit: i32) -> Result<Vec<MarketEvent>> {
+        let rows = sqlx::query("SELECT payload FROM market_events ORDER BY id DESC LIMIT ?")
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询事件失败: {}", e)))?;
+
+        rows.iter()
+            .map(|row| {
+                let payload: String = row.get("payload");
+                serde_json::from_str(&payload).map_err(ScannerError::JsonError)
+            })
+            .collect()
+    }
+
+    /// 按自增行 ID 读取游标之后的事件，游标为最后一个事件的行 ID
+    async fn get_events_after(&self, cursor: Option<&str>, limit: i32) -> Result<EventBatch> {
+        let after = parse_sequence_cursor(cursor)?;
+        let rows =
+            sqlx::query("SELECT id, payload FROM market_events WHERE id > ? ORDER BY id LIMIT ?")
+                .bind(after as i64)
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("查询事件失败: {}", e)))?;
+
+        let mut batch = EventBatch {
+            events: Vec::with_capacity(rows.len()),
+            cursor: cursor.map(str::to_string),
+        };
+        for row in rows {
+            let id: i64 = row.get("id");
+            let payload: String = row.get("payload");
+            batch.events.push(serde_json::from_str(&payload)?);
+            batch.cursor = Some(id.to_string());
+        }
+        Ok(batch)
+    }
+
+    /// 获取单个市场最近的事件（新事件在前），可按事件类型过滤
+    async fn get_recent_events_for(
+        &self,
+        condition_id: &str,
+        event_type: Option<&str>,
+        limit: i32,
+    ) -> Result<Vec<MarketEvent>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT payload FROM market_events
+            WHERE condition_id = ? AND (? IS NULL OR event_type = ?)
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(condition_id)
+        .bind(event_type)
+        .bind(event_type)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("查询事件失败: {}", e)))?;
+
+        rows.iter()
+            .map(|row| {
+                let payload: String = row.get("payload");
+                serde_json::from_str(&payload).map_err(ScannerError::JsonError)
+            })
+            .collect()
+    }
+
     /// 保存价格历史
-    async fn save_price_history(
+    #[instrument(level = "trace", skip(self, outcome_prices, volume))]
+    async fn save_price_history_at(
         &self,
         condition_id: &str,
         outcome_prices: Option<&str>,
         volume: Option<&str>,
+        timestamp: DateTime<Utc>,
     ) -> Result<()> {
-        let now = Utc::now().to_rfc3339();
+        let timestamp = timestamp.to_rfc3339();
 
         sqlx::query(
             r#"
@@ -204,10 +937,10 @@ impl Storage for Database {
         .bind(condition_id)
         .bind(outcome_prices.unwrap_or(""))
         .bind(volume)
-        .bind(&now)
+        .bind(&timestamp)
         .execute(&self.pool)
         .await
-        .map_err(|e| ScannerError::ConfigError(format!("保存价格历史失败: {}", e)))?;
+        .map_err(|e| ScannerError::StorageError(format!("保存价格历史失败: {}", e)))?;
 
         Ok(())
     }
@@ -217,11 +950,81 @@ impl Storage for Database {
         let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM markets")
             .fetch_one(&self.pool)
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("查询市场总数失败: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("查询市场总数失败: {}", e)))?;
 
         Ok(count.0)
     }
 
+    /// 用三条聚合查询统计市场、事件和价格历史
+    async fn get_stats(&self) -> Result<DatabaseStats> {
+        let (market_count, oldest, newest): (i64, Option<String>, Option<String>) = sqlx::query_as(
+            "SELECT COUNT(*), MIN(last_updated_at), MAX(last_updated_at) FROM markets",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("统计市场失败: {}", e)))?;
+
+        let by_type: Vec<(String, i64)> =
+            sqlx::query_as("SELECT event_type, COUNT(*) FROM market_events GROUP BY event_type")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("统计事件失败: {}", e)))?;
+
+        let (price_history_points,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM price_history")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("统计价格历史失败: {}", e)))?;
+
+        let parse = |ts: Option<String>| {
+            ts.and_then(|ts| DateTime::parse_from_rfc3339(&ts).ok())
+                .map(|ts| ts.with_timezone(&Utc))
+        };
+        Ok(DatabaseStats {
+            market_count,
+            event_count: by_type.iter().map(|(_, count)| count).sum(),
+            events_by_type: by_type.into_iter().collect(),
+            oldest_market_update: parse(oldest),
+            newest_market_update: parse(newest),
+            price_history_points,
+        })
+    }
+
+    async fn get_market_status_counts(&self) -> Result<HashMap<String, i64>> {
+        let by_status: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            SELECT CASE
+                       WHEN closed = 1 THEN 'closed'
+                       WHEN active = 0 THEN 'inactive'
+                       ELSE 'active'
+                   END AS status,
+                   COUNT(*)
+            FROM markets
+            GROUP BY status
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("统计市场状态失败: {}", e)))?;
+
+        let (resolved,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM markets WHERE resolved_outcome IS NOT NULL")
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("统计市场状态失败: {}", e)))?;
+
+        let mut counts: HashMap<String, i64> = ["active", "inactive", "closed"]
+            .into_iter()
+            .map(|key| (key.to_string(), 0))
+            .collect();
+        counts.insert(
+            "total".to_string(),
+            by_status.iter().map(|(_, count)| count).sum(),
+        );
+        counts.extend(by_status);
+        counts.insert("resolved".to_string(), resolved);
+        Ok(counts)
+    }
+
     /// 获取特定市场的价格历史
     async fn get_price_history(
         &self,
@@ -241,7 +1044,7 @@ impl Storage for Database {
         .bind(limit)
         .fetch_all(&self.pool)
         .await
-        .map_err(|e| ScannerError::ConfigError(format!("查询价格历史失败: {}", e)))?;
+        .map_err(|e| ScannerError::StorageError(format!("查询价格历史失败: {}", e)))?;
 
         let mut history = Vec::new();
         for row in rows {
@@ -258,33 +1061,93 @@ impl Storage for Database {
         Ok(history)
     }
 
+    /// 计算时间窗口内第一个结果价格的标准差
+    async fn get_price_volatility(
+        &self,
+        condition_id: &str,
+        window: Duration,
+    ) -> Result<Option<f64>> {
+        // timestamp 以 RFC 3339 (UTC) 存储，可以直接按字符串比较
+        let since = window_start(window).to_rfc3339();
+        let prices: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT outcome_prices
+            FROM price_history
+            WHERE condition_id = ? AND timestamp >= ?
+            ORDER BY timestamp ASC
+            "#,
+        )
+        .bind(condition_id)
+        .bind(&since)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("查询价格波动失败: {}", e)))?;
+
+        Ok(price_volatility(prices.iter().map(|(p,)| p.as_str())))
+    }
+
     /// 获取市场详情
     async fn get_market(&self, condition_id: &str) -> Result<Option<Market>> {
         let row = sqlx::query("SELECT * FROM markets WHERE condition_id = ?")
             .bind(condition_id)
             .fetch_optional(&self.pool)
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("查询市场失败: {}", e)))?;
-
-        if let Some(row) = row {
-            let market = Market {
-                condition_id: row.get("condition_id"),
-                question_id: row.get("question_id"),
-                question: row.get("question"),
-                description: row.get("description"),
-                market_slug: row.get("market_slug"),
-                outcomes: row.get("outcomes"),
-                outcome_prices: row.get("outcome_prices"),
-                volume: row.get("volume"),
-                liquidity: row.get("liquidity"),
-                end_date: row.get("end_date"),
-                active: row.get::<Option<i32>, _>("active").map(|v| v != 0),
-                closed: row.get::<Option<i32>, _>("closed").map(|v| v != 0),
-            };
-            Ok(Some(market))
-        } else {
-            Ok(None)
-        }
+            .map_err(|e| ScannerError::StorageError(format!("查询市场失败: {}", e)))?;
+
+        Ok(row.as_ref().map(row_to_market))
+    }
+
+    /// 读取保存时派生的解析字段，迁移前保存、尚未更新过的市场从原始字符串解析
+    async fn get_market_parsed(&self, condition_id: &str) -> Result<Option<ParsedMarket>> {
+        let row = sqlx::query("SELECT * FROM markets WHERE condition_id = ?")
+            .bind(condition_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询市场失败: {}", e)))?;
+
+        Ok(row.as_ref().map(row_to_parsed_market))
+    }
+
+    /// 获取 last_updated_at 晚于指定时间的市场
+    async fn get_markets_updated_since(&self, since: DateTime<Utc>) -> Result<Vec<Market>> {
+        // last_updated_at 以 RFC 3339 (UTC) 存储，可以直接按字符串比较
+        let rows = sqlx::query(
+            "SELECT * FROM markets WHERE last_updated_at > ? ORDER BY last_updated_at ASC",
+        )
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("查询更新的市场失败: {}", e)))?;
+
+        Ok(rows.iter().map(row_to_market).collect())
+    }
+
+    async fn get_stale_markets(&self, older_than: Duration) -> Result<Vec<Market>> {
+        let rows = sqlx::query(
+            "SELECT * FROM markets WHERE last_updated_at <= ? ORDER BY last_updated_at ASC",
+        )
+        .bind(window_start(older_than).to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("查询停滞的市场失败: {}", e)))?;
+
+        Ok(rows.iter().map(row_to_market).collect())
+    }
+
+    async fn get_resolved_markets_since(&self, since: DateTime<Utc>) -> Result<Vec<Market>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM markets
+            WHERE resolved_outcome IS NOT NULL AND last_updated_at > ?
+            ORDER BY last_updated_at ASC
+            "#,
+        )
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("查询已结算的市场失败: {}", e)))?;
+
+        Ok(rows.iter().map(row_to_market).collect())
     }
 
     /// 获取所有市场 ID
@@ -292,9 +1155,215 @@ impl Storage for Database {
         let rows = sqlx::query("SELECT condition_id FROM markets")
             .fetch_all(&self.pool)
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("获取市场列表失败: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("获取市场列表失败: {}", e)))?;
 
         let ids = rows.iter().map(|row| row.get("condition_id")).collect();
         Ok(ids)
     }
+
+    /// 通过 market_tags 表按标签查询市场（忽略大小写）
+    async fn get_markets_by_tag(&self, tag: &str) -> Result<Vec<Market>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT m.* FROM market_tags t
+            JOIN markets m ON m.condition_id = t.condition_id
+            WHERE t.tag = ?
+            "#,
+        )
+        .bind(tag)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("按标签查询市场失败: {}", e)))?;
+
+        Ok(rows.iter().map(row_to_market).collect())
+    }
+
+    /// 按成交量降序获取市场，空成交量视为 0
+    async fn get_top_markets_by_volume(&self, limit: i32) -> Result<Vec<Market>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM markets
+            ORDER BY CAST(COALESCE(NULLIF(volume, ''), '0') AS REAL) DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("查询成交量排行失败: {}", e)))?;
+
+        Ok(rows.iter().map(row_to_market).collect())
+    }
+
+    async fn save_scan_checkpoint(&self, name: &str, offset: u32) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO scan_checkpoint (name, next_offset, updated_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT(name) DO UPDATE SET
+                next_offset = excluded.next_offset,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(name)
+        .bind(offset as i64)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("保存扫描进度失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn get_scan_checkpoint(&self, name: &str) -> Result<Option<u32>> {
+        let row: Option<(i64,)> =
+            sqlx::query_as("SELECT next_offset FROM scan_checkpoint WHERE name = ?")
+                .bind(name)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("读取扫描进度失败: {}", e)))?;
+
+        Ok(row.map(|(offset,)| offset as u32))
+    }
+
+    async fn clear_scan_checkpoint(&self, name: &str) -> Result<()> {
+        sqlx::query("DELETE FROM scan_checkpoint WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("清除扫描进度失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn save_price_alert(&self, alert: &PriceAlert) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO price_alerts
+                (id, condition_id, outcome_index, outcome_name, threshold, direction, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(alert.id())
+        .bind(&alert.condition_id)
+        .bind(alert.outcome_index as i64)
+        .bind(&alert.outcome_name)
+        .bind(alert.threshold)
+        .bind(alert.direction.name())
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("保存价格提醒失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn get_price_alerts(&self) -> Result<Vec<PriceAlert>> {
+        let rows: Vec<(String, i64, Option<String>, f64, String)> = sqlx::query_as(
+            r#"
+            SELECT condition_id, outcome_index, outcome_name, threshold, direction
+            FROM price_alerts
+            ORDER BY created_at
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ScannerError::StorageError(format!("查询价格提醒失败: {}", e)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(condition_id, outcome_index, outcome_name, threshold, direction)| {
+                    let direction = if direction == CrossDirection::Below.name() {
+                        CrossDirection::Below
+                    } else {
+                        CrossDirection::Above
+                    };
+                    PriceAlert {
+                        outcome_name,
+                        ..PriceAlert::new(
+                            condition_id,
+                            outcome_index as usize,
+                            threshold,
+                            direction,
+                        )
+                    }
+                },
+            )
+            .collect())
+    }
+
+    async fn delete_price_alert(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM price_alerts WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("删除价格提醒失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn add_to_watchlist(&self, condition_id: &str) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO watchlist (condition_id, added_at) VALUES (?, ?)")
+            .bind(condition_id)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("添加关注市场失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn remove_from_watchlist(&self, condition_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM watchlist WHERE condition_id = ?")
+            .bind(condition_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("移除关注市场失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn get_watchlist(&self) -> Result<Vec<String>> {
+        let rows: Vec<(String,)> =
+            sqlx::query_as("SELECT condition_id FROM watchlist ORDER BY condition_id")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("查询关注列表失败: {}", e)))?;
+
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::sample_events;
+
+    #[tokio::test]
+    async fn save_events_inserts_500_events_in_one_call() {
+        // 内存数据库每个连接各自独立，只能使用一个连接
+        let config = SqliteConfig {
+            max_connections: 1,
+            wal: false,
+            ..Default::default()
+        };
+        let db = Database::with_config("sqlite::memory:", config).await.unwrap();
+        db.init().await.unwrap();
+
+        let events = sample_events(500);
+        db.save_events(events.clone()).await.unwrap();
+        // 重复保存被忽略
+        db.save_events(events.clone()).await.unwrap();
+
+        let recent: Vec<String> = db
+            .get_recent_events(1000)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        let expected: Vec<String> = events.into_iter().rev().map(|e| e.id).collect();
+        assert_eq!(recent, expected);
+    }
 }
@@ -3,7 +3,11 @@ use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum ScannerError {
     #[error("API 请求失败: {0}")]
-    ApiError(#[from] reqwest::Error),
+    ApiError(reqwest::Error),
+    
+    /// 请求在客户端超时时间内没有完成（连接、等待响应或读取响应体），见 `PolymarketClientBuilder::timeout`
+    #[error("API 请求超时: {0}")]
+    Timeout(reqwest::Error),
     
     #[error("JSON 解析错误: {0}")]
     JsonError(#[from] serde_json::Error),
@@ -11,12 +15,59 @@ pub enum ScannerError {
     #[error("无效的响应数据: {0}")]
     InvalidResponse(String),
     
+    /// API 返回 401/403，常见原因是所在地区被 Polymarket 限制访问（geoblock），重试不会改变结果
+    #[error("API 拒绝访问: {0}（可能因所在地区被限制访问，可通过 API_PROXY 配置其他地区的代理）")]
+    Forbidden(String),
+    
     #[error("网络错误: {0}")]
     #[allow(dead_code)]
     NetworkError(String),
     
     #[error("配置错误: {0}")]
     ConfigError(String),
+    
+    #[error("存储错误: {0}")]
+    StorageError(String),
+    
+    #[error("未找到: {0}")]
+    NotFound(String),
+    
+    #[error("无效的市场数据: {0}")]
+    InvalidMarket(String),
+}
+
+impl ScannerError {
+    /// 重试是否可能成功：超时、网络和 API 错误可以重试，配置、存储和数据错误不会因重试而改变
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ScannerError::ApiError(_)
+            | ScannerError::Timeout(_)
+            | ScannerError::NetworkError(_)
+            | ScannerError::InvalidResponse(_)
+            | ScannerError::JsonError(_) => true,
+            ScannerError::Forbidden(_)
+            | ScannerError::ConfigError(_)
+            | ScannerError::StorageError(_)
+            | ScannerError::NotFound(_)
+            | ScannerError::InvalidMarket(_) => false,
+        }
+    }
+    
+    /// 持续扫描遇到时应停止而不是在下一轮重试的错误（目前只有 `Forbidden`）
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, ScannerError::Forbidden(_))
+    }
+}
+
+/// 超时单独映射为 `Timeout`，便于调用方区分"API 太慢"和其他 HTTP 错误
+impl From<reqwest::Error> for ScannerError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            ScannerError::Timeout(e)
+        } else {
+            ScannerError::ApiError(e)
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, ScannerError>;
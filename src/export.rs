@@ -0,0 +1,121 @@
+use crate::error::{Result, ScannerError};
+use crate::storage::Storage;
+use crate::types::Market;
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::info;
+
+/// 每个 RecordBatch 包含的市场数量，导出时最多同时在内存中保留这么多市场
+const EXPORT_CHUNK_SIZE: usize = 1000;
+
+/// 导出文件的列定义
+fn market_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("condition_id", DataType::Utf8, false),
+        Field::new("question_id", DataType::Utf8, true),
+        Field::new("question", DataType::Utf8, false),
+        Field::new("market_slug", DataType::Utf8, true),
+        Field::new("outcomes", DataType::Utf8, false),
+        Field::new("outcome_prices", DataType::Utf8, true),
+        Field::new("yes_probability", DataType::Float64, true),
+        Field::new("volume", DataType::Float64, true),
+        Field::new("liquidity", DataType::Float64, true),
+        Field::new(
+            "end_date",
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            true,
+        ),
+        Field::new("active", DataType::Boolean, true),
+        Field::new("closed", DataType::Boolean, true),
+    ]))
+}
+
+/// 将一批市场转换为 RecordBatch
+fn markets_to_batch(schema: &SchemaRef, markets: &[Market]) -> Result<RecordBatch> {
+    let strings = |f: fn(&Market) -> Option<&str>| -> ArrayRef {
+        Arc::new(markets.iter().map(f).collect::<StringArray>())
+    };
+    let floats = |f: fn(&Market) -> Option<f64>| -> ArrayRef {
+        Arc::new(markets.iter().map(f).collect::<Float64Array>())
+    };
+    let bools = |f: fn(&Market) -> Option<bool>| -> ArrayRef {
+        Arc::new(markets.iter().map(f).collect::<BooleanArray>())
+    };
+
+    let end_dates: TimestampMicrosecondArray = markets
+        .iter()
+        .map(|m| m.end_date_parsed().map(|d| d.timestamp_micros()))
+        .collect();
+
+    let columns: Vec<ArrayRef> = vec![
+        strings(|m| Some(m.condition_id.as_str())),
+        strings(|m| m.question_id.as_deref()),
+        strings(|m| Some(m.question.as_str())),
+        strings(|m| m.market_slug.as_deref()),
+        strings(|m| Some(m.outcomes.as_str())),
+        strings(|m| m.outcome_prices.as_deref()),
+        floats(|m| m.yes_probability()),
+        floats(|m| m.volume_value()),
+        floats(|m| m.liquidity.as_deref()?.trim().parse().ok()),
+        Arc::new(end_dates.with_timezone("UTC")),
+        bools(|m| m.active),
+        bools(|m| m.closed),
+    ];
+
+    RecordBatch::try_new(schema.clone(), columns)
+        .map_err(|e| ScannerError::StorageError(format!("构建 RecordBatch 失败: {}", e)))
+}
+
+/// 将存储中的全部市场导出为 Parquet 文件，返回导出的市场数量
+///
+/// 按 `EXPORT_CHUNK_SIZE` 分批读取和写入，不会把整个存储加载到内存。
+/// 成交量、流动性为 f64，active/closed 为 bool，end_date 为 UTC 时间戳
+pub async fn export_markets_parquet(
+    storage: &(dyn Storage + Send + Sync),
+    path: impl AsRef<Path>,
+) -> Result<usize> {
+    let path = path.as_ref();
+    let schema = market_schema();
+    let file = File::create(path).map_err(|e| {
+        ScannerError::StorageError(format!("创建导出文件 {} 失败: {}", path.display(), e))
+    })?;
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(properties))
+        .map_err(|e| ScannerError::StorageError(format!("创建 Parquet writer 失败: {}", e)))?;
+
+    let ids = storage.get_all_market_ids().await?;
+    let mut exported = 0;
+
+    for chunk in ids.chunks(EXPORT_CHUNK_SIZE) {
+        let mut markets = Vec::with_capacity(chunk.len());
+        for id in chunk {
+            if let Some(market) = storage.get_market(id).await? {
+                markets.push(market);
+            }
+        }
+
+        let batch = markets_to_batch(&schema, &markets)?;
+        writer
+            .write(&batch)
+            .map_err(|e| ScannerError::StorageError(format!("写入 Parquet 失败: {}", e)))?;
+        exported += markets.len();
+        info!("已导出 {}/{} 个市场", exported, ids.len());
+    }
+
+    writer
+        .close()
+        .map_err(|e| ScannerError::StorageError(format!("关闭 Parquet 文件失败: {}", e)))?;
+
+    info!("导出完成: {} 个市场 -> {}", exported, path.display());
+    Ok(exported)
+}
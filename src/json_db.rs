@@ -1,58 +1,445 @@
 use crate::error::{Result, ScannerError};
-use crate::storage::Storage;
-use crate::types::Market;
+use crate::storage::{
+    collect_stats, event_matches, events_after, price_volatility, status_counts, top_by_volume,
+    window_start, DatabaseStats, EventBatch, Storage,
+};
+use crate::types::{Market, MarketEvent, PriceAlert};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use log::info;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, instrument, warn};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 struct MarketData {
     markets: HashMap<String, Market>,
+    /// condition_id -> last time the market was saved
+    #[serde(default)]
+    updated_at: HashMap<String, DateTime<Utc>>,
+}
+
+/// Markets file of the unsharded layout
+const MARKETS_FILE: &str = "markets.json";
+/// Directory (under the base path) holding the market shard files
+const MARKETS_DIR: &str = "markets";
+const CHECKPOINT_FILE: &str = "scan_checkpoint.json";
+/// Price alerts keyed by `PriceAlert::id()`
+const PRICE_ALERTS_FILE: &str = "price_alerts.json";
+/// Condition ids of watched markets
+const WATCHLIST_FILE: &str = "watchlist.json";
+/// Number of events kept in memory
+const MAX_EVENTS: usize = 10_000;
+/// Directory (under the base path) holding one price history file per market
+const HISTORY_DIR: &str = "history";
+/// Files of a multi-file write whose temp files are complete and only need
+/// renaming; present only while such a write is in progress
+const JOURNAL_FILE: &str = "write_journal.json";
+/// Suffix of the temp file each write goes through before being renamed
+const TEMP_SUFFIX: &str = ".tmp";
+
+/// (outcome_prices, volume, timestamp)
+type PriceHistoryEntry = (String, String, DateTime<Utc>);
+
+/// Options for `JsonDatabase`
+#[derive(Debug, Clone)]
+pub struct JsonDbConfig {
+    /// Write indented JSON instead of compact JSON; roughly doubles the file
+    /// size, so only worth enabling when inspecting the files by hand
+    pub pretty: bool,
+    /// Price history points kept per market, in memory and in
+    /// `history/{condition_id}.json`; older points are dropped
+    pub history_limit: usize,
+    /// Number of leading hex characters of the condition_id (after `0x`)
+    /// that pick the shard file `markets/{prefix}.json`: 1 gives up to 16
+    /// files, 2 up to 256. 0 keeps every market in `markets.json`
+    ///
+    /// A save rewrites only the shards of the saved markets, keeping each
+    /// write small on large catalogs. `init` converts data written with a
+    /// different setting, including the single-file layout.
+    pub shard_prefix_len: usize,
+}
+
+impl Default for JsonDbConfig {
+    fn default() -> Self {
+        Self {
+            pretty: false,
+            history_limit: 1000,
+            shard_prefix_len: 0,
+        }
+    }
 }
 
 pub struct JsonDatabase {
     base_path: PathBuf,
+    config: JsonDbConfig,
     markets: RwLock<HashMap<String, Market>>,
-    price_history: RwLock<HashMap<String, Vec<(String, String, DateTime<Utc>)>>>,
+    updated_at: RwLock<HashMap<String, DateTime<Utc>>>,
+    price_history: RwLock<HashMap<String, Vec<PriceHistoryEntry>>>,
+    events: RwLock<Vec<MarketEvent>>,
+    /// Events removed from the front of `events` since startup, so event
+    /// cursors stay valid after trimming
+    events_dropped: AtomicU64,
+    /// Serializes multi-file writes, which share `JOURNAL_FILE`
+    journal_lock: Mutex<()>,
+    /// Bumped on every change to `markets`, while its write lock is held
+    markets_version: AtomicU64,
+    /// Single writer of the market files, holding the version each file
+    /// was last written at
+    markets_files: Mutex<HashMap<String, u64>>,
 }
 
 impl JsonDatabase {
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self::with_config(path, JsonDbConfig::default())
+    }
+
+    pub fn with_config<P: AsRef<Path>>(path: P, config: JsonDbConfig) -> Self {
         Self {
             base_path: path.as_ref().to_path_buf(),
+            config,
             markets: RwLock::new(HashMap::new()),
+            updated_at: RwLock::new(HashMap::new()),
             price_history: RwLock::new(HashMap::new()),
+            events: RwLock::new(Vec::new()),
+            events_dropped: AtomicU64::new(0),
+            journal_lock: Mutex::new(()),
+            markets_version: AtomicU64::new(0),
+            markets_files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// File holding a market, relative to the base path
+    fn market_file(&self, condition_id: &str) -> String {
+        let prefix_len = self.config.shard_prefix_len;
+        if prefix_len == 0 {
+            return MARKETS_FILE.to_string();
         }
+        let id = condition_id.strip_prefix("0x").unwrap_or(condition_id);
+        // ids shorter than the prefix are padded; non-hex characters are
+        // mapped to a safe name like `history_file` does
+        let prefix: String = id
+            .chars()
+            .chain(std::iter::repeat('_'))
+            .take(prefix_len)
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{}/{}.json", MARKETS_DIR, prefix)
+    }
+
+    /// Write the market files in `files` unless a save that started later
+    /// has already written them at `version` or newer
+    ///
+    /// The snapshot is cloned under the read locks, which are released before
+    /// serializing and writing, so concurrent `save_markets` calls only wait
+    /// on each other for the copy. Snapshots are taken while holding
+    /// `markets_files`, so a file never goes back to an older state, and
+    /// saves queued behind a write that already covered them return at once.
+    /// Several shards are written together with `write_files`, so a batch
+    /// lands in all of them or in none.
+    async fn persist_markets(&self, version: u64, files: BTreeSet<String>) -> Result<()> {
+        let mut written = self.markets_files.lock().await;
+        let files: BTreeSet<String> = files
+            .into_iter()
+            .filter(|file| written.get(file).is_none_or(|&v| v < version))
+            .collect();
+        if files.is_empty() {
+            return Ok(());
+        }
+
+        let (shards, snapshot_version) = {
+            let markets = self.markets.read().await;
+            let updated_at = self.updated_at.read().await;
+            let mut shards: BTreeMap<String, MarketData> = BTreeMap::new();
+            for (id, market) in markets.iter() {
+                let file = self.market_file(id);
+                if !files.contains(&file) {
+                    continue;
+                }
+                let shard = shards.entry(file).or_default();
+                shard.markets.insert(id.clone(), market.clone());
+                if let Some(ts) = updated_at.get(id) {
+                    shard.updated_at.insert(id.clone(), *ts);
+                }
+            }
+            (shards, self.markets_version.load(Ordering::Acquire))
+        };
+
+        let contents = shards
+            .iter()
+            .map(|(file, data)| Ok((file.clone(), self.to_json(data)?)))
+            .collect::<Result<Vec<_>>>()?;
+        self.write_files(&contents).await?;
+        for file in files {
+            written.insert(file, snapshot_version);
+        }
+        Ok(())
+    }
+
+    /// Load the markets of every layout found on disk and, if any of them
+    /// is not the configured layout, rewrite them in the configured one
+    ///
+    /// The new files are written in one journaled batch before the old ones
+    /// are removed, so a crash in between only leaves duplicates, which are
+    /// resolved by keeping the most recently saved copy.
+    async fn load_markets(&self) -> Result<()> {
+        let mut files = Vec::new();
+        if self.base_path.join(MARKETS_FILE).exists() {
+            files.push(MARKETS_FILE.to_string());
+        }
+        let markets_dir = self.base_path.join(MARKETS_DIR);
+        if let Ok(mut entries) = fs::read_dir(&markets_dir).await {
+            while let Some(entry) = entries.next_entry().await.map_err(|e| {
+                ScannerError::StorageError(format!(
+                    "Failed to read {}: {}",
+                    markets_dir.display(),
+                    e
+                ))
+            })? {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if name.ends_with(".json") {
+                    files.push(format!("{}/{}", MARKETS_DIR, name));
+                }
+            }
+        }
+
+        let mut loaded = MarketData::default();
+        for file in &files {
+            let Some(data) = self.load_from_file::<MarketData>(file).await? else {
+                continue;
+            };
+            for (id, market) in data.markets {
+                let ts = data.updated_at.get(&id).copied();
+                let newer = match (ts, loaded.updated_at.get(&id)) {
+                    (Some(ts), Some(existing)) => ts > *existing,
+                    _ => !loaded.markets.contains_key(&id),
+                };
+                if newer {
+                    if let Some(ts) = ts {
+                        loaded.updated_at.insert(id.clone(), ts);
+                    }
+                    loaded.markets.insert(id, market);
+                }
+            }
+        }
+
+        let expected: BTreeSet<String> = loaded
+            .markets
+            .keys()
+            .map(|id| self.market_file(id))
+            .collect();
+        let obsolete: Vec<&String> = files.iter().filter(|f| !expected.contains(*f)).collect();
+        let count = loaded.markets.len();
+        *self.markets.write().await = loaded.markets;
+        *self.updated_at.write().await = loaded.updated_at;
+        if count > 0 {
+            info!("Loaded {} markets from {} files", count, files.len());
+        }
+
+        if !obsolete.is_empty() {
+            info!(
+                "Converting {} market files to the configured layout ({} files)",
+                files.len(),
+                expected.len()
+            );
+            self.persist_markets(self.markets_version.load(Ordering::Acquire), expected)
+                .await?;
+            for file in obsolete {
+                fs::remove_file(self.base_path.join(file)).await.map_err(|e| {
+                    ScannerError::StorageError(format!("Failed to remove {}: {}", file, e))
+                })?;
+            }
+            sync_dir(&self.base_path).await?;
+            sync_dir(&markets_dir).await?;
+        }
+        Ok(())
+    }
+
+    fn to_json<T: Serialize>(&self, data: &T) -> Result<String> {
+        if self.config.pretty {
+            serde_json::to_string_pretty(data)
+        } else {
+            serde_json::to_string(data)
+        }
+        .map_err(ScannerError::JsonError)
     }
 
     async fn save_to_file<T: Serialize>(&self, filename: &str, data: &T) -> Result<()> {
-        let file_path = self.base_path.join(filename);
-        let temp_path = self.base_path.join(format!("{}.tmp", filename));
+        let json = self.to_json(data)?;
+        self.write_files(&[(filename.to_string(), json)]).await
+    }
 
-        let json = serde_json::to_string_pretty(data).map_err(|e| ScannerError::JsonError(e))?;
+    /// Atomically replace one or more files under the base path
+    ///
+    /// Every file is first written and fsynced as `{name}.tmp`. A single file
+    /// is then renamed into place. For several files the names are recorded in
+    /// `JOURNAL_FILE` before any rename, so a crash part way through the
+    /// renames is rolled forward by `recover_writes` and the batch lands either
+    /// completely or not at all. Parent directories are fsynced after the
+    /// renames so they survive a power loss.
+    async fn write_files(&self, files: &[(String, String)]) -> Result<()> {
+        for (filename, content) in files {
+            self.write_temp_file(filename, content).await?;
+        }
 
-        let mut file = fs::File::create(&temp_path)
-            .await
-            .map_err(|e| ScannerError::ConfigError(format!("Failed to create temp file: {}", e)))?;
+        if files.len() == 1 {
+            let filename = &files[0].0;
+            self.rename_temp_file(filename).await?;
+            return self.sync_parent_dir(filename).await;
+        }
+
+        let _guard = self.journal_lock.lock().await;
+        let names: Vec<&String> = files.iter().map(|(name, _)| name).collect();
+        let journal = serde_json::to_string(&names).map_err(ScannerError::JsonError)?;
+        self.write_temp_file(JOURNAL_FILE, &journal).await?;
+        self.rename_temp_file(JOURNAL_FILE).await?;
+        self.sync_parent_dir(JOURNAL_FILE).await?;
+
+        for name in &names {
+            self.rename_temp_file(name).await?;
+        }
+        self.finish_journal(&names).await
+    }
+
+    async fn write_temp_file(&self, filename: &str, content: &str) -> Result<()> {
+        let temp_path = self.base_path.join(format!("{}{}", filename, TEMP_SUFFIX));
 
-        file.write_all(json.as_bytes()).await.map_err(|e| {
-            ScannerError::ConfigError(format!("Failed to write to temp file: {}", e))
+        let mut file = fs::File::create(&temp_path).await.map_err(|e| {
+            ScannerError::StorageError(format!("Failed to create temp file: {}", e))
         })?;
 
-        file.flush()
+        file.write_all(content.as_bytes()).await.map_err(|e| {
+            ScannerError::StorageError(format!("Failed to write to temp file: {}", e))
+        })?;
+
+        file.sync_all()
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("Failed to flush temp file: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("Failed to sync temp file: {}", e)))
+    }
 
+    async fn rename_temp_file(&self, filename: &str) -> Result<()> {
+        let file_path = self.base_path.join(filename);
+        let temp_path = self.base_path.join(format!("{}{}", filename, TEMP_SUFFIX));
         fs::rename(&temp_path, &file_path)
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("Failed to rename temp file: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("Failed to rename temp file: {}", e)))
+    }
+
+    /// Make renames in the directory containing `filename` durable
+    async fn sync_parent_dir(&self, filename: &str) -> Result<()> {
+        match self.base_path.join(filename).parent() {
+            Some(dir) => sync_dir(dir).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Sync the directories touched by a journaled write, then remove the journal
+    async fn finish_journal<S: AsRef<str>>(&self, names: &[S]) -> Result<()> {
+        let dirs: BTreeSet<PathBuf> = names
+            .iter()
+            .filter_map(|name| {
+                self.base_path
+                    .join(name.as_ref())
+                    .parent()
+                    .map(Path::to_path_buf)
+            })
+            .collect();
+        for dir in dirs {
+            sync_dir(&dir).await?;
+        }
+        fs::remove_file(self.base_path.join(JOURNAL_FILE))
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("Failed to remove journal: {}", e)))?;
+        self.sync_parent_dir(JOURNAL_FILE).await
+    }
+
+    /// Bring the data directory back to a consistent state after a crash
+    ///
+    /// A journal left behind means all temp files of a multi-file write were
+    /// complete, so the write is finished by renaming the remaining ones. Any
+    /// other temp file belongs to a write that never reached its rename and is
+    /// deleted, keeping the last complete version of that file.
+    async fn recover_writes(&self) -> Result<()> {
+        if let Some(names) = self.load_from_file::<Vec<String>>(JOURNAL_FILE).await? {
+            warn!("Finishing interrupted write of {} files", names.len());
+            for name in &names {
+                let temp_path = self.base_path.join(format!("{}{}", name, TEMP_SUFFIX));
+                if temp_path.exists() {
+                    self.rename_temp_file(name).await?;
+                }
+            }
+            self.finish_journal(&names).await?;
+        }
 
+        let mut removed = 0;
+        for dir in [
+            self.base_path.clone(),
+            self.base_path.join(MARKETS_DIR),
+            self.base_path.join(HISTORY_DIR),
+        ] {
+            let Ok(mut entries) = fs::read_dir(&dir).await else {
+                continue;
+            };
+            while let Some(entry) = entries.next_entry().await.map_err(|e| {
+                ScannerError::StorageError(format!("Failed to read {}: {}", dir.display(), e))
+            })? {
+                if entry.file_name().to_string_lossy().ends_with(TEMP_SUFFIX) {
+                    fs::remove_file(entry.path()).await.map_err(|e| {
+                        ScannerError::StorageError(format!("Failed to remove temp file: {}", e))
+                    })?;
+                    removed += 1;
+                }
+            }
+        }
+        if removed > 0 {
+            warn!("Removed {} temp files left by interrupted writes", removed);
+        }
+        Ok(())
+    }
+
+    /// File holding a market's price history, relative to the base path
+    fn history_file(condition_id: &str) -> String {
+        // condition ids are hex strings; anything else is mapped to a safe name
+        let name: String = condition_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{}/{}.json", HISTORY_DIR, name)
+    }
+
+    /// Load a market's price history from disk into memory unless it is
+    /// already cached
+    async fn ensure_history_loaded(&self, condition_id: &str) -> Result<()> {
+        if self.price_history.read().await.contains_key(condition_id) {
+            return Ok(());
+        }
+        let entries = self
+            .load_from_file::<Vec<PriceHistoryEntry>>(&Self::history_file(condition_id))
+            .await?;
+        if let Some(entries) = entries {
+            self.price_history
+                .write()
+                .await
+                .entry(condition_id.to_string())
+                .or_insert(entries);
+        }
         Ok(())
     }
 
@@ -68,64 +455,166 @@ impl JsonDatabase {
 
         let content = fs::read_to_string(&file_path)
             .await
-            .map_err(|e| ScannerError::ConfigError(format!("Failed to read file: {}", e)))?;
+            .map_err(|e| ScannerError::StorageError(format!("Failed to read file: {}", e)))?;
 
-        let data = serde_json::from_str(&content).map_err(|e| ScannerError::JsonError(e))?;
+        let data = serde_json::from_str(&content).map_err(ScannerError::JsonError)?;
 
         Ok(Some(data))
     }
 }
 
+/// fsync a directory so renames inside it are durable
+async fn sync_dir(dir: &Path) -> Result<()> {
+    // Directories cannot be opened for syncing on every platform (e.g. Windows)
+    #[cfg(unix)]
+    {
+        let result = match fs::File::open(dir).await {
+            Ok(file) => file.sync_all().await,
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| {
+            ScannerError::StorageError(format!("Failed to sync directory {}: {}", dir.display(), e))
+        })?;
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
 #[async_trait]
 impl Storage for JsonDatabase {
     async fn init(&self) -> Result<()> {
         if !self.base_path.exists() {
             fs::create_dir_all(&self.base_path).await.map_err(|e| {
-                ScannerError::ConfigError(format!("Failed to create data directory: {}", e))
+                ScannerError::StorageError(format!("Failed to create data directory: {}", e))
             })?;
         }
 
-        // Load markets
-        if let Some(data) = self.load_from_file::<MarketData>("markets.json").await? {
-            let mut markets = self.markets.write().await;
-            *markets = data.markets;
-            info!("Loaded {} markets from disk", markets.len());
+        self.recover_writes().await?;
+
+        if self.config.shard_prefix_len > 0 {
+            let markets_dir = self.base_path.join(MARKETS_DIR);
+            fs::create_dir_all(&markets_dir).await.map_err(|e| {
+                ScannerError::StorageError(format!("Failed to create markets directory: {}", e))
+            })?;
         }
+        self.load_markets().await?;
 
-        // Load history (simplified: just keeping in memory for now or implement separate file per market later)
-        // For this MVP, we'll skip loading history from disk to keep it simple,
-        // or we could implement a simple history.json if needed.
+        // Price history is loaded lazily per market, see `ensure_history_loaded`
+        let history_dir = self.base_path.join(HISTORY_DIR);
+        if !history_dir.exists() {
+            fs::create_dir_all(&history_dir).await.map_err(|e| {
+                ScannerError::StorageError(format!("Failed to create history directory: {}", e))
+            })?;
+        }
 
         Ok(())
     }
 
+    /// Verify the data directory is writable by writing and removing a probe file
+    async fn health_check(&self) -> Result<()> {
+        let probe = self.base_path.join(".health_check");
+        fs::write(&probe, b"ok").await.map_err(|e| {
+            ScannerError::StorageError(format!(
+                "Data directory {} is not writable: {}",
+                self.base_path.display(),
+                e
+            ))
+        })?;
+        fs::remove_file(&probe).await.map_err(|e| {
+            ScannerError::StorageError(format!("Failed to remove health check file: {}", e))
+        })?;
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip_all, fields(condition_id = %market.condition_id))]
     async fn save_market(&self, market: &Market) -> Result<()> {
         self.save_markets(vec![market.clone()]).await
     }
 
+    #[instrument(level = "debug", skip_all, fields(count = markets.len()))]
     async fn save_markets(&self, markets: Vec<Market>) -> Result<()> {
-        {
+        let files: BTreeSet<String> = markets
+            .iter()
+            .map(|m| self.market_file(&m.condition_id))
+            .collect();
+        let version = {
+            let now = Utc::now();
             let mut markets_map = self.markets.write().await;
+            let mut updated_at = self.updated_at.write().await;
             for market in markets {
+                updated_at.insert(market.condition_id.clone(), now);
                 markets_map.insert(market.condition_id.clone(), market);
             }
-        } // drop lock
+            self.markets_version.fetch_add(1, Ordering::AcqRel) + 1
+        }; // drop lock
 
-        let markets_map = self.markets.read().await;
-        let data = MarketData {
-            markets: markets_map.clone(),
-        };
-        self.save_to_file("markets.json", &data).await?;
+        self.persist_markets(version, files).await
+    }
 
+    #[instrument(level = "trace", skip_all, fields(condition_id = %event.market.condition_id, event_id = %event.id))]
+    async fn save_event(&self, event: &MarketEvent) -> Result<()> {
+        // Like price history, events are only kept in memory for now
+        let mut events = self.events.write().await;
+        if !event.id.is_empty() && events.iter().any(|e| e.id == event.id) {
+            return Ok(());
+        }
+        events.push(event.clone());
+        if events.len() > MAX_EVENTS {
+            let overflow = events.len() - MAX_EVENTS;
+            events.drain(..overflow);
+            self.events_dropped
+                .fetch_add(overflow as u64, Ordering::Relaxed);
+        }
         Ok(())
     }
 
-    async fn save_price_history(
+    async fn get_recent_events(&self, limit: i32) -> Result<Vec<MarketEvent>> {
+        let events = self.events.read().await;
+        Ok(events
+            .iter()
+            .rev()
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_events_after(&self, cursor: Option<&str>, limit: i32) -> Result<EventBatch> {
+        let events = self.events.read().await;
+        // Only changed while the events lock is held for writing
+        let dropped = self.events_dropped.load(Ordering::Relaxed);
+        events_after(&events, dropped, cursor, limit)
+    }
+
+    async fn get_recent_events_for(
+        &self,
+        condition_id: &str,
+        event_type: Option<&str>,
+        limit: i32,
+    ) -> Result<Vec<MarketEvent>> {
+        let events = self.events.read().await;
+        Ok(events
+            .iter()
+            .rev()
+            .filter(|e| event_matches(e, condition_id, event_type))
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    #[instrument(level = "trace", skip(self, outcome_prices, volume))]
+    async fn save_price_history_at(
         &self,
         condition_id: &str,
         outcome_prices: Option<&str>,
         volume: Option<&str>,
+        timestamp: DateTime<Utc>,
     ) -> Result<()> {
+        // Append to what is already on disk rather than overwriting it after a restart
+        self.ensure_history_loaded(condition_id).await?;
+
+        // The lock is held while writing so concurrent saves of the same
+        // market cannot overwrite each other's file
         let mut history = self.price_history.write().await;
         let entry = history
             .entry(condition_id.to_string())
@@ -134,20 +623,16 @@ impl Storage for JsonDatabase {
         entry.push((
             outcome_prices.unwrap_or("").to_string(),
             volume.unwrap_or("").to_string(),
-            Utc::now(),
+            timestamp,
         ));
 
-        // Keep only last 1000 entries
-        if entry.len() > 1000 {
-            entry.remove(0);
+        if entry.len() > self.config.history_limit {
+            let overflow = entry.len() - self.config.history_limit;
+            entry.drain(..overflow);
         }
 
-        // Note: For a full implementation, we would save this to disk too.
-        // For now, we'll just keep it in memory as per the "simple" requirement,
-        // or we could dump all history to a big json file, but that might be slow.
-        // Let's stick to in-memory for history for this iteration unless requested otherwise.
-
-        Ok(())
+        self.save_to_file(&Self::history_file(condition_id), entry)
+            .await
     }
 
     async fn get_market_count(&self) -> Result<i64> {
@@ -155,11 +640,31 @@ impl Storage for JsonDatabase {
         Ok(markets.len() as i64)
     }
 
+    /// Events only cover what is kept in memory, price history only the
+    /// markets whose history has been loaded or saved since startup
+    async fn get_stats(&self) -> Result<DatabaseStats> {
+        let markets = self.markets.read().await;
+        let updated_at = self.updated_at.read().await;
+        let events = self.events.read().await;
+        let history = self.price_history.read().await;
+        Ok(collect_stats(
+            events.iter(),
+            updated_at.values(),
+            markets.len(),
+            history.values().map(Vec::len).sum(),
+        ))
+    }
+
+    async fn get_market_status_counts(&self) -> Result<HashMap<String, i64>> {
+        Ok(status_counts(self.markets.read().await.values()))
+    }
+
     async fn get_price_history(
         &self,
         condition_id: &str,
         limit: i32,
     ) -> Result<Vec<(String, String, DateTime<Utc>)>> {
+        self.ensure_history_loaded(condition_id).await?;
         let history = self.price_history.read().await;
         if let Some(entries) = history.get(condition_id) {
             let start = if entries.len() > limit as usize {
@@ -173,13 +678,291 @@ impl Storage for JsonDatabase {
         }
     }
 
+    async fn get_price_volatility(
+        &self,
+        condition_id: &str,
+        window: Duration,
+    ) -> Result<Option<f64>> {
+        self.ensure_history_loaded(condition_id).await?;
+        let since = window_start(window);
+        let history = self.price_history.read().await;
+        Ok(history.get(condition_id).and_then(|entries| {
+            price_volatility(
+                entries
+                    .iter()
+                    .filter(|(_, _, timestamp)| *timestamp >= since)
+                    .map(|(prices, _, _)| prices.as_str()),
+            )
+        }))
+    }
+
     async fn get_market(&self, condition_id: &str) -> Result<Option<Market>> {
         let markets = self.markets.read().await;
         Ok(markets.get(condition_id).cloned())
     }
 
+    async fn get_markets_updated_since(&self, since: DateTime<Utc>) -> Result<Vec<Market>> {
+        let markets = self.markets.read().await;
+        let updated_at = self.updated_at.read().await;
+        Ok(updated_at
+            .iter()
+            .filter(|(_, ts)| **ts > since)
+            .filter_map(|(id, _)| markets.get(id).cloned())
+            .collect())
+    }
+
+    async fn get_stale_markets(&self, older_than: Duration) -> Result<Vec<Market>> {
+        let cutoff = window_start(older_than);
+        let markets = self.markets.read().await;
+        let updated_at = self.updated_at.read().await;
+        let mut stale: Vec<(&DateTime<Utc>, &Market)> = updated_at
+            .iter()
+            .filter(|(_, ts)| **ts <= cutoff)
+            .filter_map(|(id, ts)| markets.get(id).map(|m| (ts, m)))
+            .collect();
+        stale.sort_by_key(|(ts, _)| **ts);
+        Ok(stale.into_iter().map(|(_, m)| m.clone()).collect())
+    }
+
     async fn get_all_market_ids(&self) -> Result<Vec<String>> {
         let markets = self.markets.read().await;
         Ok(markets.keys().cloned().collect())
     }
+
+    async fn get_markets_by_tag(&self, tag: &str) -> Result<Vec<Market>> {
+        let markets = self.markets.read().await;
+        Ok(markets
+            .values()
+            .filter(|m| m.has_tag(tag))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_top_markets_by_volume(&self, limit: i32) -> Result<Vec<Market>> {
+        let markets = self.markets.read().await;
+        Ok(top_by_volume(markets.values().cloned().collect(), limit))
+    }
+
+    async fn save_scan_checkpoint(&self, name: &str, offset: u32) -> Result<()> {
+        let mut checkpoints = self
+            .load_from_file::<HashMap<String, u32>>(CHECKPOINT_FILE)
+            .await?
+            .unwrap_or_default();
+        checkpoints.insert(name.to_string(), offset);
+        self.save_to_file(CHECKPOINT_FILE, &checkpoints).await
+    }
+
+    async fn get_scan_checkpoint(&self, name: &str) -> Result<Option<u32>> {
+        let checkpoints = self
+            .load_from_file::<HashMap<String, u32>>(CHECKPOINT_FILE)
+            .await?;
+        Ok(checkpoints.and_then(|c| c.get(name).copied()))
+    }
+
+    async fn clear_scan_checkpoint(&self, name: &str) -> Result<()> {
+        if let Some(mut checkpoints) = self
+            .load_from_file::<HashMap<String, u32>>(CHECKPOINT_FILE)
+            .await?
+        {
+            if checkpoints.remove(name).is_some() {
+                self.save_to_file(CHECKPOINT_FILE, &checkpoints).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn save_price_alert(&self, alert: &PriceAlert) -> Result<()> {
+        let mut alerts = self
+            .load_from_file::<HashMap<String, PriceAlert>>(PRICE_ALERTS_FILE)
+            .await?
+            .unwrap_or_default();
+        alerts.insert(alert.id(), alert.clone());
+        self.save_to_file(PRICE_ALERTS_FILE, &alerts).await
+    }
+
+    async fn get_price_alerts(&self) -> Result<Vec<PriceAlert>> {
+        let alerts = self
+            .load_from_file::<HashMap<String, PriceAlert>>(PRICE_ALERTS_FILE)
+            .await?;
+        Ok(alerts
+            .map(|a| a.into_values().collect())
+            .unwrap_or_default())
+    }
+
+    async fn delete_price_alert(&self, id: &str) -> Result<()> {
+        if let Some(mut alerts) = self
+            .load_from_file::<HashMap<String, PriceAlert>>(PRICE_ALERTS_FILE)
+            .await?
+        {
+            if alerts.remove(id).is_some() {
+                self.save_to_file(PRICE_ALERTS_FILE, &alerts).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn add_to_watchlist(&self, condition_id: &str) -> Result<()> {
+        let mut watchlist = self
+            .load_from_file::<BTreeSet<String>>(WATCHLIST_FILE)
+            .await?
+            .unwrap_or_default();
+        if watchlist.insert(condition_id.to_string()) {
+            self.save_to_file(WATCHLIST_FILE, &watchlist).await?;
+        }
+        Ok(())
+    }
+
+    async fn remove_from_watchlist(&self, condition_id: &str) -> Result<()> {
+        if let Some(mut watchlist) = self
+            .load_from_file::<BTreeSet<String>>(WATCHLIST_FILE)
+            .await?
+        {
+            if watchlist.remove(condition_id) {
+                self.save_to_file(WATCHLIST_FILE, &watchlist).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_watchlist(&self) -> Result<Vec<String>> {
+        let watchlist = self
+            .load_from_file::<BTreeSet<String>>(WATCHLIST_FILE)
+            .await?;
+        Ok(watchlist
+            .map(|w| w.into_iter().collect())
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market(id: &str, question: &str) -> Market {
+        Market {
+            condition_id: id.to_string(),
+            question: question.to_string(),
+            outcomes: r#"["Yes","No"]"#.to_string(),
+            ..Default::default()
+        }
+    }
+
+    async fn question(db: &JsonDatabase, id: &str) -> Option<String> {
+        db.get_market(id).await.unwrap().map(|m| m.question)
+    }
+
+    fn temp_files(dir: &Path) -> Vec<PathBuf> {
+        [dir.to_path_buf(), dir.join(MARKETS_DIR)]
+            .iter()
+            .filter_map(|d| std::fs::read_dir(d).ok())
+            .flatten()
+            .map(|entry| entry.unwrap().path())
+            .filter(|p| p.to_string_lossy().ends_with(TEMP_SUFFIX))
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_saves_leave_latest_state_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = JsonDbConfig {
+            shard_prefix_len: 1,
+            ..Default::default()
+        };
+        let db = std::sync::Arc::new(JsonDatabase::with_config(dir.path(), config.clone()));
+        db.init().await.unwrap();
+
+        let tasks: Vec<_> = (0..32)
+            .map(|i| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    let markets = (0..4)
+                        .map(|j| market(&format!("0x{:x}{}", (i + j) % 16, j), &format!("v{}", i)))
+                        .collect();
+                    db.save_markets(markets).await.unwrap();
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let reloaded = JsonDatabase::with_config(dir.path(), config);
+        reloaded.init().await.unwrap();
+        let in_memory: HashMap<String, String> = db
+            .markets
+            .read()
+            .await
+            .iter()
+            .map(|(id, m)| (id.clone(), m.question.clone()))
+            .collect();
+        let on_disk: HashMap<String, String> = reloaded
+            .markets
+            .read()
+            .await
+            .iter()
+            .map(|(id, m)| (id.clone(), m.question.clone()))
+            .collect();
+        assert_eq!(on_disk, in_memory);
+    }
+
+    #[tokio::test]
+    async fn recovery_discards_temp_file_without_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = JsonDatabase::new(dir.path());
+        db.init().await.unwrap();
+        db.save_market(&market("0xaa", "saved")).await.unwrap();
+
+        // Crash while writing the next version, before the rename
+        let temp_path = dir.path().join(format!("{}{}", MARKETS_FILE, TEMP_SUFFIX));
+        std::fs::write(&temp_path, r#"{"markets":{"0xaa":{"#).unwrap();
+
+        let db = JsonDatabase::new(dir.path());
+        db.init().await.unwrap();
+        assert_eq!(question(&db, "0xaa").await.as_deref(), Some("saved"));
+        assert!(temp_files(dir.path()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn recovery_finishes_journaled_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = JsonDbConfig {
+            shard_prefix_len: 1,
+            ..Default::default()
+        };
+        let db = JsonDatabase::with_config(dir.path(), config.clone());
+        db.init().await.unwrap();
+        db.save_markets(vec![market("0xaa", "old a"), market("0xbb", "old b")])
+            .await
+            .unwrap();
+        let files = [db.market_file("0xaa"), db.market_file("0xbb")];
+
+        // Crash after the journal was written and the first shard renamed
+        let contents = [
+            db.to_json(&MarketData {
+                markets: HashMap::from([("0xaa".to_string(), market("0xaa", "new a"))]),
+                ..Default::default()
+            })
+            .unwrap(),
+            db.to_json(&MarketData {
+                markets: HashMap::from([("0xbb".to_string(), market("0xbb", "new b"))]),
+                ..Default::default()
+            })
+            .unwrap(),
+        ];
+        for (file, content) in files.iter().zip(&contents) {
+            db.write_temp_file(file, content).await.unwrap();
+        }
+        db.write_temp_file(JOURNAL_FILE, &serde_json::to_string(&files).unwrap())
+            .await
+            .unwrap();
+        db.rename_temp_file(JOURNAL_FILE).await.unwrap();
+        db.rename_temp_file(&files[0]).await.unwrap();
+
+        let db = JsonDatabase::with_config(dir.path(), config);
+        db.init().await.unwrap();
+        assert_eq!(question(&db, "0xaa").await.as_deref(), Some("new a"));
+        assert_eq!(question(&db, "0xbb").await.as_deref(), Some("new b"));
+        assert!(!dir.path().join(JOURNAL_FILE).exists());
+        assert!(temp_files(dir.path()).is_empty());
+    }
 }
@@ -0,0 +1,159 @@
+use crate::alerts::Notifier;
+use crate::error::{Result, ScannerError};
+use crate::types::{EventType, MarketEvent};
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+/// 单条消息等待投递确认的默认时间
+const DEFAULT_DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 将市场事件发布到 Kafka topic
+///
+/// 每个事件序列化为 JSON，以 condition_id 作为消息 key，同一市场的事件进入同一分区并保持顺序。
+/// 投递失败时记录日志并返回错误；启用缓冲后失败的事件会暂存在内存中，
+/// 在下一次发布时先于新事件重新发送
+pub struct KafkaSink {
+    producer: FutureProducer,
+    topic: String,
+    /// 需要发布的事件类型名称，空表示全部
+    event_types: Vec<String>,
+    delivery_timeout: Duration,
+    /// 投递失败待重发的事件，超过容量时丢弃最旧的事件
+    ///
+    /// 发布期间一直持有该锁，并发的 `notify` 依次发送，缓冲的事件总在新事件之前发出
+    buffer: Mutex<VecDeque<MarketEvent>>,
+    buffer_capacity: usize,
+}
+
+impl KafkaSink {
+    /// 连接到 `brokers`（逗号分隔的 host:port 列表）并发布到 `topic`
+    pub fn new(brokers: &str, topic: impl Into<String>) -> Result<Self> {
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", brokers);
+        Self::with_client_config(&config, topic)
+    }
+
+    /// 使用自定义的 librdkafka 配置（如 SASL 认证、压缩）创建
+    pub fn with_client_config(config: &ClientConfig, topic: impl Into<String>) -> Result<Self> {
+        let producer: FutureProducer = config
+            .create()
+            .map_err(|e| ScannerError::ConfigError(format!("创建 Kafka producer 失败: {}", e)))?;
+
+        Ok(Self {
+            producer,
+            topic: topic.into(),
+            event_types: Vec::new(),
+            delivery_timeout: DEFAULT_DELIVERY_TIMEOUT,
+            buffer: Mutex::new(VecDeque::new()),
+            buffer_capacity: 0,
+        })
+    }
+
+    /// 只发布指定类型的事件（如 `["NewMarket", "PriceChange"]`）
+    pub fn with_event_types<I, S>(mut self, event_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.event_types = event_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 单条消息等待投递确认的最长时间（默认 5 秒）
+    pub fn with_delivery_timeout(mut self, timeout: Duration) -> Self {
+        self.delivery_timeout = timeout;
+        self
+    }
+
+    /// 投递失败时最多缓冲 `capacity` 个事件等待重发（默认 0，不缓冲）
+    ///
+    /// 缓冲只保存在内存中，进程退出后丢失
+    pub fn with_buffer(mut self, capacity: usize) -> Self {
+        self.buffer_capacity = capacity;
+        self
+    }
+
+    /// 当前缓冲中等待重发的事件数，正在发布时等待发布完成
+    pub async fn buffered(&self) -> usize {
+        self.buffer.lock().await.len()
+    }
+
+    /// 发送单个事件并等待投递确认
+    async fn send(&self, event: &MarketEvent) -> Result<()> {
+        let payload = serde_json::to_string(event)?;
+        let record = FutureRecord::to(&self.topic)
+            .key(&event.market.condition_id)
+            .payload(&payload);
+
+        self.producer
+            .send(record, self.delivery_timeout)
+            .await
+            .map_err(|(e, _)| {
+                ScannerError::NetworkError(format!("Kafka 投递失败 [{}]: {}", self.topic, e))
+            })?;
+
+        Ok(())
+    }
+
+    /// 投递失败的事件放入缓冲，缓冲已满时丢弃最旧的事件
+    fn buffer_event(&self, buffer: &mut VecDeque<MarketEvent>, event: MarketEvent) {
+        if self.buffer_capacity == 0 {
+            return;
+        }
+        if buffer.len() >= self.buffer_capacity {
+            buffer.pop_front();
+            warn!("Kafka 缓冲已满，丢弃最旧的事件");
+        }
+        buffer.push_back(event);
+    }
+
+    /// 按顺序重发缓冲中的事件，遇到失败时停止，未发出的事件留在缓冲中
+    async fn flush_buffer(&self, buffer: &mut VecDeque<MarketEvent>) -> Result<()> {
+        while let Some(event) = buffer.front() {
+            if let Err(e) = self.send(event).await {
+                warn!("重发缓冲事件失败: {}", e);
+                return Err(e);
+            }
+            debug!("已重发缓冲事件: {}", event.id);
+            buffer.pop_front();
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for KafkaSink {
+    /// 先重发缓冲中的事件再发布新事件，保持同一市场的事件顺序
+    ///
+    /// 缓冲中的事件没有全部发出时，新事件直接放入缓冲，不会越过更早的事件
+    async fn notify(&self, event: &MarketEvent) -> Result<()> {
+        let mut buffer = self.buffer.lock().await;
+        let result = match self.flush_buffer(&mut buffer).await {
+            Ok(()) => self.send(event).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => {
+                debug!("已发布 Kafka 事件: {} -> {}", event.id, self.topic);
+                Ok(())
+            }
+            Err(e) => {
+                self.buffer_event(&mut buffer, event.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn wants(&self, event_type: &EventType) -> bool {
+        self.event_types.is_empty() || self.event_types.iter().any(|t| t == event_type.name())
+    }
+
+    fn name(&self) -> &str {
+        "kafka"
+    }
+}
@@ -1,17 +1,52 @@
 pub mod api;
 pub mod scanner;
+pub mod source;
 pub mod types;
 pub mod error;
+#[cfg(feature = "redis")]
 pub mod database;
+#[cfg(feature = "sqlite")]
 pub mod db;
 pub mod storage;
+#[cfg(feature = "json")]
 pub mod json_db;
+pub mod memory;
+pub mod tee;
+#[cfg(feature = "mongo")]
+pub mod mongo;
+pub mod alerts;
+pub mod config;
+#[cfg(feature = "parquet")]
+pub mod export;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "kafka")]
+pub mod kafka;
 
-pub use api::PolymarketClient;
-pub use scanner::MarketScanner;
-pub use types::{Market, MarketEvent, EventType};
+pub use api::{CircuitState, GammaQuery, MarketPage, PolymarketClient, PolymarketClientBuilder, PriceInterval, StreamOptions};
+pub use scanner::{
+    ActivityConfig, ErrorGroup, MarketHandler, MarketScanner, PersistPolicy, PriceChangeMode,
+    ReconcileReport, ScanCadence, ScanControl, ScanOptions, ScanSummary, ScannerConfig,
+    SpreadConfig, SummaryHandler,
+};
+pub use source::{MarketSource, MockMarketSource};
+pub use types::{CrossDirection, Market, MarketDiff, MarketEvent, EventType, NumericChange, OrderBook, OutcomeDelta, ParsedMarket, PriceAlert, Tag};
 pub use error::{ScannerError, Result};
+#[cfg(feature = "redis")]
 pub use database::Database as RedisDatabase;
-pub use db::Database as SqliteDatabase;
-pub use storage::Storage;
-pub use json_db::JsonDatabase;
+#[cfg(feature = "sqlite")]
+pub use db::{Database as SqliteDatabase, PriceBucket, SqliteConfig};
+pub use storage::{
+    import_markets_ndjson, replay_events, restore, snapshot, DatabaseStats, EventBatch,
+    ImportReport, MarketTimeline, SnapshotReport, Storage, TimelinePoint, SNAPSHOT_VERSION,
+};
+#[cfg(feature = "json")]
+pub use json_db::{JsonDatabase, JsonDbConfig};
+pub use memory::InMemoryStorage;
+pub use tee::TeeStorage;
+#[cfg(feature = "mongo")]
+pub use mongo::Database as MongoDatabase;
+pub use alerts::{Notifier, SlackNotifier};
+pub use config::Config;
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaSink;
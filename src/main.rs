@@ -1,77 +1,774 @@
-mod api;
-mod scanner;
-mod types;
-mod error;
-mod database;
-mod db;
-mod storage;
-mod json_db;
-
 use anyhow::Result;
-use log::{info, error};
+use clap::{Args, Parser, Subcommand};
+use tracing::{info, warn, error};
+use polymarket_scanner::config::Config;
+use polymarket_scanner::{alerts, api, scanner};
+use polymarket_scanner::storage::Storage;
+use polymarket_scanner::{
+    replay_events, restore, snapshot, CrossDirection, PriceAlert, ScannerError, TeeStorage,
+};
+use chrono::{DateTime, Utc};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use crate::storage::Storage;
+use tokio::time::Instant;
+
+/// Polymarket 预测市场扫描器
+///
+/// 配置优先级：命令行参数 > 环境变量 > 配置文件（默认 config.toml）> 默认值
+#[derive(Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+struct Cli {
+    /// 配置文件路径，不指定时读取当前目录下的 config.toml（不存在则忽略）
+    #[arg(long, env = "CONFIG_FILE", global = true)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    storage: StorageArgs,
+
+    /// API 请求超时时间（秒）[env: API_TIMEOUT]
+    #[arg(long, global = true)]
+    api_timeout: Option<u64>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// 未指定子命令时按 `scan` 运行
+    #[command(flatten)]
+    scan: ScanArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 持续扫描市场变化（默认）
+    Scan(ScanArgs),
+    /// 扫描全部市场并写入存储（回填）
+    ScanAll(ScanAllArgs),
+    /// 查询存储中的市场数量、最近事件和成交量排行
+    Query(QueryArgs),
+    /// 导出单个市场的数据、最近事件和价格历史（JSON）
+    Timeline(TimelineArgs),
+    /// 按时间顺序回放存储的事件，每行输出一个 JSON 事件
+    Replay(ReplayArgs),
+    /// 对比存储与 API，检查过期、下架和缺失的市场
+    Reconcile(ReconcileArgs),
+    /// 把整个存储（市场、价格历史、事件、关注列表和提醒）导出为快照，用于备份或迁移
+    Snapshot(SnapshotArgs),
+    /// 把快照写入当前配置的存储（不清空已有数据）
+    Restore(RestoreArgs),
+    /// 管理价格提醒（持续扫描时按提醒产生 ThresholdCrossed 事件）
+    #[command(subcommand)]
+    Alert(AlertCommand),
+    /// 管理关注列表（持续扫描时关注的市场每轮都会扫描）
+    #[command(subcommand)]
+    Watchlist(WatchlistCommand),
+}
+
+#[derive(Args)]
+struct StorageArgs {
+    /// 存储后端: json, sqlite, redis, memory（启用 mongo feature 时还支持 mongo），可用 + 同时写入多个后端，如 sqlite+redis [env: STORAGE_TYPE]
+    #[arg(long, global = true)]
+    storage: Option<String>,
+
+    /// [env: REDIS_URL]
+    #[arg(long, global = true)]
+    redis_url: Option<String>,
+
+    /// [env: DATABASE_URL]
+    #[arg(long, global = true)]
+    database_url: Option<String>,
+
+    /// [env: JSON_DB_PATH]
+    #[arg(long, global = true)]
+    json_path: Option<String>,
+
+    /// [env: MONGO_URL]
+    #[cfg(feature = "mongo")]
+    #[arg(long, global = true)]
+    mongo_url: Option<String>,
+}
+
+impl StorageArgs {
+    /// 用命令行参数覆盖配置
+    fn apply(self, config: &mut Config) {
+        let storage = &mut config.storage;
+        if let Some(storage_type) = self.storage {
+            storage.storage_type = storage_type;
+        }
+        if let Some(url) = self.redis_url {
+            storage.redis_url = url;
+        }
+        if let Some(url) = self.database_url {
+            storage.database_url = url;
+        }
+        if let Some(path) = self.json_path {
+            storage.json_path = path;
+        }
+        #[cfg(feature = "mongo")]
+        if let Some(url) = self.mongo_url {
+            storage.mongo_url = url;
+        }
+    }
+}
+
+#[derive(Args)]
+struct ScanArgs {
+    /// 扫描间隔（秒）[env: SCAN_INTERVAL, 默认 10]
+    #[arg(long)]
+    interval: Option<u64>,
+
+    /// 每轮扫描的市场数量，0 表示分页扫描全部市场 [env: MAX_MARKETS, 默认 50]
+    #[arg(long)]
+    max_markets: Option<u32>,
+
+    /// 价格变化阈值，0 表示任何变化都触发事件 [env: PRICE_THRESHOLD]
+    #[arg(long)]
+    price_threshold: Option<f64>,
+
+    /// 价格阈值的比较基准: price 或 probability（隐含概率）[env: PRICE_CHANGE_MODE, 默认 price]
+    #[arg(long)]
+    price_change_mode: Option<scanner::PriceChangeMode>,
+
+    /// 成交量变化阈值，0 表示任何变化都触发事件 [env: VOLUME_THRESHOLD]
+    #[arg(long)]
+    volume_threshold: Option<f64>,
+
+    /// 包含已关闭的市场：查询全部市场并保存已关闭的市场，对持续扫描和首次全量扫描都生效 [env: INCLUDE_CLOSED]
+    #[arg(long, alias = "persist-closed")]
+    include_closed: bool,
+
+    /// 开始持续扫描前先扫描一遍全部市场 [env: SCAN_ALL_FIRST]
+    #[arg(long)]
+    scan_all_first: bool,
+
+    /// 首次全量扫描的每批数量 [env: BATCH_SIZE, 默认 100]
+    #[arg(long)]
+    batch_size: Option<u32>,
+
+    /// 关注市场的扫描间隔（秒），设置后与常规扫描（--interval）并发运行 [env: WATCHLIST_INTERVAL]
+    #[arg(long)]
+    watchlist_interval: Option<u64>,
+}
+
+impl ScanArgs {
+    /// 用命令行参数覆盖配置，未指定的参数保持配置中的值
+    fn apply(self, config: &mut Config) {
+        let scan = &mut config.scan;
+        if let Some(interval) = self.interval {
+            scan.interval = interval;
+        }
+        if let Some(max_markets) = self.max_markets {
+            scan.max_markets = max_markets;
+        }
+        if let Some(threshold) = self.price_threshold {
+            scan.price_threshold = threshold;
+        }
+        if let Some(mode) = self.price_change_mode {
+            scan.price_change_mode = mode;
+        }
+        if let Some(threshold) = self.volume_threshold {
+            scan.volume_threshold = threshold;
+        }
+        if let Some(batch_size) = self.batch_size {
+            scan.batch_size = batch_size;
+        }
+        if let Some(interval) = self.watchlist_interval {
+            scan.watchlist_interval = Some(interval);
+        }
+        scan.scan_all_first |= self.scan_all_first;
+        scan.include_closed |= self.include_closed;
+    }
+}
+
+#[derive(Args)]
+struct ScanAllArgs {
+    /// 每批获取的市场数量 [env: BATCH_SIZE, 默认 100]
+    #[arg(long)]
+    batch_size: Option<u32>,
+
+    /// 本次扫描包含已关闭/已结算的市场 [env: INCLUDE_CLOSED]
+    #[arg(long)]
+    include_closed: bool,
+
+    /// 从上次中断的断点继续
+    #[arg(long)]
+    resume: bool,
+
+    /// 只统计数量，不写入存储
+    #[arg(long)]
+    dry_run: bool,
+
+    /// 获取到该数量的市场后停止，用于快速抽样
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// 两页请求之间的等待时间（毫秒），0 表示不等待 [env: INTER_BATCH_DELAY_MS, 默认 100]
+    #[arg(long)]
+    inter_batch_delay_ms: Option<u64>,
+}
+
+#[derive(Args)]
+struct QueryArgs {
+    /// 显示的最近事件数量
+    #[arg(long, default_value_t = 10)]
+    events: i32,
+
+    /// 显示的成交量排行数量
+    #[arg(long, default_value_t = 5)]
+    top: i32,
+}
+
+#[derive(Args)]
+struct TimelineArgs {
+    condition_id: String,
+
+    /// 价格历史条数
+    #[arg(long, default_value_t = 1000)]
+    history: i32,
+
+    /// 事件条数
+    #[arg(long, default_value_t = 200)]
+    events: i32,
+
+    /// 输出文件，不指定时输出到标准输出
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ReplayArgs {
+    /// 只回放该时间（RFC 3339，如 2024-01-01T00:00:00Z）之后的事件
+    #[arg(long)]
+    from: Option<DateTime<Utc>>,
+}
+
+#[derive(Args)]
+struct ReconcileArgs {
+    /// 超过多少小时未更新的未关闭市场视为过期
+    #[arg(long, default_value_t = 24)]
+    stale_hours: u64,
+
+    /// 同时列出每一类的市场 ID
+    #[arg(long)]
+    verbose: bool,
+}
+
+#[derive(Args)]
+struct SnapshotArgs {
+    /// 输出文件（日志也写到标准输出，所以快照只能写入文件）
+    output: PathBuf,
+}
+
+#[derive(Args)]
+struct RestoreArgs {
+    /// `snapshot` 导出的快照文件
+    input: PathBuf,
+}
+
+#[derive(Subcommand)]
+enum AlertCommand {
+    /// 添加提醒，例如 `alert add <condition_id> --above 0.8`
+    Add {
+        condition_id: String,
+
+        /// 结果下标或名称（如 yes，忽略大小写和空白），二元市场中 0 通常为 Yes
+        #[arg(long, default_value = "0")]
+        outcome: String,
+
+        /// 价格向上穿越该值时提醒
+        #[arg(long, conflicts_with = "below", required_unless_present = "below")]
+        above: Option<f64>,
+
+        /// 价格向下穿越该值时提醒
+        #[arg(long)]
+        below: Option<f64>,
+    },
+    /// 列出全部提醒
+    List,
+    /// 按 ID 删除提醒
+    Remove { id: String },
+}
+
+#[derive(Subcommand)]
+enum WatchlistCommand {
+    /// 关注市场
+    Add { condition_id: String },
+    /// 列出关注的市场
+    List,
+    /// 取消关注市场
+    Remove { condition_id: String },
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // 加载环境变量（需要在读取配置之前）
+    dotenv::dotenv().ok();
+
     // 初始化日志
-    env_logger::init();
-    
+    tracing_subscriber::fmt::init();
+
+    let cli = Cli::parse();
+
+    // 默认值 <- 配置文件 <- 环境变量 <- 命令行参数
+    let mut config = Config::load_from(cli.config.as_deref())?;
+    cli.storage.apply(&mut config);
+    if let Some(secs) = cli.api_timeout {
+        config.api.timeout_secs = Some(secs);
+    }
+
     info!("启动 Polymarket 扫描器...");
-    
-    // 加载环境变量
-    dotenv::dotenv().ok();
-    
-    // 创建 API 客户端
-    let client = api::PolymarketClient::new()?;
-    
-    // 选择存储后端
-    let storage_type = std::env::var("STORAGE_TYPE").unwrap_or_else(|_| "json".to_string());
-    info!("使用存储后端: {}", storage_type);
-    
-    let storage: Arc<dyn Storage + Send + Sync> = match storage_type.as_str() {
+
+    // 创建 API 客户端（仅在显式设置时才写入原始响应，生产环境默认关闭）
+    if let Some(dir) = &config.api.debug_dump_dir {
+        info!("原始 API 响应将写入: {}", dir.display());
+    }
+    let client = config.client_builder().build()?;
+
+    let storage = open_storage(&config).await?;
+
+    match cli.command {
+        Some(Command::Scan(args)) => {
+            args.apply(&mut config);
+            run_scan(client, storage, &config).await
+        },
+        Some(Command::ScanAll(args)) => run_scan_all(client, storage, &config, args).await,
+        Some(Command::Query(args)) => run_query(storage, args).await,
+        Some(Command::Timeline(args)) => run_timeline(storage, args).await,
+        Some(Command::Replay(args)) => run_replay(storage, args).await,
+        Some(Command::Reconcile(args)) => run_reconcile(client, storage, &config, args).await,
+        Some(Command::Snapshot(args)) => run_snapshot(storage, args).await,
+        Some(Command::Restore(args)) => run_restore(storage, args).await,
+        Some(Command::Alert(command)) => run_alert(storage, command).await,
+        Some(Command::Watchlist(command)) => run_watchlist(storage, command).await,
+        None => {
+            cli.scan.apply(&mut config);
+            run_scan(client, storage, &config).await
+        },
+    }
+}
+
+/// 连接存储后端，失败时在 `storage.connect_timeout_secs` 内按指数退避重试
+///
+/// 与存储服务一起启动（如 docker compose）时，数据库可能还没有就绪。
+/// 只重试连接、初始化和健康检查的错误，后端未编译等配置错误直接返回
+async fn open_storage(config: &Config) -> Result<Arc<dyn Storage + Send + Sync>> {
+    let args = &config.storage;
+    info!("使用存储后端: {}", args.storage_type);
+
+    let deadline = Instant::now() + Duration::from_secs(args.connect_timeout_secs);
+    let mut backoff = Duration::from_millis(500);
+    let mut attempt = 1;
+    loop {
+        let error = match connect_storage(config).await {
+            Ok(storage) => {
+                info!("存储后端初始化完成");
+                return Ok(storage);
+            },
+            Err(e) => match e.downcast_ref::<ScannerError>() {
+                Some(ScannerError::ConfigError(_)) | None => return Err(e),
+                Some(_) => e,
+            },
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            error!("存储后端 {} 不可用: {}", args.storage_type, error);
+            anyhow::bail!("存储后端 {} 连接失败（已尝试 {} 次）: {}", args.storage_type, attempt, error);
+        }
+        let delay = backoff.min(remaining);
+        warn!(
+            "存储后端 {} 第 {} 次连接失败: {}，{:?} 后重试",
+            args.storage_type, attempt, error, delay
+        );
+        tokio::time::sleep(delay).await;
+        backoff = (backoff * 2).min(Duration::from_secs(5));
+        attempt += 1;
+    }
+}
+
+/// 连接并初始化存储后端
+///
+/// `storage_type` 可以用 `+` 组合多个后端（如 `sqlite+redis`），写入所有后端，
+/// 从第一个后端读取
+async fn connect_storage(config: &Config) -> Result<Arc<dyn Storage + Send + Sync>> {
+    let storage_type = &config.storage.storage_type;
+    let storage = if storage_type.contains('+') {
+        let mut backends = Vec::new();
+        for name in storage_type.split('+').map(str::trim) {
+            backends.push(connect_backend(config, name).await?);
+        }
+        Arc::new(TeeStorage::new(backends)?)
+    } else {
+        connect_backend(config, storage_type).await?
+    };
+
+    // 启动时立即检查存储是否可用，尽早暴露配置错误
+    storage.health_check().await?;
+    Ok(storage)
+}
+
+/// 连接并初始化单个存储后端
+// 未启用任何存储 feature 时只剩 memory 后端，用不到 config
+#[cfg_attr(
+    not(any(feature = "redis", feature = "sqlite", feature = "mongo", feature = "json")),
+    allow(unused_variables)
+)]
+async fn connect_backend(
+    config: &Config,
+    storage_type: &str,
+) -> Result<Arc<dyn Storage + Send + Sync>> {
+    let storage: Arc<dyn Storage + Send + Sync> = match storage_type {
+        #[cfg(feature = "redis")]
         "redis" => {
-            let redis_url = std::env::var("REDIS_URL")
-                .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
-            let db = database::Database::new(&redis_url).await?;
+            let db = polymarket_scanner::database::Database::new(&config.storage.redis_url)
+                .await?
+                .with_store_parsed(config.storage.store_parsed);
             db.init().await?;
             Arc::new(db)
         },
+        #[cfg(feature = "sqlite")]
         "sqlite" => {
-            let db_url = std::env::var("DATABASE_URL")
-                .unwrap_or_else(|_| "sqlite:data.db".to_string());
-            let db = db::Database::new(&db_url).await?;
+            let db = polymarket_scanner::db::Database::with_config(
+                &config.storage.database_url,
+                config.sqlite_config(),
+            )
+            .await?;
+            db.init().await?;
+            Arc::new(db)
+        },
+        #[cfg(feature = "mongo")]
+        "mongo" => {
+            let db = polymarket_scanner::mongo::Database::new(&config.storage.mongo_url).await?;
             db.init().await?;
             Arc::new(db)
         },
-        "json" | _ => {
-            let json_path = std::env::var("JSON_DB_PATH")
-                .unwrap_or_else(|_| "data".to_string());
-            let db = json_db::JsonDatabase::new(json_path);
+        "memory" => {
+            // 仅保存在内存中，进程退出后数据丢失
+            Arc::new(polymarket_scanner::InMemoryStorage::new())
+        },
+        // 后端存在但编译时未启用对应 feature，明确报错而不是退回默认后端
+        #[cfg(not(feature = "redis"))]
+        "redis" => anyhow::bail!("存储后端 redis 未编译，请启用 redis feature"),
+        #[cfg(not(feature = "sqlite"))]
+        "sqlite" => anyhow::bail!("存储后端 sqlite 未编译，请启用 sqlite feature"),
+        #[cfg(not(feature = "mongo"))]
+        "mongo" => anyhow::bail!("存储后端 mongo 未编译，请启用 mongo feature"),
+        #[cfg(feature = "json")]
+        _ => {
+            // 默认使用 JSON 文件存储
+            let db = polymarket_scanner::json_db::JsonDatabase::with_config(
+                &config.storage.json_path,
+                config.json_db_config(),
+            );
             db.init().await?;
             Arc::new(db)
         }
+        #[cfg(not(feature = "json"))]
+        other => anyhow::bail!("存储后端 {} 未编译或不存在，请启用 json feature", other),
     };
-    
-    info!("存储后端初始化完成");
-    
+
+    Ok(storage)
+}
+
+/// 持续扫描
+async fn run_scan(
+    client: api::PolymarketClient,
+    storage: Arc<dyn Storage + Send + Sync>,
+    config: &Config,
+) -> Result<()> {
+    // 扫描器配置
+    let scanner_config = config.scanner_config();
+    info!("扫描器配置: {:?}", scanner_config);
+
     // 创建扫描器
-    let scanner = scanner::MarketScanner::with_database(client, storage);
-    
+    let mut scanner = scanner::MarketScanner::with_config(client, storage, scanner_config);
+
+    // 可选：Slack 通知
+    if let Some(webhook_url) = &config.slack.webhook_url {
+        let mut notifier = alerts::SlackNotifier::new(webhook_url.clone());
+        if !config.slack.event_types.is_empty() {
+            notifier = notifier.with_event_types(config.slack.event_types.iter().cloned());
+        }
+        if let Some(min_volume) = config.slack.min_volume {
+            notifier = notifier.with_min_volume(min_volume);
+        }
+        info!("启用 Slack 通知");
+        scanner = scanner.with_notifier(notifier);
+    }
+
+    // 可选：向 Kafka 发布事件
+    #[cfg(feature = "kafka")]
+    if let Some(brokers) = &config.kafka.brokers {
+        let sink = polymarket_scanner::kafka::KafkaSink::new(brokers, config.kafka.topic.clone())?
+            .with_event_types(config.kafka.event_types.iter().cloned())
+            .with_buffer(config.kafka.buffer_size);
+        info!("启用 Kafka 事件发布: {} -> {}", brokers, config.kafka.topic);
+        scanner = scanner.with_notifier(sink);
+    }
+
+    // 可选：通过 SSE 向浏览器推送实时事件
+    #[cfg(feature = "server")]
+    if let Some(addr) = &config.server.sse_addr {
+        let addr: std::net::SocketAddr = addr.parse()?;
+        let events = scanner.event_sender();
+        let control = scanner.control();
+        tokio::spawn(async move {
+            if let Err(e) = polymarket_scanner::server::serve(addr, events, control).await {
+                error!("事件推送服务错误: {}", e);
+            }
+        });
+    }
+
     // 检查是否需要先扫描所有市场
-    if std::env::var("SCAN_ALL_FIRST").unwrap_or_default() == "true" {
+    if config.scan.scan_all_first {
         info!("首次运行：扫描所有市场...");
-        scanner.scan_all_markets(100).await?;
+        let options = scanner::ScanOptions {
+            batch_size: config.scan.batch_size,
+            include_closed: config.scan.include_closed,
+            inter_batch_delay: Duration::from_millis(config.scan.inter_batch_delay_ms),
+            ..Default::default()
+        };
+        scanner.scan_all_markets_with(options).await?;
         info!("所有市场扫描完成");
     }
-    
+
     // 开始持续扫描
-    match scanner.start_scanning(Duration::from_secs(10)).await {
+    match scanner.start().await {
         Ok(_) => info!("扫描器正常关闭"),
+        // 致命错误（如 403）以非零状态退出，便于进程管理器发现
+        Err(e) if e.is_fatal() => return Err(e.into()),
         Err(e) => error!("扫描器错误: {}", e),
     }
-    
+
+    Ok(())
+}
+
+/// 全量扫描（回填）
+async fn run_scan_all(
+    client: api::PolymarketClient,
+    storage: Arc<dyn Storage + Send + Sync>,
+    config: &Config,
+    args: ScanAllArgs,
+) -> Result<()> {
+    let scanner = scanner::MarketScanner::with_config(client, storage, config.scanner_config());
+    let options = scanner::ScanOptions {
+        batch_size: args.batch_size.unwrap_or(config.scan.batch_size),
+        include_closed: args.include_closed || config.scan.include_closed,
+        resume: args.resume,
+        persist: !args.dry_run,
+        max_total: args.limit,
+        inter_batch_delay: Duration::from_millis(
+            args.inter_batch_delay_ms.unwrap_or(config.scan.inter_batch_delay_ms),
+        ),
+        ..Default::default()
+    };
+
+    let total = scanner.scan_all_markets_with(options).await?;
+    info!("全量扫描完成，共 {} 个市场", total);
     Ok(())
 }
 
+/// 查询存储
+async fn run_query(storage: Arc<dyn Storage + Send + Sync>, args: QueryArgs) -> Result<()> {
+    let stats = storage.get_stats().await?;
+    println!("市场总数: {}", stats.market_count);
+    println!("事件总数: {}", stats.event_count);
+    for (event_type, count) in &stats.events_by_type {
+        println!("  {}: {}", event_type, count);
+    }
+    println!("价格历史记录: {}", stats.price_history_points);
+    if let (Some(oldest), Some(newest)) = (stats.oldest_market_update, stats.newest_market_update) {
+        println!("市场更新时间: {} ~ {}", oldest, newest);
+    }
+
+    println!("\n成交量前 {} 的市场:", args.top);
+    for (i, market) in storage.get_top_markets_by_volume(args.top).await?.iter().enumerate() {
+        println!(
+            "  {}. {} (成交量: {})",
+            i + 1,
+            market.question,
+            market.volume.as_deref().unwrap_or("-")
+        );
+    }
+
+    println!("\n最近 {} 个事件:", args.events);
+    for event in storage.get_recent_events(args.events).await? {
+        println!(
+            "  [{}] {} - {}",
+            event.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            event.event_type.name(),
+            event.market.question
+        );
+    }
+
+    Ok(())
+}
+
+/// 导出单个市场的时间线
+async fn run_timeline(storage: Arc<dyn Storage + Send + Sync>, args: TimelineArgs) -> Result<()> {
+    let timeline = storage
+        .export_market_timeline(&args.condition_id, args.history, args.events)
+        .await?;
+    let json = serde_json::to_string_pretty(&timeline)?;
+
+    match args.output {
+        Some(path) => {
+            std::fs::write(&path, json)?;
+            info!(
+                "已导出 {} 个事件和 {} 条价格历史到 {}",
+                timeline.events.len(),
+                timeline.price_history.len(),
+                path.display()
+            );
+        },
+        None => println!("{}", json),
+    }
+
+    Ok(())
+}
+
+/// 按存储顺序输出事件（NDJSON）
+async fn run_replay(storage: Arc<dyn Storage + Send + Sync>, args: ReplayArgs) -> Result<()> {
+    let mut failed = None;
+    let count = replay_events(storage.as_ref(), args.from, |event| {
+        if failed.is_none() {
+            match serde_json::to_string(&event) {
+                Ok(line) => println!("{}", line),
+                Err(e) => failed = Some(e),
+            }
+        }
+    })
+    .await?;
+    if let Some(e) = failed {
+        return Err(e.into());
+    }
+    info!("已回放 {} 个事件", count);
+    Ok(())
+}
+
+/// 检查存储与 API 的一致性
+async fn run_reconcile(
+    client: api::PolymarketClient,
+    storage: Arc<dyn Storage + Send + Sync>,
+    config: &Config,
+    args: ReconcileArgs,
+) -> Result<()> {
+    let scanner = scanner::MarketScanner::with_config(client, storage, config.scanner_config());
+    let report = scanner
+        .reconcile(Duration::from_secs(args.stale_hours * 3600))
+        .await?;
+
+    println!("{}", report);
+    if args.verbose {
+        for (label, ids) in [
+            ("过期", &report.stale),
+            ("下架", &report.delisted),
+            ("已结算未同步", &report.resolved),
+            ("本地缺失", &report.missing_locally),
+        ] {
+            if ids.is_empty() {
+                continue;
+            }
+            println!("\n{} ({}):", label, ids.len());
+            for id in ids {
+                println!("  {}", id);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 导出整个存储的快照
+async fn run_snapshot(storage: Arc<dyn Storage + Send + Sync>, args: SnapshotArgs) -> Result<()> {
+    let file = BufWriter::new(File::create(&args.output)?);
+    let report = snapshot(storage.as_ref(), file).await?;
+    info!(
+        "快照已导出到 {}: {} 个市场、{} 条价格历史、{} 个事件、{} 个关注市场、{} 个提醒",
+        args.output.display(),
+        report.markets, report.price_history_points, report.events, report.watchlist, report.price_alerts
+    );
+    Ok(())
+}
+
+/// 从快照恢复存储
+async fn run_restore(storage: Arc<dyn Storage + Send + Sync>, args: RestoreArgs) -> Result<()> {
+    let report = restore(storage.as_ref(), BufReader::new(File::open(&args.input)?)).await?;
+    info!(
+        "快照已恢复: {} 个市场、{} 条价格历史、{} 个事件、{} 个关注市场、{} 个提醒",
+        report.markets, report.price_history_points, report.events, report.watchlist, report.price_alerts
+    );
+    Ok(())
+}
+
+/// 添加、列出或删除价格提醒
+async fn run_alert(storage: Arc<dyn Storage + Send + Sync>, command: AlertCommand) -> Result<()> {
+    match command {
+        AlertCommand::Add {
+            condition_id,
+            outcome,
+            above,
+            below,
+        } => {
+            let (threshold, direction) = match (above, below) {
+                (Some(threshold), _) => (threshold, CrossDirection::Above),
+                (None, Some(threshold)) => (threshold, CrossDirection::Below),
+                (None, None) => anyhow::bail!("需要指定 --above 或 --below"),
+            };
+            let alert = match outcome.trim().parse::<usize>() {
+                Ok(index) => PriceAlert::new(condition_id, index, threshold, direction),
+                Err(_) => PriceAlert::for_outcome(condition_id, &outcome, threshold, direction),
+            };
+            alert.validate()?;
+            storage.save_price_alert(&alert).await?;
+            println!("已添加提醒: {}", alert.id());
+        },
+        AlertCommand::List => {
+            let alerts = storage.get_price_alerts().await?;
+            println!("共 {} 个提醒:", alerts.len());
+            for alert in alerts {
+                println!("  {}", alert.id());
+            }
+        },
+        AlertCommand::Remove { id } => {
+            storage.delete_price_alert(&id).await?;
+            println!("已删除提醒: {}", id);
+        },
+    }
+
+    Ok(())
+}
+
+/// 添加、列出或移除关注的市场
+async fn run_watchlist(
+    storage: Arc<dyn Storage + Send + Sync>,
+    command: WatchlistCommand,
+) -> Result<()> {
+    match command {
+        WatchlistCommand::Add { condition_id } => {
+            if storage.get_market(&condition_id).await?.is_none() {
+                // 尚未扫描到的市场也可以关注，下一轮扫描时按 ID 获取
+                println!("提示: 存储中还没有市场 {}", condition_id);
+            }
+            storage.add_to_watchlist(&condition_id).await?;
+            println!("已关注: {}", condition_id);
+        },
+        WatchlistCommand::List => {
+            let ids = storage.get_watchlist().await?;
+            println!("共关注 {} 个市场:", ids.len());
+            for id in ids {
+                match storage.get_market(&id).await? {
+                    Some(market) => println!("  {} {}", id, market.question),
+                    None => println!("  {}", id),
+                }
+            }
+        },
+        WatchlistCommand::Remove { condition_id } => {
+            storage.remove_from_watchlist(&condition_id).await?;
+            println!("已取消关注: {}", condition_id);
+        },
+    }
+
+    Ok(())
+}
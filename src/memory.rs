@@ -0,0 +1,306 @@
+use crate::error::Result;
+use crate::storage::{
+    collect_stats, event_matches, events_after, price_volatility, status_counts, top_by_volume,
+    window_start, DatabaseStats, EventBatch, Storage,
+};
+use crate::types::{Market, MarketEvent, PriceAlert};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// (outcome_prices, volume, timestamp)
+type PriceHistoryEntry = (String, String, DateTime<Utc>);
+
+/// Storage that keeps everything in process memory
+///
+/// Nothing is persisted or capped, so it is meant for tests, examples and as
+/// the reference implementation of the `Storage` trait.
+#[derive(Default)]
+pub struct InMemoryStorage {
+    markets: RwLock<HashMap<String, Market>>,
+    updated_at: RwLock<HashMap<String, DateTime<Utc>>>,
+    price_history: RwLock<HashMap<String, Vec<PriceHistoryEntry>>>,
+    events: RwLock<Vec<MarketEvent>>,
+    checkpoints: RwLock<HashMap<String, u32>>,
+    price_alerts: RwLock<HashMap<String, PriceAlert>>,
+    watchlist: RwLock<BTreeSet<String>>,
+}
+
+impl InMemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All stored events, oldest first
+    pub async fn events(&self) -> Vec<MarketEvent> {
+        self.events.read().await.clone()
+    }
+}
+
+#[async_trait]
+impl Storage for InMemoryStorage {
+    async fn init(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn save_market(&self, market: &Market) -> Result<()> {
+        self.save_markets(vec![market.clone()]).await
+    }
+
+    async fn save_markets(&self, markets: Vec<Market>) -> Result<()> {
+        let now = Utc::now();
+        let mut stored = self.markets.write().await;
+        let mut updated_at = self.updated_at.write().await;
+        for market in markets {
+            updated_at.insert(market.condition_id.clone(), now);
+            stored.insert(market.condition_id.clone(), market);
+        }
+        Ok(())
+    }
+
+    async fn save_event(&self, event: &MarketEvent) -> Result<()> {
+        let mut events = self.events.write().await;
+        if !event.id.is_empty() && events.iter().any(|e| e.id == event.id) {
+            return Ok(());
+        }
+        events.push(event.clone());
+        Ok(())
+    }
+
+    async fn get_recent_events(&self, limit: i32) -> Result<Vec<MarketEvent>> {
+        let events = self.events.read().await;
+        Ok(events
+            .iter()
+            .rev()
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_events_after(&self, cursor: Option<&str>, limit: i32) -> Result<EventBatch> {
+        // Events are never removed, so the cursor is simply a position
+        events_after(&self.events.read().await, 0, cursor, limit)
+    }
+
+    async fn get_recent_events_for(
+        &self,
+        condition_id: &str,
+        event_type: Option<&str>,
+        limit: i32,
+    ) -> Result<Vec<MarketEvent>> {
+        let events = self.events.read().await;
+        Ok(events
+            .iter()
+            .rev()
+            .filter(|e| event_matches(e, condition_id, event_type))
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn save_price_history_at(
+        &self,
+        condition_id: &str,
+        outcome_prices: Option<&str>,
+        volume: Option<&str>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<()> {
+        self.price_history
+            .write()
+            .await
+            .entry(condition_id.to_string())
+            .or_default()
+            .push((
+                outcome_prices.unwrap_or("").to_string(),
+                volume.unwrap_or("").to_string(),
+                timestamp,
+            ));
+        Ok(())
+    }
+
+    async fn get_market_count(&self) -> Result<i64> {
+        Ok(self.markets.read().await.len() as i64)
+    }
+
+    async fn get_stats(&self) -> Result<DatabaseStats> {
+        let markets = self.markets.read().await;
+        let updated_at = self.updated_at.read().await;
+        let events = self.events.read().await;
+        let history = self.price_history.read().await;
+        Ok(collect_stats(
+            events.iter(),
+            updated_at.values(),
+            markets.len(),
+            history.values().map(Vec::len).sum(),
+        ))
+    }
+
+    async fn get_market_status_counts(&self) -> Result<HashMap<String, i64>> {
+        Ok(status_counts(self.markets.read().await.values()))
+    }
+
+    /// Newest entries first, like the SQLite and Redis backends
+    async fn get_price_history(
+        &self,
+        condition_id: &str,
+        limit: i32,
+    ) -> Result<Vec<(String, String, DateTime<Utc>)>> {
+        let history = self.price_history.read().await;
+        Ok(history
+            .get(condition_id)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .rev()
+                    .take(limit.max(0) as usize)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn get_price_volatility(
+        &self,
+        condition_id: &str,
+        window: Duration,
+    ) -> Result<Option<f64>> {
+        let since = window_start(window);
+        let history = self.price_history.read().await;
+        Ok(history.get(condition_id).and_then(|entries| {
+            price_volatility(
+                entries
+                    .iter()
+                    .filter(|(_, _, timestamp)| *timestamp >= since)
+                    .map(|(prices, _, _)| prices.as_str()),
+            )
+        }))
+    }
+
+    async fn get_market(&self, condition_id: &str) -> Result<Option<Market>> {
+        Ok(self.markets.read().await.get(condition_id).cloned())
+    }
+
+    async fn get_markets_updated_since(&self, since: DateTime<Utc>) -> Result<Vec<Market>> {
+        let markets = self.markets.read().await;
+        let updated_at = self.updated_at.read().await;
+        Ok(updated_at
+            .iter()
+            .filter(|(_, ts)| **ts > since)
+            .filter_map(|(id, _)| markets.get(id).cloned())
+            .collect())
+    }
+
+    async fn get_stale_markets(&self, older_than: Duration) -> Result<Vec<Market>> {
+        let cutoff = window_start(older_than);
+        let markets = self.markets.read().await;
+        let updated_at = self.updated_at.read().await;
+        let mut stale: Vec<(&DateTime<Utc>, &Market)> = updated_at
+            .iter()
+            .filter(|(_, ts)| **ts <= cutoff)
+            .filter_map(|(id, ts)| markets.get(id).map(|m| (ts, m)))
+            .collect();
+        stale.sort_by_key(|(ts, _)| **ts);
+        Ok(stale.into_iter().map(|(_, m)| m.clone()).collect())
+    }
+
+    async fn get_all_market_ids(&self) -> Result<Vec<String>> {
+        Ok(self.markets.read().await.keys().cloned().collect())
+    }
+
+    async fn get_markets_by_tag(&self, tag: &str) -> Result<Vec<Market>> {
+        let markets = self.markets.read().await;
+        Ok(markets
+            .values()
+            .filter(|m| m.has_tag(tag))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_top_markets_by_volume(&self, limit: i32) -> Result<Vec<Market>> {
+        let markets = self.markets.read().await;
+        Ok(top_by_volume(markets.values().cloned().collect(), limit))
+    }
+
+    async fn save_scan_checkpoint(&self, name: &str, offset: u32) -> Result<()> {
+        self.checkpoints
+            .write()
+            .await
+            .insert(name.to_string(), offset);
+        Ok(())
+    }
+
+    async fn get_scan_checkpoint(&self, name: &str) -> Result<Option<u32>> {
+        Ok(self.checkpoints.read().await.get(name).copied())
+    }
+
+    async fn clear_scan_checkpoint(&self, name: &str) -> Result<()> {
+        self.checkpoints.write().await.remove(name);
+        Ok(())
+    }
+
+    async fn save_price_alert(&self, alert: &PriceAlert) -> Result<()> {
+        self.price_alerts
+            .write()
+            .await
+            .insert(alert.id(), alert.clone());
+        Ok(())
+    }
+
+    async fn get_price_alerts(&self) -> Result<Vec<PriceAlert>> {
+        Ok(self.price_alerts.read().await.values().cloned().collect())
+    }
+
+    async fn delete_price_alert(&self, id: &str) -> Result<()> {
+        self.price_alerts.write().await.remove(id);
+        Ok(())
+    }
+
+    async fn add_to_watchlist(&self, condition_id: &str) -> Result<()> {
+        self.watchlist
+            .write()
+            .await
+            .insert(condition_id.to_string());
+        Ok(())
+    }
+
+    async fn remove_from_watchlist(&self, condition_id: &str) -> Result<()> {
+        self.watchlist.write().await.remove(condition_id);
+        Ok(())
+    }
+
+    async fn get_watchlist(&self) -> Result<Vec<String>> {
+        Ok(self.watchlist.read().await.iter().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::sample_events;
+
+    #[tokio::test]
+    async fn save_events_inserts_500_events_in_one_call() {
+        let storage = InMemoryStorage::new();
+
+        let events = sample_events(500);
+        storage.save_events(events.clone()).await.unwrap();
+        // Saving the same events again is a no-op
+        storage.save_events(events.clone()).await.unwrap();
+
+        let recent: Vec<String> = storage
+            .get_recent_events(1000)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        let expected: Vec<String> = events.into_iter().rev().map(|e| e.id).collect();
+        assert_eq!(recent, expected);
+    }
+}
@@ -0,0 +1,861 @@
+use crate::error::{Result, ScannerError};
+use crate::storage::{price_volatility, window_start, DatabaseStats, EventBatch, Storage};
+use crate::types::{parse_prices, Market, MarketEvent, PriceAlert};
+use async_trait::async_trait;
+use bson::{doc, Bson, Document};
+use chrono::{DateTime, Utc};
+use mongodb::options::{FindOneOptions, FindOptions, ReplaceOptions, UpdateOptions};
+use mongodb::{Client, Collection, IndexModel};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{info, instrument};
+
+/// 未在连接串中指定数据库时使用的默认库名
+const DEFAULT_DATABASE: &str = "polymarket";
+
+/// MongoDB 存储
+///
+/// 集合：
+/// - `markets`：以 condition_id 作为 `_id` upsert，价格、成交量等字段按类型存储
+/// - `events`：市场事件日志
+/// - `price_history`：价格历史快照
+/// - `scan_checkpoints`：全量扫描断点
+/// - `price_alerts`：价格提醒，以提醒 ID 作为 `_id`
+/// - `watchlist`：关注的市场，以 condition_id 作为 `_id`
+pub struct Database {
+    markets: Collection<Document>,
+    events: Collection<Document>,
+    price_history: Collection<Document>,
+    checkpoints: Collection<Document>,
+    price_alerts: Collection<Document>,
+    watchlist: Collection<Document>,
+    db: mongodb::Database,
+}
+
+impl Database {
+    /// 创建 MongoDB 连接
+    pub async fn new(mongo_url: &str) -> Result<Self> {
+        info!("连接 MongoDB: {}", mongo_url);
+
+        let client = Client::with_uri_str(mongo_url)
+            .await
+            .map_err(|e| ScannerError::ConfigError(format!("MongoDB 客户端创建失败: {}", e)))?;
+
+        let db = client
+            .default_database()
+            .unwrap_or_else(|| client.database(DEFAULT_DATABASE));
+
+        info!("MongoDB 连接成功，数据库: {}", db.name());
+        Ok(Self {
+            markets: db.collection("markets"),
+            events: db.collection("events"),
+            price_history: db.collection("price_history"),
+            checkpoints: db.collection("scan_checkpoints"),
+            price_alerts: db.collection("price_alerts"),
+            watchlist: db.collection("watchlist"),
+            db,
+        })
+    }
+
+    async fn create_index(collection: &Collection<Document>, keys: Document) -> Result<()> {
+        let model = IndexModel::builder().keys(keys).build();
+
+        collection
+            .create_index(model, None)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("创建 MongoDB 索引失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 按条件查询最近的事件（新事件在前）
+    async fn find_recent_events(&self, filter: Document, limit: i32) -> Result<Vec<MarketEvent>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "timestamp": -1, "_id": -1 })
+            .limit(limit.max(0) as i64)
+            .build();
+
+        let mut cursor = self
+            .events
+            .find(filter, options)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询事件失败: {}", e)))?;
+
+        let mut events = Vec::new();
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询事件失败: {}", e)))?
+        {
+            let document = cursor
+                .deserialize_current()
+                .map_err(|e| ScannerError::StorageError(format!("读取事件失败: {}", e)))?;
+
+            if let Some(payload) = document.get("payload") {
+                let event = bson::from_bson(payload.clone())
+                    .map_err(|e| ScannerError::StorageError(format!("事件反序列化失败: {}", e)))?;
+                events.push(event);
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+/// 事件游标，见 `get_events_after`
+fn event_cursor(timestamp: bson::DateTime, id: &Bson) -> String {
+    match id {
+        Bson::ObjectId(oid) => format!("{}:oid:{}", timestamp.timestamp_millis(), oid.to_hex()),
+        Bson::String(id) => format!("{}:{}", timestamp.timestamp_millis(), id),
+        other => format!("{}:{}", timestamp.timestamp_millis(), other),
+    }
+}
+
+fn parse_event_cursor(cursor: &str) -> Result<(bson::DateTime, Bson)> {
+    let invalid = || ScannerError::StorageError(format!("无效的事件游标: {}", cursor));
+    let (millis, id) = cursor.split_once(':').ok_or_else(invalid)?;
+    let millis: i64 = millis.parse().map_err(|_| invalid())?;
+    let id = match id.strip_prefix("oid:") {
+        Some(hex) => Bson::ObjectId(bson::oid::ObjectId::parse_str(hex).map_err(|_| invalid())?),
+        None => Bson::String(id.to_string()),
+    };
+    Ok((bson::DateTime::from_millis(millis), id))
+}
+
+/// 解析数值字符串，空串或无法解析时返回 None
+fn parse_number(value: Option<&str>) -> Option<f64> {
+    value.and_then(|v| v.trim().parse::<f64>().ok())
+}
+
+/// 将市场转换为文档：可聚合的字段按类型存储，原始字符串保存在 `raw` 中以便无损还原
+fn market_to_document(market: &Market) -> Document {
+    let outcomes = market.outcome_list().unwrap_or_default();
+
+    doc! {
+        "question_id": market.question_id.clone(),
+        "question": market.question.clone(),
+        "description": market.description.clone(),
+        "market_slug": market.market_slug.clone(),
+        "outcomes": outcomes,
+        "outcome_prices": market.prices(),
+        "volume": market.volume_value(),
+        "liquidity": parse_number(market.liquidity.as_deref()),
+        "end_date": market.end_date.clone(),
+        "active": market.active,
+        "closed": market.closed,
+        "clob_token_ids": market.token_ids(),
+        "tags": market.tags.clone(),
+        "uma_resolution_status": market.uma_resolution_status.clone(),
+        "resolved_outcome": market.resolved_outcome.map(|i| i as i64),
+        // 小写标签，用于忽略大小写的标签查询
+        "tag_keys": market.tags.iter().map(|t| t.to_lowercase()).collect::<Vec<_>>(),
+        "raw": {
+            "outcomes": market.outcomes.clone(),
+            "outcome_prices": market.outcome_prices.clone(),
+            "volume": market.volume.clone(),
+            "liquidity": market.liquidity.clone(),
+            "clob_token_ids": market.clob_token_ids.clone(),
+        },
+    }
+}
+
+fn get_string(doc: &Document, key: &str) -> Option<String> {
+    doc.get_str(key).ok().map(str::to_string)
+}
+
+fn get_bool(doc: &Document, key: &str) -> Option<bool> {
+    doc.get_bool(key).ok()
+}
+
+/// 从文档还原市场
+fn document_to_market(doc: &Document) -> Market {
+    let raw = doc.get_document("raw").cloned().unwrap_or_default();
+
+    Market {
+        condition_id: get_string(doc, "_id").unwrap_or_default(),
+        question_id: get_string(doc, "question_id"),
+        question: get_string(doc, "question").unwrap_or_default(),
+        description: get_string(doc, "description"),
+        market_slug: get_string(doc, "market_slug"),
+        outcomes: get_string(&raw, "outcomes").unwrap_or_default(),
+        outcome_prices: get_string(&raw, "outcome_prices"),
+        volume: get_string(&raw, "volume"),
+        liquidity: get_string(&raw, "liquidity"),
+        end_date: get_string(doc, "end_date"),
+        active: get_bool(doc, "active"),
+        closed: get_bool(doc, "closed"),
+        clob_token_ids: get_string(&raw, "clob_token_ids"),
+        tags: doc
+            .get_array("tags")
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|t| t.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        uma_resolution_status: get_string(doc, "uma_resolution_status"),
+        resolved_outcome: doc
+            .get_i64("resolved_outcome")
+            .ok()
+            .map(|i| i as usize),
+        created_at: None,
+    }
+}
+
+#[async_trait]
+impl Storage for Database {
+    /// 初始化索引
+    async fn init(&self) -> Result<()> {
+        Self::create_index(&self.markets, doc! { "volume": -1 }).await?;
+        Self::create_index(&self.markets, doc! { "market_slug": 1 }).await?;
+        Self::create_index(&self.markets, doc! { "last_updated_at": 1 }).await?;
+        Self::create_index(&self.markets, doc! { "tag_keys": 1 }).await?;
+        Self::create_index(&self.events, doc! { "condition_id": 1, "timestamp": -1 }).await?;
+        Self::create_index(&self.events, doc! { "timestamp": -1 }).await?;
+        Self::create_index(
+            &self.price_history,
+            doc! { "condition_id": 1, "timestamp": -1 },
+        )
+        .await?;
+
+        info!("MongoDB 索引初始化完成");
+        Ok(())
+    }
+
+    /// 发送 ping 命令检查 MongoDB 是否可用
+    async fn health_check(&self) -> Result<()> {
+        self.db
+            .run_command(doc! { "ping": 1 }, None)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("MongoDB 健康检查失败: {}", e)))?;
+        Ok(())
+    }
+
+    /// 保存或更新市场（以 condition_id 为 _id upsert）
+    #[instrument(level = "trace", skip_all, fields(condition_id = %market.condition_id))]
+    async fn save_market(&self, market: &Market) -> Result<()> {
+        let now = bson::DateTime::from_chrono(Utc::now());
+
+        let mut fields = market_to_document(market);
+        fields.insert("last_updated_at", now);
+
+        self.markets
+            .update_one(
+                doc! { "_id": &market.condition_id },
+                doc! {
+                    "$set": fields,
+                    "$setOnInsert": { "first_seen_at": now },
+                },
+                UpdateOptions::builder().upsert(true).build(),
+            )
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("保存市场失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 批量保存市场
+    #[instrument(level = "debug", skip_all, fields(count = markets.len()))]
+    async fn save_markets(&self, markets: Vec<Market>) -> Result<()> {
+        for market in &markets {
+            self.save_market(market).await?;
+        }
+
+        info!("批量保存 {} 个市场", markets.len());
+        Ok(())
+    }
+
+    /// 保存市场事件，事件 ID 作为 `_id`，已存在时忽略
+    #[instrument(level = "trace", skip_all, fields(condition_id = %event.market.condition_id, event_id = %event.id))]
+    async fn save_event(&self, event: &MarketEvent) -> Result<()> {
+        let payload = bson::to_bson(event)
+            .map_err(|e| ScannerError::StorageError(format!("事件序列化失败: {}", e)))?;
+
+        let document = doc! {
+            "condition_id": &event.market.condition_id,
+            "event_type": event.event_type.name(),
+            "timestamp": bson::DateTime::from_chrono(event.timestamp),
+            "volume": event.market.volume_value(),
+            "outcome_prices": event.market.prices(),
+            "payload": payload,
+        };
+
+        if event.id.is_empty() {
+            self.events
+                .insert_one(document, None)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("保存事件失败: {}", e)))?;
+        } else {
+            // upsert 时 `_id` 取自查询条件
+            self.events
+                .update_one(
+                    doc! { "_id": &event.id },
+                    doc! { "$setOnInsert": document },
+                    UpdateOptions::builder().upsert(true).build(),
+                )
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("保存事件失败: {}", e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// 获取最近的事件（新事件在前）
+    async fn get_recent_events(&self, limit: i32) -> Result<Vec<MarketEvent>> {
+        self.find_recent_events(doc! {}, limit).await
+    }
+
+    /// 按 (timestamp, _id) 顺序读取游标之后的事件
+    ///
+    /// 游标为最后一个事件的 `{毫秒时间戳}:{_id}`，`_id` 为 ObjectId 时以 `oid:` 前缀区分
+    async fn get_events_after(&self, cursor: Option<&str>, limit: i32) -> Result<EventBatch> {
+        let filter = match cursor {
+            Some(cursor) => {
+                let (timestamp, id) = parse_event_cursor(cursor)?;
+                doc! { "$or": [
+                    { "timestamp": { "$gt": timestamp } },
+                    { "timestamp": timestamp, "_id": { "$gt": id } },
+                ] }
+            }
+            None => doc! {},
+        };
+        let options = FindOptions::builder()
+            .sort(doc! { "timestamp": 1, "_id": 1 })
+            .limit(limit.max(0) as i64)
+            .build();
+
+        let mut documents = self
+            .events
+            .find(filter, options)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询事件失败: {}", e)))?;
+
+        let mut batch = EventBatch {
+            events: Vec::new(),
+            cursor: cursor.map(str::to_string),
+        };
+        while documents
+            .advance()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询事件失败: {}", e)))?
+        {
+            let document = documents
+                .deserialize_current()
+                .map_err(|e| ScannerError::StorageError(format!("读取事件失败: {}", e)))?;
+
+            if let Some(payload) = document.get("payload") {
+                let event = bson::from_bson(payload.clone())
+                    .map_err(|e| ScannerError::StorageError(format!("事件反序列化失败: {}", e)))?;
+                batch.events.push(event);
+            }
+            if let (Ok(timestamp), Some(id)) =
+                (document.get_datetime("timestamp"), document.get("_id"))
+            {
+                batch.cursor = Some(event_cursor(*timestamp, id));
+            }
+        }
+
+        Ok(batch)
+    }
+
+    /// 获取单个市场最近的事件（新事件在前），可按事件类型过滤
+    async fn get_recent_events_for(
+        &self,
+        condition_id: &str,
+        event_type: Option<&str>,
+        limit: i32,
+    ) -> Result<Vec<MarketEvent>> {
+        let mut filter = doc! { "condition_id": condition_id };
+        if let Some(event_type) = event_type {
+            filter.insert("event_type", event_type);
+        }
+        self.find_recent_events(filter, limit).await
+    }
+
+    /// 保存价格历史
+    #[instrument(level = "trace", skip(self, outcome_prices, volume))]
+    async fn save_price_history_at(
+        &self,
+        condition_id: &str,
+        outcome_prices: Option<&str>,
+        volume: Option<&str>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<()> {
+        let document = doc! {
+            "condition_id": condition_id,
+            "outcome_prices": outcome_prices.and_then(parse_prices),
+            "volume": parse_number(volume),
+            "raw_outcome_prices": outcome_prices,
+            "raw_volume": volume,
+            "timestamp": bson::DateTime::from_chrono(timestamp),
+        };
+
+        self.price_history
+            .insert_one(document, None)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("保存价格历史失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 获取市场数量
+    async fn get_market_count(&self) -> Result<i64> {
+        let count = self
+            .markets
+            .count_documents(None, None)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询市场数量失败: {}", e)))?;
+
+        Ok(count as i64)
+    }
+
+    /// 计数使用 count_documents，按类型统计使用 $group 聚合
+    async fn get_stats(&self) -> Result<DatabaseStats> {
+        let count = |collection: &Collection<Document>| {
+            let collection = collection.clone();
+            async move {
+                collection
+                    .count_documents(None, None)
+                    .await
+                    .map(|n| n as i64)
+                    .map_err(|e| ScannerError::StorageError(format!("统计文档数失败: {}", e)))
+            }
+        };
+        let market_count = count(&self.markets).await?;
+        let event_count = count(&self.events).await?;
+        let price_history_points = count(&self.price_history).await?;
+
+        let mut cursor = self
+            .events
+            .aggregate(
+                vec![doc! { "$group": { "_id": "$event_type", "count": { "$sum": 1 } } }],
+                None,
+            )
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("统计事件失败: {}", e)))?;
+        let mut events_by_type = std::collections::BTreeMap::new();
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("统计事件失败: {}", e)))?
+        {
+            let document = cursor
+                .deserialize_current()
+                .map_err(|e| ScannerError::StorageError(format!("读取事件统计失败: {}", e)))?;
+            if let Ok(event_type) = document.get_str("_id") {
+                let count = match document.get("count") {
+                    Some(Bson::Int32(n)) => *n as i64,
+                    Some(Bson::Int64(n)) => *n,
+                    _ => 0,
+                };
+                events_by_type.insert(event_type.to_string(), count);
+            }
+        }
+
+        let last_updated = |direction: i32| async move {
+            let options = FindOneOptions::builder()
+                .sort(doc! { "last_updated_at": direction })
+                .projection(doc! { "last_updated_at": 1 })
+                .build();
+            self.markets
+                .find_one(None, options)
+                .await
+                .map_err(|e| ScannerError::StorageError(format!("查询市场更新时间失败: {}", e)))
+                .map(|document| {
+                    document
+                        .and_then(|d| d.get_datetime("last_updated_at").ok().copied())
+                        .map(|ts| ts.to_chrono())
+                })
+        };
+
+        Ok(DatabaseStats {
+            market_count,
+            event_count,
+            events_by_type,
+            oldest_market_update: last_updated(1).await?,
+            newest_market_update: last_updated(-1).await?,
+            price_history_points,
+        })
+    }
+
+    /// 按状态分别 count_documents，未关闭且未停用的市场由总数减去其余两类得到
+    async fn get_market_status_counts(&self) -> Result<HashMap<String, i64>> {
+        let count = |filter: Document| {
+            let markets = self.markets.clone();
+            async move {
+                markets
+                    .count_documents(filter, None)
+                    .await
+                    .map(|n| n as i64)
+                    .map_err(|e| ScannerError::StorageError(format!("统计市场状态失败: {}", e)))
+            }
+        };
+        let total = count(doc! {}).await?;
+        let closed = count(doc! { "closed": true }).await?;
+        let inactive = count(doc! { "closed": { "$ne": true }, "active": false }).await?;
+        let resolved = count(doc! { "resolved_outcome": { "$ne": null } }).await?;
+
+        Ok(HashMap::from([
+            ("total".to_string(), total),
+            ("active".to_string(), total - closed - inactive),
+            ("inactive".to_string(), inactive),
+            ("closed".to_string(), closed),
+            ("resolved".to_string(), resolved),
+        ]))
+    }
+
+    /// 获取市场的价格历史
+    async fn get_price_history(
+        &self,
+        condition_id: &str,
+        limit: i32,
+    ) -> Result<Vec<(String, String, DateTime<Utc>)>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "timestamp": -1 })
+            .limit(limit.max(0) as i64)
+            .build();
+
+        let mut cursor = self
+            .price_history
+            .find(doc! { "condition_id": condition_id }, options)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询价格历史失败: {}", e)))?;
+
+        let mut history = Vec::new();
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询价格历史失败: {}", e)))?
+        {
+            let document = cursor
+                .deserialize_current()
+                .map_err(|e| ScannerError::StorageError(format!("读取价格历史失败: {}", e)))?;
+
+            let prices = get_string(&document, "raw_outcome_prices").unwrap_or_default();
+            let volume = get_string(&document, "raw_volume").unwrap_or_default();
+            let timestamp = document
+                .get_datetime("timestamp")
+                .map(|t| t.to_chrono())
+                .unwrap_or_else(|_| Utc::now());
+
+            history.push((prices, volume, timestamp));
+        }
+
+        Ok(history)
+    }
+
+    /// 计算时间窗口内第一个结果价格的标准差
+    async fn get_price_volatility(
+        &self,
+        condition_id: &str,
+        window: Duration,
+    ) -> Result<Option<f64>> {
+        let since = bson::DateTime::from_chrono(window_start(window));
+        let mut cursor = self
+            .price_history
+            .find(
+                doc! { "condition_id": condition_id, "timestamp": { "$gte": since } },
+                None,
+            )
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询价格波动失败: {}", e)))?;
+
+        let mut prices = Vec::new();
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询价格波动失败: {}", e)))?
+        {
+            let document = cursor
+                .deserialize_current()
+                .map_err(|e| ScannerError::StorageError(format!("读取价格历史失败: {}", e)))?;
+            if let Some(raw) = get_string(&document, "raw_outcome_prices") {
+                prices.push(raw);
+            }
+        }
+
+        Ok(price_volatility(prices.iter().map(String::as_str)))
+    }
+
+    /// 获取单个市场
+    async fn get_market(&self, condition_id: &str) -> Result<Option<Market>> {
+        let document = self
+            .markets
+            .find_one(doc! { "_id": condition_id }, None)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询市场失败: {}", e)))?;
+
+        Ok(document.as_ref().map(document_to_market))
+    }
+
+    /// 获取 last_updated_at 晚于指定时间的市场
+    async fn get_markets_updated_since(&self, since: DateTime<Utc>) -> Result<Vec<Market>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "last_updated_at": 1 })
+            .build();
+
+        let mut cursor = self
+            .markets
+            .find(
+                doc! { "last_updated_at": { "$gt": bson::DateTime::from_chrono(since) } },
+                options,
+            )
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询更新的市场失败: {}", e)))?;
+
+        let mut markets = Vec::new();
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询更新的市场失败: {}", e)))?
+        {
+            let document = cursor
+                .deserialize_current()
+                .map_err(|e| ScannerError::StorageError(format!("读取市场失败: {}", e)))?;
+            markets.push(document_to_market(&document));
+        }
+
+        Ok(markets)
+    }
+
+    /// 获取 last_updated_at 不晚于 `older_than` 之前的市场
+    async fn get_stale_markets(&self, older_than: Duration) -> Result<Vec<Market>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "last_updated_at": 1 })
+            .build();
+        let cutoff = bson::DateTime::from_chrono(window_start(older_than));
+
+        let mut cursor = self
+            .markets
+            .find(doc! { "last_updated_at": { "$lte": cutoff } }, options)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询停滞的市场失败: {}", e)))?;
+
+        let mut markets = Vec::new();
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询停滞的市场失败: {}", e)))?
+        {
+            let document = cursor
+                .deserialize_current()
+                .map_err(|e| ScannerError::StorageError(format!("读取市场失败: {}", e)))?;
+            markets.push(document_to_market(&document));
+        }
+
+        Ok(markets)
+    }
+
+    /// 通过 tag_keys 字段按标签查询市场（忽略大小写）
+    async fn get_markets_by_tag(&self, tag: &str) -> Result<Vec<Market>> {
+        let mut cursor = self
+            .markets
+            .find(doc! { "tag_keys": tag.to_lowercase() }, None)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("按标签查询市场失败: {}", e)))?;
+
+        let mut markets = Vec::new();
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("按标签查询市场失败: {}", e)))?
+        {
+            let document = cursor
+                .deserialize_current()
+                .map_err(|e| ScannerError::StorageError(format!("读取市场失败: {}", e)))?;
+            markets.push(document_to_market(&document));
+        }
+
+        Ok(markets)
+    }
+
+    /// 获取所有市场 ID
+    async fn get_all_market_ids(&self) -> Result<Vec<String>> {
+        let ids = self
+            .markets
+            .distinct("_id", None, None)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询市场 ID 失败: {}", e)))?;
+
+        Ok(ids
+            .into_iter()
+            .filter_map(|id| match id {
+                Bson::String(id) => Some(id),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// 按成交量获取前 N 个市场（volume 缺失的排在最后）
+    async fn get_top_markets_by_volume(&self, limit: i32) -> Result<Vec<Market>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "volume": -1 })
+            .limit(limit.max(0) as i64)
+            .build();
+
+        let mut cursor = self
+            .markets
+            .find(None, options)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询市场失败: {}", e)))?;
+
+        let mut markets = Vec::new();
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询市场失败: {}", e)))?
+        {
+            let document = cursor
+                .deserialize_current()
+                .map_err(|e| ScannerError::StorageError(format!("读取市场失败: {}", e)))?;
+            markets.push(document_to_market(&document));
+        }
+
+        Ok(markets)
+    }
+
+    /// 保存全量扫描断点
+    async fn save_scan_checkpoint(&self, name: &str, offset: u32) -> Result<()> {
+        self.checkpoints
+            .update_one(
+                doc! { "_id": name },
+                doc! {
+                    "$set": {
+                        "next_offset": offset as i64,
+                        "updated_at": bson::DateTime::from_chrono(Utc::now()),
+                    }
+                },
+                UpdateOptions::builder().upsert(true).build(),
+            )
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("保存扫描断点失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 获取全量扫描断点
+    async fn get_scan_checkpoint(&self, name: &str) -> Result<Option<u32>> {
+        let document = self
+            .checkpoints
+            .find_one(doc! { "_id": name }, None)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询扫描断点失败: {}", e)))?;
+
+        Ok(document
+            .and_then(|d| d.get_i64("next_offset").ok())
+            .map(|offset| offset as u32))
+    }
+
+    /// 清除全量扫描断点
+    async fn clear_scan_checkpoint(&self, name: &str) -> Result<()> {
+        self.checkpoints
+            .delete_one(doc! { "_id": name }, None)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("清除扫描断点失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 保存价格提醒
+    async fn save_price_alert(&self, alert: &PriceAlert) -> Result<()> {
+        let mut document = bson::to_document(alert)
+            .map_err(|e| ScannerError::StorageError(format!("价格提醒序列化失败: {}", e)))?;
+        document.insert("_id", alert.id());
+
+        self.price_alerts
+            .replace_one(
+                doc! { "_id": alert.id() },
+                document,
+                ReplaceOptions::builder().upsert(true).build(),
+            )
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("保存价格提醒失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 获取全部价格提醒
+    async fn get_price_alerts(&self) -> Result<Vec<PriceAlert>> {
+        let mut cursor = self
+            .price_alerts
+            .find(doc! {}, None)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询价格提醒失败: {}", e)))?;
+
+        let mut alerts = Vec::new();
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询价格提醒失败: {}", e)))?
+        {
+            let document = cursor
+                .deserialize_current()
+                .map_err(|e| ScannerError::StorageError(format!("读取价格提醒失败: {}", e)))?;
+            let alert = bson::from_document(document)
+                .map_err(|e| ScannerError::StorageError(format!("价格提醒反序列化失败: {}", e)))?;
+            alerts.push(alert);
+        }
+
+        Ok(alerts)
+    }
+
+    /// 删除价格提醒
+    async fn delete_price_alert(&self, id: &str) -> Result<()> {
+        self.price_alerts
+            .delete_one(doc! { "_id": id }, None)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("删除价格提醒失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 添加关注市场
+    async fn add_to_watchlist(&self, condition_id: &str) -> Result<()> {
+        self.watchlist
+            .update_one(
+                doc! { "_id": condition_id },
+                doc! { "$setOnInsert": { "added_at": bson::DateTime::now() } },
+                UpdateOptions::builder().upsert(true).build(),
+            )
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("添加关注市场失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 移除关注市场
+    async fn remove_from_watchlist(&self, condition_id: &str) -> Result<()> {
+        self.watchlist
+            .delete_one(doc! { "_id": condition_id }, None)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("移除关注市场失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 获取关注列表
+    async fn get_watchlist(&self) -> Result<Vec<String>> {
+        let options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+        let mut cursor = self
+            .watchlist
+            .find(doc! {}, options)
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询关注列表失败: {}", e)))?;
+
+        let mut ids = Vec::new();
+        while cursor
+            .advance()
+            .await
+            .map_err(|e| ScannerError::StorageError(format!("查询关注列表失败: {}", e)))?
+        {
+            let document = cursor
+                .deserialize_current()
+                .map_err(|e| ScannerError::StorageError(format!("读取关注列表失败: {}", e)))?;
+            if let Ok(id) = document.get_str("_id") {
+                ids.push(id.to_string());
+            }
+        }
+
+        Ok(ids)
+    }
+}
@@ -1,136 +1,1410 @@
-use crate::api::PolymarketClient;
-use crate::error::Result;
-use crate::storage::Storage;
-use crate::types::{EventType, Market, MarketEvent};
-use chrono::Utc;
-use log::{debug, error, info, warn};
-use std::collections::HashMap;
+use crate::alerts::Notifier;
+use crate::api::{CircuitState, GammaQuery, StreamOptions, DEFAULT_INTER_BATCH_DELAY};
+use crate::error::{Result, ScannerError};
+use crate::source::{stream_markets, MarketSource};
+use crate::storage::{window_start, Storage};
+use crate::types::{EventType, Market, MarketDiff, MarketEvent, PriceAlert};
+use lru::LruCache;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Mutex as AsyncMutex, Notify, Semaphore};
+use tracing::{
+    debug, debug_span, error, info, info_span, instrument, trace, trace_span, warn, Instrument,
+};
+
+/// 事件广播通道容量，订阅者落后超过该数量时丢弃最旧的事件
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// 每类错误在汇总中最多保留的示例信息条数
+const MAX_ERROR_SAMPLES: usize = 3;
+
+/// 全量扫描选项
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// 每批获取的市场数量
+    pub batch_size: u32,
+    /// 是否写入数据库，false 时为 dry-run 模式
+    pub persist: bool,
+    /// 本次扫描是否包含已关闭/已结算的市场（同时获取并保存），
+    /// `ScannerConfig::include_closed` 为 true 时总是包含
+    pub include_closed: bool,
+    /// 是否从存储中保存的断点继续扫描
+    pub resume: bool,
+    /// 单页请求失败后的最大重试次数
+    pub max_retries: u32,
+    /// 获取到该数量的市场后停止，用于快速抽样；设置后不读写断点
+    pub max_total: Option<usize>,
+    /// 两页请求之间的等待时间，0 表示不等待，见 `StreamOptions::inter_batch_delay`
+    pub inter_batch_delay: Duration,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 100,
+            persist: true,
+            include_closed: false,
+            resume: false,
+            max_retries: 3,
+            max_total: None,
+            inter_batch_delay: DEFAULT_INTER_BATCH_DELAY,
+        }
+    }
+}
+
+/// 价差追踪配置
+#[derive(Debug, Clone)]
+pub struct SpreadConfig {
+    /// 价差变化超过该值时触发 SpreadChange 事件
+    pub threshold: f64,
+    /// 只为成交量不低于该值的市场获取订单簿，限制 API 调用次数
+    pub min_volume: f64,
+}
+
+impl Default for SpreadConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 0.02,
+            min_volume: 10_000.0,
+        }
+    }
+}
+
+/// 市场活跃度配置
+///
+/// 活跃度 = `volume_weight` × 成交量相对变化（变化量 / 上一轮成交量）
+///        + `price_weight` × 任一结果价格的最大绝对变化
+#[derive(Debug, Clone)]
+pub struct ActivityConfig {
+    pub volume_weight: f64,
+    pub price_weight: f64,
+    /// 活跃度不低于该值时触发 HighActivity 事件
+    pub threshold: f64,
+}
+
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        Self {
+            volume_weight: 1.0,
+            price_weight: 10.0,
+            threshold: 0.5,
+        }
+    }
+}
+
+impl ActivityConfig {
+    /// 计算两轮扫描之间的活跃度，成交量和价格都无法比较时返回 None
+    pub fn score(&self, old: &Market, new: &Market) -> Option<f64> {
+        let volume_change = match (old.volume_value(), new.volume_value()) {
+            (Some(o), Some(n)) if o > 0.0 => Some(((n - o) / o).abs()),
+            _ => None,
+        };
+        let price_change = match (old.prices(), new.prices()) {
+            (Some(o), Some(n)) if o.len() == n.len() => o
+                .iter()
+                .zip(&n)
+                .map(|(o, n)| (n - o).abs())
+                .reduce(f64::max),
+            _ => None,
+        };
+        if volume_change.is_none() && price_change.is_none() {
+            return None;
+        }
+        Some(
+            self.volume_weight * volume_change.unwrap_or(0.0)
+                + self.price_weight * price_change.unwrap_or(0.0),
+        )
+    }
+}
+
+/// 持续扫描的配置
+///
+/// 默认值与之前的行为一致：每 10 秒扫描 50 个活跃市场，任何价格/成交量变化都会产生事件，
+/// 且不保存已关闭的市场
+#[derive(Debug, Clone)]
+pub struct ScannerConfig {
+    /// 扫描间隔
+    pub scan_interval: Duration,
+    /// 每轮扫描间隔的随机偏移比例，如 0.1 表示在间隔的 ±10% 内随机，0（默认）表示不偏移
+    ///
+    /// 多个实例同时启动时避免按相同的节奏同时请求 API。`cadence` 的两个间隔同样生效，
+    /// 超出 0 到 1 的值按边界处理
+    pub scan_jitter: f64,
+    /// 价格变化阈值（任一结果价格的绝对变化），0 表示任何变化都触发事件
+    pub price_threshold: f64,
+    /// `price_threshold` 比较的是原始价格还是归一化后的隐含概率
+    pub price_change_mode: PriceChangeMode,
+    /// 成交量变化阈值（绝对值），0 表示任何变化都触发事件
+    pub volume_threshold: f64,
+    /// 每轮扫描的市场数量，None 表示分页扫描全部市场
+    pub max_markets: Option<u32>,
+    /// 是否包含已关闭的市场，默认 false
+    ///
+    /// 同时决定 API 查询条件和保存时的过滤条件（见 `status_query` 和 `should_persist`），
+    /// 对持续扫描、`scan_all_markets` 和 `reconcile` 都生效。为 false 时只查询活跃市场，
+    /// 其中刚关闭的市场仍会产生 MarketClosed 事件，但不会被保存；
+    /// 为 true 时查询全部市场，已关闭的市场也会保存（同时记录其关闭时的最终价格）
+    pub include_closed: bool,
+    /// 价差追踪配置，None 表示不获取订单簿
+    pub spread: Option<SpreadConfig>,
+    /// 已追踪的市场连续多少轮全量扫描未出现时产生 MarketDelisted 事件，None 表示不检测
+    ///
+    /// 只有 `max_markets` 为 None（每轮分页获取全部市场）时才会检测。
+    /// 已过结束时间（`Market::end_date_parsed`）的市场不会被视为下架；
+    /// 过滤条件排除已关闭市场时，在两轮扫描之间关闭的市场也会被视为下架。
+    pub delist_after_scans: Option<u32>,
+    /// 未关闭的市场超过该时长没有保存过变化时产生 MarketStale 事件，None（默认）表示不检测
+    ///
+    /// 依据存储中的最后更新时间（`Storage::get_stale_markets`），每轮常规扫描检查一次，
+    /// 需要配置存储并保存快照。同一市场只在进入停滞状态时产生一次事件，再次更新后重新检测
+    pub stale_after: Option<Duration>,
+    /// 同时进行的数据库保存任务上限，达到上限时扫描循环等待（背压）
+    pub max_concurrent_saves: usize,
+    /// 价格保留的小数位数，在比较和保存之前统一四舍五入，None 表示保持 API 原样
+    pub price_precision: Option<u32>,
+    /// 活跃度配置，None 表示不计算活跃度
+    pub activity: Option<ActivityConfig>,
+    /// 单轮扫描（含事件处理）耗时超过扫描间隔时是否记录警告
+    pub warn_on_overrun: bool,
+    /// 已追踪市场的新数据未通过校验时是否产生 MalformedUpdate 事件，false 时只跳过并记录日志
+    pub emit_malformed: bool,
+    /// 价格与该市场上一次写入的价格历史相同（在 `price_precision` 精度内）时跳过写入
+    pub dedupe_price_history: bool,
+    /// 关注市场的价格历史改为每轮记录订单簿中间价（`OrderBook::mid_price`），默认 false
+    ///
+    /// 成交稀少的市场最后成交价可能已经过时，中间价的序列更准确。开启后关注市场不再在价格变化时
+    /// 记录最后成交价，而是每轮（设置了 `cadence` 时为每轮关注扫描）记录一次中间价；
+    /// 订单簿单边为空或获取失败的结果使用最后成交价。每个结果需要一次订单簿请求
+    pub watchlist_mid_prices: bool,
+    /// 持续扫描和全量扫描分别写入哪些数据，默认全部写入
+    pub persist_policy: PersistPolicy,
+    /// 关注市场与常规扫描分开的扫描节奏，None（默认）表示按 `scan_interval` 只运行常规扫描
+    ///
+    /// 常规扫描总会补充获取不在本轮结果中的关注市场
+    pub cadence: Option<ScanCadence>,
+    /// 内存中最多追踪的市场数量，None（默认）表示不限制
+    ///
+    /// 超过上限时淘汰最久未出现在扫描结果中的市场。被淘汰的市场之后再出现时从存储重新加载，
+    /// 照常比较变化而不会产生 NewMarket 事件；没有配置存储时会被当作新市场。
+    /// 被淘汰的市场不再参与下架检测，上限应不小于每轮扫描的市场数量
+    pub max_tracked_markets: Option<usize>,
+    /// 设置后按该间隔单独检测新市场，None（默认）表示只通过常规扫描发现新市场
+    ///
+    /// 检测时按创建时间倒序获取上一次检测之后创建的市场（`MarketSource::get_recent_markets`），
+    /// 通常只需要一次请求，常规扫描的间隔可以相应调大。数据源不支持按创建时间获取时，
+    /// 每次检测退回获取与常规扫描相同的市场列表
+    pub new_market_interval: Option<Duration>,
+}
+
+/// 写入存储的数据种类
+///
+/// 例如只需要事件日志时关闭 `save_snapshots`，扫描器不再更新市场表，写入量大幅减少。
+/// SQLite 的价格历史通过外键引用市场表，关闭快照时建议同时关闭价格历史
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PersistPolicy {
+    /// 保存市场的当前快照（`save_markets`），包括全量扫描和结算结果
+    pub save_snapshots: bool,
+    /// 保存事件日志（`save_events`）
+    pub save_events: bool,
+    /// 新市场和价格变化时记录价格历史（`save_price_history`）
+    pub save_price_history: bool,
+}
+
+impl Default for PersistPolicy {
+    fn default() -> Self {
+        Self {
+            save_snapshots: true,
+            save_events: true,
+            save_price_history: true,
+        }
+    }
+}
+
+/// PriceChange 事件的比较基准
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceChangeMode {
+    /// 比较原始价格（包含价差）
+    #[default]
+    Price,
+    /// 比较 `Market::implied_probabilities` 归一化后的隐含概率，无法计算时退回比较原始价格
+    Probability,
+}
+
+impl FromStr for PriceChangeMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "price" => Ok(Self::Price),
+            "probability" => Ok(Self::Probability),
+            other => Err(format!("未知的价格变化模式: {}", other)),
+        }
+    }
+}
+
+/// 两级扫描节奏：关注的市场高频扫描，完整的市场列表低频扫描
+///
+/// 两个扫描循环并发运行，共享已追踪的市场状态，事件进入同一个处理流程
+#[derive(Debug, Clone)]
+pub struct ScanCadence {
+    /// 关注市场的扫描间隔（如几秒）
+    pub watchlist_interval: Duration,
+    /// 常规扫描的间隔（如几分钟）
+    pub full_interval: Duration,
+}
+
+impl Default for ScannerConfig {
+    fn default() -> Self {
+        Self {
+            scan_interval: Duration::from_secs(10),
+            scan_jitter: 0.0,
+            price_threshold: 0.0,
+            price_change_mode: PriceChangeMode::Price,
+            volume_threshold: 0.0,
+            max_markets: Some(50),
+            include_closed: false,
+            spread: None,
+            delist_after_scans: Some(3),
+            stale_after: None,
+            max_concurrent_saves: 8,
+            price_precision: Some(4),
+            activity: None,
+            warn_on_overrun: true,
+            emit_malformed: false,
+            dedupe_price_history: false,
+            watchlist_mid_prices: false,
+            persist_policy: PersistPolicy::default(),
+            cadence: None,
+            max_tracked_markets: None,
+            new_market_interval: None,
+        }
+    }
+}
+
+/// 单轮扫描的汇总
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScanSummary {
+    /// 本轮获取到的市场数量
+    pub markets_scanned: usize,
+    pub new_markets: usize,
+    pub price_changes: usize,
+    pub volume_changes: usize,
+    pub closed: usize,
+    pub delisted: usize,
+    pub stale: usize,
+    pub spread_changes: usize,
+    pub high_activity: usize,
+    pub malformed: usize,
+    pub threshold_crossings: usize,
+    /// 本轮记录到结算结果的市场数（关闭时已结算，或关闭后在之后的扫描中结算）
+    pub resolved: usize,
+    /// 未通过 `Market::validate` 而被跳过的市场数（含产生 MalformedUpdate 的市场）
+    pub invalid: usize,
+    /// 本轮遇到的错误数（获取市场列表、订单簿、保存和通知失败）
+    pub errors: usize,
+    /// 按操作分组的错误次数和示例信息
+    pub error_groups: Vec<ErrorGroup>,
+    /// 本轮扫描耗时（不含事件处理）
+    #[serde(with = "duration_millis")]
+    pub duration: Duration,
+}
+
+impl ScanSummary {
+    /// 按事件类型累加计数
+    fn record(&mut self, event: &MarketEvent) {
+        match event.event_type {
+            EventType::NewMarket => self.new_markets += 1,
+            EventType::PriceChange => self.price_changes += 1,
+            EventType::VolumeUpdate => self.volume_changes += 1,
+            EventType::MarketClosed => self.closed += 1,
+            EventType::MarketDelisted => self.delisted += 1,
+            EventType::MarketStale => self.stale += 1,
+            EventType::SpreadChange { .. } => self.spread_changes += 1,
+            EventType::HighActivity { .. } => self.high_activity += 1,
+            EventType::MalformedUpdate { .. } => self.malformed += 1,
+            EventType::ThresholdCrossed { .. } => self.threshold_crossings += 1,
+        }
+    }
+
+    /// 本轮没有扫描到任何市场
+    pub fn is_idle(&self) -> bool {
+        self.markets_scanned == 0
+    }
+
+    /// 记录一次失败
+    pub fn record_error(&mut self, operation: &'static str, error: impl fmt::Display) {
+        self.errors += 1;
+        ErrorGroup::record(&mut self.error_groups, operation, error.to_string());
+    }
+
+    /// 合并在后台任务中记录的失败
+    fn absorb_errors(&mut self, groups: Vec<ErrorGroup>) {
+        for group in groups {
+            self.errors += group.count;
+            let target = ErrorGroup::find_or_insert(&mut self.error_groups, group.operation);
+            target.count += group.count;
+            for sample in group.samples {
+                target.add_sample(sample);
+            }
+        }
+    }
+}
+
+/// 同一操作在一轮扫描中的失败汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorGroup {
+    /// 失败的操作，例如 "保存市场"
+    pub operation: &'static str,
+    pub count: usize,
+    /// 最多 `MAX_ERROR_SAMPLES` 条不重复的错误信息
+    pub samples: Vec<String>,
+}
+
+impl ErrorGroup {
+    /// 在 `groups` 中为 `operation` 记录一次失败
+    fn record(groups: &mut Vec<ErrorGroup>, operation: &'static str, message: String) {
+        let group = Self::find_or_insert(groups, operation);
+        group.count += 1;
+        group.add_sample(message);
+    }
+
+    fn find_or_insert<'a>(
+        groups: &'a mut Vec<ErrorGroup>,
+        operation: &'static str,
+    ) -> &'a mut ErrorGroup {
+        let index = match groups.iter().position(|g| g.operation == operation) {
+            Some(index) => index,
+            None => {
+                groups.push(ErrorGroup {
+                    operation,
+                    count: 0,
+                    samples: Vec::new(),
+                });
+                groups.len() - 1
+            }
+        };
+        &mut groups[index]
+    }
+
+    fn add_sample(&mut self, message: String) {
+        if self.samples.len() < MAX_ERROR_SAMPLES && !self.samples.contains(&message) {
+            self.samples.push(message);
+        }
+    }
+}
+
+impl fmt::Display for ScanSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "扫描 {} 个市场: 新增 {}, 价格变化 {}, 成交量变化 {}, 关闭 {}, 结算 {}, 下架 {}, 停滞 {}, 价差变化 {}, 高活跃 {}, 阈值穿越 {}, 数据异常 {}, 无效 {}, 错误 {}, 耗时 {:?}",
+            self.markets_scanned,
+            self.new_markets,
+            self.price_changes,
+            self.volume_changes,
+            self.closed,
+            self.resolved,
+            self.delisted,
+            self.stale,
+            self.spread_changes,
+            self.high_activity,
+            self.threshold_crossings,
+            self.malformed,
+            self.invalid,
+            self.errors,
+            self.duration
+        )?;
+        for group in &self.error_groups {
+            write!(
+                f,
+                "; {}失败 {} 次: {}",
+                group.operation,
+                group.count,
+                group.samples.join(" | ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// 存储与 API 的一致性检查结果，见 `MarketScanner::reconcile`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReconcileReport {
+    /// API 返回的市场数量（含已关闭）
+    pub api_markets: usize,
+    /// 存储中的市场数量
+    pub stored_markets: usize,
+    /// API 中仍未关闭，但存储中超过 `stale_after` 未更新的市场
+    pub stale: Vec<String>,
+    /// 存储中有、API 已不再返回的市场
+    pub delisted: Vec<String>,
+    /// 存储中未关闭、API 中已关闭的市场（关闭事件被漏掉）
+    pub resolved: Vec<String>,
+    /// API 中有且按 `include_closed` 应保存、但存储中没有的市场
+    pub missing_locally: Vec<String>,
+}
+
+impl ReconcileReport {
+    /// 存储与 API 完全一致
+    pub fn is_consistent(&self) -> bool {
+        self.stale.is_empty()
+            && self.delisted.is_empty()
+            && self.resolved.is_empty()
+            && self.missing_locally.is_empty()
+    }
+}
+
+impl fmt::Display for ReconcileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "API {} 个市场, 存储 {} 个市场: 过期 {}, 下架 {}, 已结算未同步 {}, 本地缺失 {}",
+            self.api_markets,
+            self.stored_markets,
+            self.stale.len(),
+            self.delisted.len(),
+            self.resolved.len(),
+            self.missing_locally.len()
+        )
+    }
+}
+
+/// 以毫秒数序列化 Duration
+mod duration_millis {
+    use serde::Serializer;
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+}
+
+/// 按 `include_closed` 请求 API 时使用的状态过滤条件
+///
+/// 不包含已关闭的市场时仍只按 `active` 过滤而不加 `closed=false`，
+/// 这样刚关闭的市场还会返回一次，用于产生 MarketClosed 事件
+fn status_query(include_closed: bool) -> GammaQuery {
+    if include_closed {
+        GammaQuery::all()
+    } else {
+        GammaQuery::active()
+    }
+}
+
+/// 是否应将市场写入存储
+///
+/// 默认只存储 end=False（未关闭）的市场；`include_closed` 为 true 时已关闭的市场也会保存，
+/// 用于归档已结算的市场
+fn should_persist(market: &Market, include_closed: bool) -> bool {
+    include_closed || market.closed != Some(true)
+}
+
+/// 移除未通过校验的市场并返回移除的数量，避免无效数据进入存储
+fn retain_valid(markets: &mut Vec<Market>) -> usize {
+    let before = markets.len();
+    markets.retain(|market| match market.validate() {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("跳过无效市场: {}", e);
+            false
+        }
+    });
+    before - markets.len()
+}
+
+/// 在 `interval` 上加入 ±`jitter` 比例的随机偏移，见 `ScannerConfig::scan_jitter`
+fn jittered(interval: Duration, jitter: f64) -> Duration {
+    let jitter = if jitter.is_finite() { jitter.clamp(0.0, 1.0) } else { 0.0 };
+    if jitter == 0.0 {
+        return interval;
+    }
+    interval.mul_f64(1.0 + jitter * (fastrand::f64() * 2.0 - 1.0))
+}
+
+/// 扫描循环中跨轮次保留的状态
+#[derive(Debug)]
+struct ScanState {
+    /// 按最近一次出现的顺序排列，用于 `max_tracked_markets` 淘汰
+    tracked_markets: LruCache<String, Market>,
+    /// condition_id -> 上一次观察到的价差
+    spreads: HashMap<String, f64>,
+    /// condition_id -> 连续未出现在全量扫描中的轮数
+    missing_scans: HashMap<String, u32>,
+    /// 上一轮结束时 API 熔断器的状态，用于记录状态切换
+    circuit: CircuitState,
+    /// 已关闭、尚未结算的市场，每轮常规扫描重新查询结算结果
+    unresolved: HashSet<String>,
+    /// 已产生过 MarketStale 事件、之后尚未更新的市场
+    stale: HashSet<String>,
+    /// 上一次成功检测新市场时请求的开始时间，下一次检测以此为截止时间
+    new_markets_since: Option<chrono::DateTime<chrono::Utc>>,
+    /// 是否已记录过数据源不支持按创建时间获取
+    recent_markets_unsupported: bool,
+}
+
+impl Default for ScanState {
+    fn default() -> Self {
+        Self {
+            tracked_markets: LruCache::unbounded(),
+            spreads: HashMap::new(),
+            missing_scans: HashMap::new(),
+            circuit: CircuitState::default(),
+            unresolved: HashSet::new(),
+            stale: HashSet::new(),
+            new_markets_since: None,
+            recent_markets_unsupported: false,
+        }
+    }
+}
+
+impl ScanState {
+    /// 记录市场的最新数据，追踪的市场超过 `cap` 时淘汰最久未出现的市场
+    fn track(&mut self, market: Market, cap: Option<usize>) {
+        self.tracked_markets.put(market.condition_id.clone(), market);
+        let Some(cap) = cap else {
+            return;
+        };
+        while self.tracked_markets.len() > cap.max(1) {
+            let Some((id, market)) = self.tracked_markets.pop_lru() else {
+                break;
+            };
+            trace!("追踪的市场达到上限，淘汰: {}", market.question);
+            self.spreads.remove(&id);
+            self.missing_scans.remove(&id);
+        }
+    }
+}
+
+/// 一轮扫描的范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanScope {
+    /// 按配置获取市场，并补充关注列表中的市场
+    Full,
+    /// 只获取关注列表中的市场
+    Watchlist,
+    /// 只获取上一次检测之后创建的市场，见 `ScannerConfig::new_market_interval`
+    NewMarkets,
+}
+
+/// 持续扫描的暂停开关，可在扫描器启动后从其他任务（如 REST 接口）控制
+///
+/// 暂停后正在进行的一轮扫描照常完成，之后扫描循环等待恢复而不是轮询；
+/// 不影响 `scan_once` 等直接调用的扫描
+#[derive(Debug, Default)]
+pub struct ScanControl {
+    paused: AtomicBool,
+    resumed: Notify,
+}
+
+impl ScanControl {
+    /// 暂停持续扫描，返回调用前是否已处于暂停状态
+    pub fn pause(&self) -> bool {
+        let was_paused = self.paused.swap(true, Ordering::SeqCst);
+        if !was_paused {
+            info!("⏸️ 扫描已暂停");
+        }
+        was_paused
+    }
+
+    /// 恢复持续扫描，返回调用前是否处于暂停状态
+    pub fn resume(&self) -> bool {
+        let was_paused = self.paused.swap(false, Ordering::SeqCst);
+        if was_paused {
+            info!("▶️ 扫描已恢复");
+            self.resumed.notify_waiters();
+        }
+        was_paused
+    }
+
+    /// 是否处于暂停状态
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// 暂停时等待恢复，未暂停时立即返回
+    async fn wait_while_paused(&self) {
+        loop {
+            // 先注册等待再检查状态，避免在两者之间调用的 resume 丢失
+            let resumed = self.resumed.notified();
+            if !self.is_paused() {
+                return;
+            }
+            resumed.await;
+        }
+    }
+}
+
+/// 单个市场事件的回调
+pub type MarketHandler = Arc<dyn Fn(MarketEvent) + Send + Sync>;
+
+/// 每轮扫描汇总的回调
+pub type SummaryHandler = Arc<dyn Fn(&ScanSummary) + Send + Sync>;
 
 pub struct MarketScanner {
-    client: PolymarketClient,
+    client: Arc<dyn MarketSource>,
     database: Option<Arc<dyn Storage + Send + Sync>>,
-    tracked_markets: HashMap<String, Market>,
+    /// 跨轮次保留的扫描状态，常规扫描和关注市场扫描共享
+    state: AsyncMutex<ScanState>,
+    config: ScannerConfig,
+    notifiers: Vec<Arc<dyn Notifier>>,
+    /// condition_id -> 该市场的回调列表
+    watchers: RwLock<HashMap<String, Vec<MarketHandler>>>,
+    /// condition_id -> 该市场的价格提醒
+    price_alerts: RwLock<HashMap<String, Vec<PriceAlert>>>,
+    /// 关注的市场 condition_id
+    watchlist: RwLock<HashSet<String>>,
+    summary_handlers: RwLock<Vec<SummaryHandler>>,
+    /// 限制并发保存任务数量
+    save_permits: Arc<Semaphore>,
+    /// 所有事件的广播通道
+    events: broadcast::Sender<MarketEvent>,
+    /// condition_id -> 最近一次写入价格历史的价格，用于 `dedupe_price_history`
+    last_history_prices: Arc<Mutex<HashMap<String, Vec<f64>>>>,
+    /// 后台保存和通知任务中的失败，在下一次汇报汇总时合并
+    background_errors: Arc<Mutex<Vec<ErrorGroup>>>,
+    /// 持续扫描的暂停开关
+    control: Arc<ScanControl>,
 }
 
 impl MarketScanner {
     #[allow(dead_code)]
-    pub fn new(client: PolymarketClient) -> Self {
+    pub fn new(client: impl MarketSource + 'static) -> Self {
+        let config = ScannerConfig::default();
         Self {
-            client,
+            client: Arc::new(client),
             database: None,
-            tracked_markets: HashMap::new(),
+            state: AsyncMutex::default(),
+            save_permits: Arc::new(Semaphore::new(config.max_concurrent_saves.max(1))),
+            config,
+            notifiers: Vec::new(),
+            watchers: RwLock::new(HashMap::new()),
+            price_alerts: RwLock::new(HashMap::new()),
+            watchlist: RwLock::new(HashSet::new()),
+            summary_handlers: RwLock::new(Vec::new()),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            last_history_prices: Arc::default(),
+            background_errors: Arc::default(),
+            control: Arc::default(),
         }
     }
 
     /// 创建带数据库支持的扫描器
     pub fn with_database(
-        client: PolymarketClient,
+        client: impl MarketSource + 'static,
+        database: Arc<dyn Storage + Send + Sync>,
+    ) -> Self {
+        Self::with_config(client, database, ScannerConfig::default())
+    }
+
+    /// 使用自定义配置创建带数据库支持的扫描器
+    pub fn with_config(
+        client: impl MarketSource + 'static,
         database: Arc<dyn Storage + Send + Sync>,
+        config: ScannerConfig,
     ) -> Self {
         Self {
-            client,
+            client: Arc::new(client),
             database: Some(database),
-            tracked_markets: HashMap::new(),
+            state: AsyncMutex::default(),
+            save_permits: Arc::new(Semaphore::new(config.max_concurrent_saves.max(1))),
+            config,
+            notifiers: Vec::new(),
+            watchers: RwLock::new(HashMap::new()),
+            price_alerts: RwLock::new(HashMap::new()),
+            watchlist: RwLock::new(HashSet::new()),
+            summary_handlers: RwLock::new(Vec::new()),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            last_history_prices: Arc::default(),
+            background_errors: Arc::default(),
+            control: Arc::default(),
+        }
+    }
+
+    /// 启用基于订单簿的价差追踪
+    pub fn with_spread_tracking(mut self, config: SpreadConfig) -> Self {
+        self.config.spread = Some(config);
+        self
+    }
+
+    /// 注册通知器，事件按通知器自身的 `accepts` 过滤
+    pub fn with_notifier(mut self, notifier: impl Notifier + 'static) -> Self {
+        self.notifiers.push(Arc::new(notifier));
+        self
+    }
+
+    /// 关注指定市场，只有该市场的事件会触发 `handler`
+    ///
+    /// 回调在扫描循环中同步执行，耗时操作应自行 spawn。
+    /// 未被关注的市场照常扫描和保存，只是不会触发回调。
+    pub fn watch<F>(&self, condition_id: impl Into<String>, handler: F)
+    where
+        F: Fn(MarketEvent) + Send + Sync + 'static,
+    {
+        self.watchers
+            .write()
+            .entry(condition_id.into())
+            .or_default()
+            .push(Arc::new(handler));
+    }
+
+    /// 取消关注指定市场，移除它的全部回调
+    pub fn unwatch(&self, condition_id: &str) {
+        self.watchers.write().remove(condition_id);
+    }
+
+    /// 添加价格提醒，有数据库时同时保存，重启后由 `start` 重新加载
+    ///
+    /// 相同的提醒（`PriceAlert::id` 相同）只保留一个
+    pub async fn add_price_alert(&self, alert: PriceAlert) -> Result<()> {
+        alert.validate()?;
+        if let Some(db) = &self.database {
+            db.save_price_alert(&alert).await?;
         }
+        self.insert_price_alert(alert);
+        Ok(())
+    }
+
+    /// 删除价格提醒，返回提醒是否存在
+    pub async fn remove_price_alert(&self, id: &str) -> Result<bool> {
+        if let Some(db) = &self.database {
+            db.delete_price_alert(id).await?;
+        }
+        let mut registry = self.price_alerts.write();
+        let mut removed = false;
+        registry.retain(|_, alerts| {
+            let before = alerts.len();
+            alerts.retain(|a| a.id() != id);
+            removed |= alerts.len() != before;
+            !alerts.is_empty()
+        });
+        Ok(removed)
+    }
+
+    /// 当前的全部价格提醒
+    pub fn price_alerts(&self) -> Vec<PriceAlert> {
+        self.price_alerts
+            .read()
+            .values()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// 从数据库加载已保存的价格提醒，返回加载的数量
+    pub async fn load_price_alerts(&self) -> Result<usize> {
+        let Some(db) = &self.database else {
+            return Ok(0);
+        };
+        let alerts = db.get_price_alerts().await?;
+        let count = alerts.len();
+        for alert in alerts {
+            self.insert_price_alert(alert);
+        }
+        Ok(count)
+    }
+
+    fn insert_price_alert(&self, alert: PriceAlert) {
+        let mut registry = self.price_alerts.write();
+        let alerts = registry.entry(alert.condition_id.clone()).or_default();
+        if !alerts.iter().any(|a| a.id() == alert.id()) {
+            alerts.push(alert);
+        }
+    }
+
+    /// 检查市场两次快照之间触发的价格提醒
+    fn check_price_alerts(&self, old: &Market, new: &Market) -> Vec<MarketEvent> {
+        let registry = self.price_alerts.read();
+        let Some(alerts) = registry.get(&new.condition_id) else {
+            return Vec::new();
+        };
+        alerts
+            .iter()
+            .filter_map(|alert| {
+                let price = alert.check(old, new)?;
+                Some(MarketEvent::new(
+                    new.clone(),
+                    EventType::ThresholdCrossed {
+                        outcome_index: alert.resolve_outcome(new)?,
+                        threshold: alert.threshold,
+                        direction: alert.direction,
+                        price,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// 关注市场，有数据库时同时保存，重启后由 `start` 重新加载
+    ///
+    /// 关注的市场每轮常规扫描都会获取，配置 `ScannerConfig::cadence` 时还会单独高频扫描
+    pub async fn add_to_watchlist(&self, condition_id: impl Into<String>) -> Result<()> {
+        let condition_id = condition_id.into();
+        if condition_id.is_empty() {
+            return Err(ScannerError::ConfigError(
+                "condition_id 不能为空".to_string(),
+            ));
+        }
+        if let Some(db) = &self.database {
+            db.add_to_watchlist(&condition_id).await?;
+        }
+        self.watchlist.write().insert(condition_id);
+        Ok(())
+    }
+
+    /// 取消关注市场，返回该市场之前是否在关注列表中
+    pub async fn remove_from_watchlist(&self, condition_id: &str) -> Result<bool> {
+        if let Some(db) = &self.database {
+            db.remove_from_watchlist(condition_id).await?;
+        }
+        Ok(self.watchlist.write().remove(condition_id))
+    }
+
+    /// 当前关注的市场，按 condition_id 排序
+    pub fn watchlist(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.watchlist.read().iter().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// 从数据库加载关注列表，返回加载的数量
+    pub async fn load_watchlist(&self) -> Result<usize> {
+        let Some(db) = &self.database else {
+            return Ok(0);
+        };
+        let ids = db.get_watchlist().await?;
+        let count = ids.len();
+        self.watchlist.write().extend(ids);
+        Ok(count)
+    }
+
+    /// 订阅全部事件
+    ///
+    /// 扫描循环发送事件时不会等待订阅者；接收过慢的订阅者会收到
+    /// `RecvError::Lagged` 并丢失最旧的事件
+    pub fn subscribe(&self) -> broadcast::Receiver<MarketEvent> {
+        self.events.subscribe()
+    }
+
+    /// 暂停持续扫描，正在进行的一轮扫描会先完成；返回调用前是否已暂停
+    pub fn pause(&self) -> bool {
+        self.control.pause()
+    }
+
+    /// 恢复持续扫描；返回调用前是否处于暂停状态
+    pub fn resume(&self) -> bool {
+        self.control.resume()
+    }
+
+    /// 持续扫描是否处于暂停状态
+    pub fn is_paused(&self) -> bool {
+        self.control.is_paused()
+    }
+
+    /// 暂停开关，可在 `start` 运行期间交给其他任务（如 REST 接口）使用
+    pub fn control(&self) -> Arc<ScanControl> {
+        self.control.clone()
+    }
+
+    /// 事件广播通道的发送端，可在扫描器启动后继续创建新的订阅（如 SSE 连接）
+    pub fn event_sender(&self) -> broadcast::Sender<MarketEvent> {
+        self.events.clone()
+    }
+
+    /// 注册每轮扫描结束后调用的回调，可用于监控扫描进度
+    pub fn on_summary<F>(&self, handler: F)
+    where
+        F: Fn(&ScanSummary) + Send + Sync + 'static,
+    {
+        self.summary_handlers.write().push(Arc::new(handler));
+    }
+
+    /// 当前配置
+    pub fn config(&self) -> &ScannerConfig {
+        &self.config
+    }
+
+    /// 按配置中的扫描间隔开始扫描
+    pub async fn start(&self) -> Result<()> {
+        self.start_scanning(self.config.scan_interval).await
     }
 
-    /// 开始扫描市场
+    /// 开始扫描市场，正常情况下不会返回
+    ///
+    /// 配置了 `ScannerConfig::cadence` 时按其中的两个间隔并发运行常规扫描和关注市场扫描，
+    /// 忽略 `interval`；配置了 `new_market_interval` 时同时运行新市场检测。
+    /// 一般错误在下一轮重试；遇到重试无用的错误（`ScannerError::is_fatal`，
+    /// 如被地区限制访问时的 `Forbidden`）时停止全部扫描并返回该错误
     pub async fn start_scanning(&self, interval: Duration) -> Result<()> {
-        info!("开始扫描 Polymarket 市场，扫描间隔: {:?}", interval);
+        match &self.config.cadence {
+            Some(cadence) => info!(
+                "开始扫描 Polymarket 市场，常规扫描间隔: {:?}，关注市场扫描间隔: {:?}",
+                cadence.full_interval, cadence.watchlist_interval
+            ),
+            None => info!("开始扫描 Polymarket 市场，扫描间隔: {:?}", interval),
+        }
 
         // 如果有数据库，先加载已保存的市场
-        let mut tracked_markets = if let Some(db) = &self.database {
+        if let Some(db) = &self.database {
             info!("正在从数据库加载市场数据...");
-            let mut markets = HashMap::new();
+            let mut markets = Vec::new();
             if let Ok(ids) = db.get_all_market_ids().await {
-                for id in ids {
-                    if let Ok(Some(market)) = db.get_market(&id).await {
-                        markets.insert(id, market);
+                let limit = self.config.max_tracked_markets.unwrap_or(usize::MAX);
+                for id in ids.into_iter().take(limit) {
+                    if let Ok(Some(mut market)) = db.get_market(&id).await {
+                        // 与扫描结果使用相同的精度，避免重启后首轮产生虚假的价格变化
+                        if let Some(decimals) = self.config.price_precision {
+                            market.normalize_prices(decimals);
+                        }
+                        markets.push(market);
                     }
                 }
             }
             info!("已加载 {} 个市场", markets.len());
-            markets
-        } else {
-            self.tracked_markets.clone()
+            match self.load_price_alerts().await {
+                Ok(count) => info!("已加载 {} 个价格提醒", count),
+                Err(e) => warn!("加载价格提醒失败: {}", e),
+            }
+            match self.load_watchlist().await {
+                Ok(count) => info!("已加载 {} 个关注市场", count),
+                Err(e) => warn!("加载关注列表失败: {}", e),
+            }
+            let mut state = self.state.lock().await;
+            for market in markets {
+                state.track(market, self.config.max_tracked_markets);
+            }
+        }
+
+        let scans = async {
+            match &self.config.cadence {
+                Some(cadence) => tokio::try_join!(
+                    self.run_loop(ScanScope::Full, cadence.full_interval),
+                    self.run_loop(ScanScope::Watchlist, cadence.watchlist_interval),
+                )
+                .map(|_| ()),
+                None => self.run_loop(ScanScope::Full, interval).await,
+            }
         };
+        let new_markets = async {
+            match self.config.new_market_interval {
+                Some(interval) => self.run_loop(ScanScope::NewMarkets, interval).await,
+                None => Ok(()),
+            }
+        };
+        tokio::try_join!(scans, new_markets)?;
+        Ok(())
+    }
 
+    /// 按 `interval` 反复执行指定范围的扫描，只在遇到致命错误时返回
+    async fn run_loop(&self, scope: ScanScope, interval: Duration) -> Result<()> {
+        let mut cycle = 0;
         loop {
-            match self.scan_markets(&mut tracked_markets).await {
-                Ok(events) => {
-                    if !events.is_empty() {
-                        info!("检测到 {} 个市场事件", events.len());
-                        for event in events {
-                            self.handle_event(event);
-                        }
-                    } else {
-                        debug!("本轮扫描未发现新事件");
+            if self.control.is_paused() {
+                debug!("{:?} 扫描已暂停，等待恢复", scope);
+                self.control.wait_while_paused().await;
+            }
+            cycle += 1;
+            let started = Instant::now();
+            self.run_cycle(scope, cycle).await?;
+
+            // 扣除本轮耗时，使实际扫描节奏保持在 interval；超时则立即开始下一轮
+            let elapsed = started.elapsed();
+            if elapsed >= interval {
+                if self.config.warn_on_overrun {
+                    warn!(
+                        "本轮扫描耗时 {:?}，超过扫描间隔 {:?}，存储或 API 可能跟不上",
+                        elapsed, interval
+                    );
+                }
+            } else {
+                let interval = jittered(interval, self.config.scan_jitter);
+                tokio::time::sleep(interval.saturating_sub(elapsed)).await;
+            }
+        }
+    }
+
+    /// 执行一轮扫描：检测变化、分发并保存事件、汇报汇总
+    ///
+    /// 本轮的 API 请求、事件处理和保存任务都在同一个 `scan_cycle` span 下。
+    /// 只有致命错误（见 `ScannerError::is_fatal`）会返回，其他错误计入汇总
+    #[instrument(name = "scan_cycle", skip(self))]
+    async fn run_cycle(&self, scope: ScanScope, cycle: u64) -> Result<()> {
+        if scope == ScanScope::Watchlist && self.watchlist.read().is_empty() {
+            trace!("关注列表为空，跳过本轮");
+            return Ok(());
+        }
+        let started = Instant::now();
+        let mut summary = match self.scan_markets(scope).await {
+            Ok((mut events, mut summary)) => {
+                if scope == ScanScope::Full {
+                    events.extend(self.detect_stale_markets(&mut summary).await);
+                }
+                if !events.is_empty() {
+                    info!("检测到 {} 个市场事件", events.len());
+                    for event in &events {
+                        self.handle_event(event);
                     }
+                    self.persist_events(events).await;
+                } else {
+                    debug!("本轮扫描未发现新事件");
                 }
-                Err(e) => {
-                    error!("扫描错误: {}", e);
+                // 分开扫描关注市场时只在关注扫描中记录，避免同一时段记录两次
+                if scope == ScanScope::Watchlist
+                    || (scope == ScanScope::Full && self.config.cadence.is_none())
+                {
+                    self.record_mid_prices(&mut summary).await;
                 }
+                summary
+            }
+            Err(e) if e.is_fatal() => {
+                error!(
+                    "{}。停止扫描：重试不会成功，配置代理后重启；已使用代理时请检查代理出口所在的地区",
+                    e
+                );
+                return Err(e);
+            }
+            Err(e) => {
+                error!("扫描错误: {}", e);
+                let mut summary = ScanSummary {
+                    duration: started.elapsed(),
+                    ..Default::default()
+                };
+                summary.record_error("获取市场列表", e);
+                summary
+            }
+        };
+        if scope == ScanScope::Full {
+            self.update_resolutions(&mut summary).await;
+        }
+        self.report_summary(scope, &mut summary);
+        self.log_circuit_change(&mut *self.state.lock().await);
+        Ok(())
+    }
+
+    /// 立即执行一轮常规扫描，返回本轮产生的事件和汇总
+    ///
+    /// 事件同样会分发给回调、通知器和订阅者并保存。与持续扫描共享已追踪的市场，
+    /// 但不会从数据库加载市场，第一次调用时所有市场都视为新市场。
+    /// 配合 `MockMarketSource` 可以在没有网络的情况下测试事件检测。
+    /// 保存在后台进行，本次保存的失败会计入下一次的汇总
+    pub async fn scan_once(&self) -> Result<(Vec<MarketEvent>, ScanSummary)> {
+        let (mut events, mut summary) = self.scan_markets(ScanScope::Full).await?;
+        events.extend(self.detect_stale_markets(&mut summary).await);
+        for event in &events {
+            self.handle_event(event);
+        }
+        self.persist_events(events.clone()).await;
+        self.record_mid_prices(&mut summary).await;
+        self.update_resolutions(&mut summary).await;
+        self.report_summary(ScanScope::Full, &mut summary);
+        Ok((events, summary))
+    }
+
+    /// 执行一轮常规扫描，只返回本轮产生了事件的市场
+    ///
+    /// 与 `scan_once` 相同，事件照常分发和保存；每个市场只返回一次（按首次出现的事件排序），
+    /// 内容取该市场最后一个事件中的数据。适合只关心变化、不想自己比较前后快照的集成
+    pub async fn scan_changed_only(&self) -> Result<Vec<Market>> {
+        let (events, _) = self.scan_once().await?;
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut markets: Vec<Market> = Vec::new();
+        for event in events {
+            match index.get(&event.market.condition_id) {
+                Some(&i) => markets[i] = event.market,
+                None => {
+                    index.insert(event.market.condition_id.clone(), markets.len());
+                    markets.push(event.market);
+                }
+            }
+        }
+        Ok(markets)
+    }
+
+    /// 从存储中查询超过 `stale_after` 没有更新的未关闭市场，为新进入停滞状态的市场产生事件
+    async fn detect_stale_markets(&self, summary: &mut ScanSummary) -> Vec<MarketEvent> {
+        let (Some(older_than), Some(db)) = (self.config.stale_after, &self.database) else {
+            return Vec::new();
+        };
+        let markets = match db.get_stale_markets(older_than).await {
+            Ok(markets) => markets,
+            Err(e) => {
+                warn!("查询停滞的市场失败: {}", e);
+                summary.record_error("查询停滞市场", e);
+                return Vec::new();
+            }
+        };
+
+        let mut state = self.state.lock().await;
+        // 不再停滞的市场（已重新保存）之后可以再次产生事件
+        let current: HashSet<&str> = markets.iter().map(|m| m.condition_id.as_str()).collect();
+        state.stale.retain(|id| current.contains(id.as_str()));
+
+        let mut events = Vec::new();
+        for market in markets {
+            // 已关闭的市场本来就不会再变化
+            if market.closed == Some(true) || market.resolved_outcome.is_some() {
+                continue;
+            }
+            if !state.stale.insert(market.condition_id.clone()) {
+                continue;
+            }
+            info!("市场超过 {:?} 没有变化 [{}]", older_than, market.question);
+            let event = MarketEvent::new(market, EventType::MarketStale);
+            summary.record(&event);
+            events.push(event);
+        }
+        events
+    }
+
+    /// 查询已关闭、尚未结算的市场，保存新出现的结算结果
+    ///
+    /// 市场通常在关闭一段时间后才完成结算，关闭时还没有结算结果的市场在之后的每轮常规扫描中
+    /// 重新查询，直到结算完成。只在 `include_closed` 且保存快照时跟踪，否则已关闭的市场不会被保存
+    async fn update_resolutions(&self, summary: &mut ScanSummary) {
+        let pending: Vec<String> = self.state.lock().await.unresolved.iter().cloned().collect();
+        if pending.is_empty() {
+            return;
+        }
+        let markets = match self.client.get_markets_by_ids(&pending).await {
+            Ok(markets) => markets,
+            Err(e) => {
+                warn!("获取结算结果失败: {}", e);
+                summary.record_error("获取结算结果", e);
+                return;
+            }
+        };
+
+        // API 不再返回的市场不再查询
+        let mut finished: HashSet<String> = pending.into_iter().collect();
+        let mut resolved = Vec::new();
+        for mut market in markets {
+            if let Some(decimals) = self.config.price_precision {
+                market.normalize_prices(decimals);
+            }
+            if market.fill_resolution() {
+                info!(
+                    "市场已结算 [{}]: {}",
+                    market.question,
+                    market.resolved_outcome_name().unwrap_or_default()
+                );
+                resolved.push(market);
+            } else if market
+                .uma_resolution_status
+                .as_deref()
+                .is_some_and(|s| s.eq_ignore_ascii_case("resolved"))
+            {
+                // 已结算但没有唯一的获胜结果（如按比例结算），不会再有变化
+                info!("市场已结算但没有唯一的获胜结果 [{}]", market.question);
+            } else {
+                finished.remove(&market.condition_id);
             }
+        }
+        {
+            let mut state = self.state.lock().await;
+            for id in &finished {
+                state.unresolved.remove(id);
+            }
+            for market in &resolved {
+                state.track(market.clone(), self.config.max_tracked_markets);
+            }
+        }
+        if resolved.is_empty() {
+            return;
+        }
+        summary.resolved += resolved.len();
+
+        if let Some(db) = &self.database {
+            if let Err(e) = db.save_markets(resolved).await {
+                error!("保存结算结果失败: {}", e);
+                summary.record_error("保存结算结果", e);
+            }
+        }
+    }
+
+    /// API 熔断器状态变化时记录日志
+    fn log_circuit_change(&self, state: &mut ScanState) {
+        let circuit = self.client.circuit_state();
+        if circuit == state.circuit {
+            return;
+        }
+        match circuit {
+            CircuitState::Open => warn!("API 熔断器已打开，冷却期内的请求将直接失败"),
+            CircuitState::HalfOpen => info!("API 熔断器半开，下一次请求将探测 API 是否恢复"),
+            CircuitState::Closed => info!("API 熔断器已关闭，请求恢复正常"),
+        }
+        state.circuit = circuit;
+    }
+
+    /// 合并后台任务记录的失败，记录本轮扫描汇总并调用汇总回调
+    ///
+    /// 新市场检测大多数时候本来就没有市场，不按空扫描警告
+    fn report_summary(&self, scope: ScanScope, summary: &mut ScanSummary) {
+        summary.absorb_errors(std::mem::take(&mut *self.background_errors.lock()));
+        if summary.is_idle() && scope != ScanScope::NewMarkets {
+            warn!("本轮扫描没有获取到任何市场: {}", summary);
+        } else {
+            info!("{}", summary);
+        }
 
-            tokio::time::sleep(interval).await;
+        let handlers = self.summary_handlers.read().clone();
+        for handler in handlers {
+            handler(&*summary);
         }
     }
 
     /// 扫描市场并检测变化
-    async fn scan_markets(
-        &self,
-        tracked_markets: &mut HashMap<String, Market>,
-    ) -> Result<Vec<MarketEvent>> {
-        let markets = self.client.get_markets(Some(50)).await?;
+    ///
+    /// 获取市场、订单簿和从存储重新加载市场时都不持有状态锁，只在检测变化时加锁，
+    /// 各扫描循环的网络请求可以并发进行
+    #[instrument(skip_all)]
+    async fn scan_markets(&self, scope: ScanScope) -> Result<(Vec<MarketEvent>, ScanSummary)> {
+        let started = Instant::now();
+        let mut markets = match scope {
+            ScanScope::Full => self.fetch_markets().await?,
+            ScanScope::Watchlist => Vec::new(),
+            ScanScope::NewMarkets => self.fetch_new_markets().await?,
+        };
+        // 只有拿到完整的市场列表时，"未出现" 才意味着下架；在补充关注市场之前统计
+        let full_listing = scope == ScanScope::Full && self.config.max_markets.is_none();
+        let seen: HashSet<String> = if full_listing {
+            markets.iter().map(|m| m.condition_id.clone()).collect()
+        } else {
+            HashSet::new()
+        };
+        if scope != ScanScope::NewMarkets {
+            self.fetch_missing_watchlist(&mut markets).await?;
+        }
+        for market in &mut markets {
+            if let Some(decimals) = self.config.price_precision {
+                market.normalize_prices(decimals);
+            }
+            market.fill_resolution();
+        }
         let mut events = Vec::new();
+        let mut summary = ScanSummary {
+            markets_scanned: markets.len(),
+            ..Default::default()
+        };
+        let mut spreads = self.fetch_spreads(&markets, &mut summary).await;
+        let mut reloaded = self.reload_evicted(&markets, &mut summary).await;
+
+        let mut state = self.state.lock().await;
+        let state = &mut *state;
+        if let (true, Some(limit)) = (full_listing, self.config.delist_after_scans) {
+            events.extend(Self::detect_delisted(state, &seen, limit));
+        }
+        // 在下架检测之后再过滤，无效的市场仍然算作出现在 API 中
+        let before = markets.len();
+        markets.retain(|market| {
+            let Err(e) = market.validate() else {
+                return true;
+            };
+            warn!("跳过无效市场: {}", e);
+            // 已追踪的市场突然变为无效（常见于结算过程中），单独产生事件而不是价格变化
+            if self.config.emit_malformed {
+                if let Some(old_market) = state.tracked_markets.peek(&market.condition_id) {
+                    events.push(MarketEvent::new(
+                        old_market.clone(),
+                        EventType::MalformedUpdate {
+                            reason: e.to_string(),
+                        },
+                    ));
+                }
+            }
+            false
+        });
+        summary.invalid = before - markets.len();
+
+        let cap = self.config.max_tracked_markets;
 
         for market in markets {
             let condition_id = market.condition_id.clone();
 
-            if let Some(old_market) = tracked_markets.get(&condition_id) {
+            // 因达到上限被淘汰的市场重新出现时，以从存储取回的上一次数据作为比较基准
+            if !state.tracked_markets.contains(&condition_id) {
+                if let Some(stored) = reloaded.remove(&condition_id) {
+                    state.tracked_markets.put(condition_id.clone(), stored);
+                }
+            }
+
+            let spread = spreads.remove(&condition_id);
+            if let Some(event) = self.check_spread(&market, spread, &mut state.spreads) {
+                events.push(event);
+            }
+
+            if let Some(old_market) = state.tracked_markets.peek(&condition_id) {
+                let diff = old_market.diff(&market);
+
                 // 检测价格变化
-                if market.outcome_prices != old_market.outcome_prices {
+                if self.price_changed(&diff, &market) {
                     info!(
                         "市场价格变化 [{}]: {:?} -> {:?}",
                         market.question, old_market.outcome_prices, market.outcome_prices
                     );
 
-                    events.push(MarketEvent {
-                        market: market.clone(),
-                        timestamp: Utc::now(),
-                        event_type: EventType::PriceChange,
-                    });
+                    events.push(
+                        MarketEvent::new(market.clone(), EventType::PriceChange)
+                            .with_changes(diff.prices.clone()),
+                    );
                 }
 
+                // 检查价格提醒
+                events.extend(self.check_price_alerts(old_market, &market));
+
                 // 检测成交量变化
-                if market.volume != old_market.volume {
-                    debug!(
+                if self.volume_changed(&diff) {
+                    trace!(
                         "市场成交量变化 [{}]: {:?} -> {:?}",
-                        market.question, old_market.volume, market.volume
+                        market.question,
+                        old_market.volume,
+                        market.volume
                     );
 
-                    events.push(MarketEvent {
-                        market: market.clone(),
-                        timestamp: Utc::now(),
-                        event_type: EventType::VolumeUpdate,
-                    });
+                    events.push(MarketEvent::new(market.clone(), EventType::VolumeUpdate));
+                }
+
+                // 检测高活跃度
+                if let Some(activity) = &self.config.activity {
+                    if let Some(score) = activity.score(old_market, &market) {
+                        if score >= activity.threshold {
+                            info!("市场交易活跃 [{}]: 活跃度 {:.2}", market.question, score);
+                            events.push(MarketEvent::new(
+                                market.clone(),
+                                EventType::HighActivity { score },
+                            ));
+                        }
+                    }
                 }
 
                 // 检测市场关闭
-                if market.closed == Some(true) && old_market.closed != Some(true) {
-                    info!("市场已关闭 [{}]", market.question);
-
-                    events.push(MarketEvent {
-                        market: market.clone(),
-                        timestamp: Utc::now(),
-                        event_type: EventType::MarketClosed,
-                    });
+                if diff.closed {
+                    match market.resolved_outcome_name() {
+                        Some(outcome) => {
+                            info!("市场已关闭 [{}]，结算结果: {}", market.question, outcome);
+                            summary.resolved += 1;
+                        }
+                        None => {
+                            info!("市场已关闭 [{}]", market.question);
+                            // 关闭的市场不会被保存时，之后的结算结果也无处记录
+                            if self.config.include_closed
+                                && self.config.persist_policy.save_snapshots
+                            {
+                                state.unresolved.insert(condition_id.clone());
+                            }
+                        }
+                    }
+
+                    events.push(MarketEvent::new(market.clone(), EventType::MarketClosed));
                 }
 
                 // 更新追踪的市场
-                tracked_markets.insert(condition_id, market);
+                state.track(market, cap);
             } else {
                 // 新市场
                 info!("发现新市场: {}", market.question);
@@ -140,21 +1414,344 @@ impl MarketScanner {
                     info!("  - 成交量: {}", volume);
                 }
 
-                events.push(MarketEvent {
-                    market: market.clone(),
-                    timestamp: Utc::now(),
-                    event_type: EventType::NewMarket,
-                });
+                events.push(MarketEvent::new(market.clone(), EventType::NewMarket));
 
-                tracked_markets.insert(condition_id, market);
+                state.track(market, cap);
             }
         }
 
-        Ok(events)
+        for event in &events {
+            summary.record(event);
+        }
+        summary.duration = started.elapsed();
+
+        Ok((events, summary))
+    }
+
+    /// 统计本轮全量扫描中缺失的已追踪市场，连续缺失 `limit` 轮后产生 MarketDelisted 事件
+    /// 并停止追踪该市场
+    fn detect_delisted(
+        state: &mut ScanState,
+        seen: &HashSet<String>,
+        limit: u32,
+    ) -> Vec<MarketEvent> {
+        let missing_scans = &mut state.missing_scans;
+        missing_scans.retain(|id, _| !seen.contains(id.as_str()));
+
+        let now = chrono::Utc::now();
+        let mut delisted = Vec::new();
+        for (id, market) in &state.tracked_markets {
+            // 已知关闭或已过结束时间（等待结算）的市场被过滤条件排除是正常的
+            let ended = market.end_date_parsed().is_some_and(|end| end <= now);
+            if seen.contains(id.as_str()) || market.closed == Some(true) || ended {
+                continue;
+            }
+            let count = missing_scans.entry(id.clone()).or_insert(0);
+            *count += 1;
+            if *count >= limit.max(1) {
+                delisted.push(id.clone());
+            }
+        }
+
+        delisted
+            .into_iter()
+            .filter_map(|id| {
+                missing_scans.remove(&id);
+                let market = state.tracked_markets.pop(&id)?;
+                state.spreads.remove(&id);
+                info!("市场已下架 [{}]: 连续 {} 轮未出现", market.question, limit);
+                Some(MarketEvent::new(market, EventType::MarketDelisted))
+            })
+            .collect()
+    }
+
+    /// 从存储读取之前被淘汰出追踪列表的市场，存储中没有（确实是新市场）时返回 None
+    async fn reload_tracked(&self, condition_id: &str, summary: &mut ScanSummary) -> Option<Market> {
+        let db = self.database.as_ref()?;
+        match db.get_market(condition_id).await {
+            Ok(Some(mut market)) => {
+                if let Some(decimals) = self.config.price_precision {
+                    market.normalize_prices(decimals);
+                }
+                trace!("从存储重新加载市场: {}", market.question);
+                Some(market)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("从存储加载市场失败 [{}]: {}", condition_id, e);
+                summary.record_error("加载市场", e);
+                None
+            }
+        }
+    }
+
+    /// 按配置获取本轮要扫描的市场
+    async fn fetch_markets(&self) -> Result<Vec<Market>> {
+        match self.config.max_markets {
+            Some(limit) => {
+                self.client
+                    .get_markets_paginated_with(&status_query(self.config.include_closed), limit, 0)
+                    .await
+            }
+            None => {
+                let mut all_markets = Vec::new();
+                let options = StreamOptions {
+                    query: status_query(self.config.include_closed),
+                    ..Default::default()
+                };
+                stream_markets(self.client.as_ref(), &options, |markets| {
+                    all_markets.extend(markets);
+                    async { Ok(()) }
+                })
+                .await?;
+                Ok(all_markets)
+            }
+        }
+    }
+
+    /// 获取上一次检测之后创建的市场，第一次检测向前看一个 `new_market_interval`
+    ///
+    /// 数据源不支持按创建时间获取时退回 `fetch_markets`，只在第一次时记录警告
+    async fn fetch_new_markets(&self) -> Result<Vec<Market>> {
+        let started = chrono::Utc::now();
+        let since = self.state.lock().await.new_markets_since.unwrap_or_else(|| {
+            window_start(self.config.new_market_interval.unwrap_or(self.config.scan_interval))
+        });
+        match self.client.get_recent_markets(since).await? {
+            Some(markets) => {
+                debug!("{} 之后创建的市场: {} 个", since, markets.len());
+                self.state.lock().await.new_markets_since = Some(started);
+                Ok(markets)
+            }
+            None => {
+                let warned = std::mem::replace(
+                    &mut self.state.lock().await.recent_markets_unsupported,
+                    true,
+                );
+                if !warned {
+                    warn!("数据源不支持按创建时间获取市场，新市场检测退回获取完整的市场列表");
+                }
+                self.fetch_markets().await
+            }
+        }
+    }
+
+    /// 获取不在 `markets` 中的关注市场并追加到 `markets`
+    async fn fetch_missing_watchlist(&self, markets: &mut Vec<Market>) -> Result<()> {
+        let missing: Vec<String> = {
+            let watchlist = self.watchlist.read();
+            if watchlist.is_empty() {
+                return Ok(());
+            }
+            let fetched: HashSet<&str> = markets.iter().map(|m| m.condition_id.as_str()).collect();
+            watchlist
+                .iter()
+                .filter(|id| !fetched.contains(id.as_str()))
+                .cloned()
+                .collect()
+        };
+        if missing.is_empty() {
+            return Ok(());
+        }
+        debug!("获取 {} 个关注市场", missing.len());
+        markets.extend(self.client.get_markets_by_ids(&missing).await?);
+        Ok(())
+    }
+
+    /// 价格是否发生了超过阈值的变化
+    ///
+    /// 阈值为 0 时任何变化都触发；`Probability` 模式比较隐含概率，无法计算时退回比较价格。
+    /// 价格无法逐项比较时只要原始值不同就视为变化，结果数量变化时不视为价格变化
+    fn price_changed(&self, diff: &MarketDiff, market: &Market) -> bool {
+        if let Some((old_count, new_count)) = diff.outcome_count {
+            // 结果数量变化时逐项比较没有意义，不产生价格变化事件
+            warn!(
+                "市场价格数量变化 [{}]: {} -> {}，跳过价格比较",
+                market.question, old_count, new_count
+            );
+            return false;
+        }
+        if diff.raw_prices_changed {
+            return true;
+        }
+        let delta = match self.config.price_change_mode {
+            PriceChangeMode::Probability => diff
+                .max_probability_delta()
+                .or_else(|| diff.max_price_delta()),
+            PriceChangeMode::Price => diff.max_price_delta(),
+        };
+        let Some(delta) = delta else {
+            return false;
+        };
+        let threshold = self.config.price_threshold;
+        if threshold <= 0.0 {
+            delta > 0.0
+        } else {
+            delta >= threshold
+        }
+    }
+
+    /// 成交量是否发生了超过阈值的变化，无法解析时只要原始值不同就视为变化
+    fn volume_changed(&self, diff: &MarketDiff) -> bool {
+        let Some(change) = diff.volume else {
+            return false;
+        };
+        let threshold = self.config.volume_threshold;
+        threshold <= 0.0 || change.delta().is_none_or(|d| d.abs() >= threshold)
+    }
+
+    /// 获取需要追踪价差的市场当前的价差，condition_id -> 价差
+    ///
+    /// 在加状态锁之前调用，成交量低于 `min_volume`、未通过校验或订单簿单边为空的市场不在结果中
+    async fn fetch_spreads(
+        &self,
+        markets: &[Market],
+        summary: &mut ScanSummary,
+    ) -> HashMap<String, f64> {
+        let mut spreads = HashMap::new();
+        let Some(config) = &self.config.spread else {
+            return spreads;
+        };
+        for market in markets {
+            if market.volume_value().unwrap_or(0.0) < config.min_volume || market.validate().is_err() {
+                continue;
+            }
+            let Some(token_id) = market.token_ids().into_iter().next() else {
+                continue;
+            };
+            match self.client.get_order_book(&token_id).await {
+                Ok(book) => {
+                    if let Some(spread) = book.spread() {
+                        spreads.insert(market.condition_id.clone(), spread);
+                    }
+                }
+                Err(e) => {
+                    warn!("获取订单簿失败 [{}]: {}", market.question, e);
+                    summary.record_error("获取订单簿", e);
+                }
+            }
+        }
+        spreads
+    }
+
+    /// 从存储取回本轮出现、但因达到 `max_tracked_markets` 已被淘汰的市场
+    ///
+    /// 只在判断哪些市场未被追踪时短暂加锁，读取存储时不持有状态锁
+    async fn reload_evicted(
+        &self,
+        markets: &[Market],
+        summary: &mut ScanSummary,
+    ) -> HashMap<String, Market> {
+        let mut reloaded = HashMap::new();
+        if self.config.max_tracked_markets.is_none() || self.database.is_none() {
+            return reloaded;
+        }
+        let untracked: Vec<String> = {
+            let state = self.state.lock().await;
+            markets
+                .iter()
+                .filter(|market| !state.tracked_markets.contains(&market.condition_id))
+                .map(|market| market.condition_id.clone())
+                .collect()
+        };
+        for condition_id in untracked {
+            if let Some(market) = self.reload_tracked(&condition_id, summary).await {
+                reloaded.insert(condition_id, market);
+            }
+        }
+        reloaded
+    }
+
+    /// 按本轮获取的价差检测价差变化
+    ///
+    /// 首次观察到的市场只记录价差，不产生事件
+    fn check_spread(
+        &self,
+        market: &Market,
+        spread: Option<f64>,
+        spreads: &mut HashMap<String, f64>,
+    ) -> Option<MarketEvent> {
+        let config = self.config.spread.as_ref()?;
+        let spread = spread?;
+
+        let old = spreads.insert(market.condition_id.clone(), spread)?;
+        if (spread - old).abs() < config.threshold {
+            return None;
+        }
+
+        info!(
+            "市场价差变化 [{}]: {:.4} -> {:.4}",
+            market.question, old, spread
+        );
+        Some(MarketEvent::new(
+            market.clone(),
+            EventType::SpreadChange { old, new: spread },
+        ))
+    }
+
+    /// 为未关闭的关注市场把订单簿中间价记录到价格历史（`watchlist_mid_prices`）
+    ///
+    /// 每个结果分别获取对应 token 的订单簿，没有中间价的结果使用最后成交价，保证价格序列没有缺口
+    async fn record_mid_prices(&self, summary: &mut ScanSummary) {
+        if !self.config.watchlist_mid_prices || !self.config.persist_policy.save_price_history {
+            return;
+        }
+        let Some(db) = &self.database else {
+            return;
+        };
+        let watchlist = self.watchlist.read().clone();
+        let markets: Vec<Market> = {
+            let state = self.state.lock().await;
+            watchlist
+                .iter()
+                .filter_map(|id| state.tracked_markets.peek(id))
+                .filter(|market| market.closed != Some(true))
+                .cloned()
+                .collect()
+        };
+
+        for mut market in markets {
+            let (Some(last_prices), token_ids) = (market.prices(), market.token_ids()) else {
+                continue;
+            };
+            if token_ids.len() != last_prices.len() {
+                debug!("token 数量与价格数量不一致，跳过中间价: {}", market.condition_id);
+                continue;
+            }
+            let mut prices = Vec::with_capacity(token_ids.len());
+            for (token_id, last_price) in token_ids.iter().zip(last_prices) {
+                let mid = match self.client.get_order_book(token_id).await {
+                    Ok(book) => book.mid_price(),
+                    Err(e) => {
+                        warn!("获取订单簿失败 [{}]: {}", market.question, e);
+                        summary.record_error("获取订单簿", e);
+                        None
+                    }
+                };
+                prices.push(mid.unwrap_or(last_price).to_string());
+            }
+            market.outcome_prices = serde_json::to_string(&prices).ok();
+            if let Some(decimals) = self.config.price_precision {
+                market.normalize_prices(decimals);
+            }
+
+            trace!("记录中间价 [{}]: {:?}", market.question, market.outcome_prices);
+            if let Err(e) = db
+                .save_price_history(
+                    &market.condition_id,
+                    market.outcome_prices.as_deref(),
+                    market.volume.as_deref(),
+                )
+                .await
+            {
+                error!("保存中间价失败: {}", e);
+                summary.record_error("保存价格历史", e);
+            }
+        }
     }
 
     /// 处理市场事件
-    fn handle_event(&self, event: MarketEvent) {
+    fn handle_event(&self, event: &MarketEvent) {
         match event.event_type {
             EventType::NewMarket => {
                 info!("📊 新市场上线");
@@ -170,66 +1767,394 @@ impl MarketScanner {
             EventType::MarketClosed => {
                 info!("🔒 市场关闭: {}", event.market.question);
             }
+            EventType::MarketDelisted => {
+                warn!("🗑️ 市场下架: {}", event.market.question);
+            }
+            EventType::MarketStale => {
+                warn!("💤 市场长时间无变化: {}", event.market.question);
+            }
+            EventType::HighActivity { score } => {
+                info!(
+                    "🔥 交易活跃: {} (活跃度 {:.2})",
+                    event.market.question, score
+                );
+            }
+            EventType::SpreadChange { old, new } => {
+                info!(
+                    "↔️ 价差变化: {} ({:.4} -> {:.4})",
+                    event.market.question, old, new
+                );
+            }
+            EventType::MalformedUpdate { ref reason } => {
+                warn!("⚠️ 数据异常: {} ({})", event.market.question, reason);
+            }
+            EventType::ThresholdCrossed {
+                outcome_index,
+                threshold,
+                direction,
+                price,
+            } => {
+                info!(
+                    "🎯 价格穿越阈值: {} (结果 #{} {} {}, 当前 {:.4})",
+                    event.market.question,
+                    outcome_index,
+                    direction.name(),
+                    threshold,
+                    price
+                );
+            }
         }
 
-        // 保存到数据库
-        if let Some(db) = &self.database {
-            tokio::spawn({
-                let db = db.clone();
+        // 广播给订阅者，没有订阅者时发送失败是正常的
+        let _ = self.events.send(event.clone());
+
+        // 调用关注该市场的回调（先复制列表，避免回调中调用 watch 时死锁）
+        let handlers = self
+            .watchers
+            .read()
+            .get(&event.market.condition_id)
+            .cloned();
+        if let Some(handlers) = handlers {
+            for handler in handlers {
+                handler(event.clone());
+            }
+        }
+
+        // 每个通知器在独立任务中推送，失败只记录日志
+        for notifier in &self.notifiers {
+            if notifier.accepts(event) {
+                let notifier = notifier.clone();
                 let event = event.clone();
-                async move {
-                    // 用户要求：只存储 end=False (未关闭) 的市场
-                    if event.market.closed != Some(true) {
-                        if let Err(e) = db.save_market(&event.market).await {
-                            error!("保存市场数据失败: {}", e);
+                let errors = self.background_errors.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = notifier.notify(&event).await {
+                        warn!("通知发送失败 [{}]: {}", notifier.name(), e);
+                        ErrorGroup::record(
+                            &mut errors.lock(),
+                            "发送通知",
+                            format!("{}: {}", notifier.name(), e),
+                        );
+                    }
+                });
+            }
+        }
+    }
+
+    /// 批量保存一轮扫描的事件、价格历史和市场数据，并发任务数达到上限时在此等待
+    async fn persist_events(&self, events: Vec<MarketEvent>) {
+        let Some(db) = &self.database else {
+            return;
+        };
+        let permit = match self.save_permits.clone().acquire_owned().await {
+            Ok(permit) => permit,
+            Err(_) => return,
+        };
+        let include_closed = self.config.include_closed;
+        let policy = self.config.persist_policy;
+        let dedupe = self.config.dedupe_price_history;
+        // 这些市场的价格历史由 `record_mid_prices` 记录
+        let mid_markets = if self.config.watchlist_mid_prices {
+            self.watchlist.read().clone()
+        } else {
+            HashSet::new()
+        };
+        // 价格已按精度四舍五入，差值小于半个最小单位即视为相同
+        let tolerance = self
+            .config
+            .price_precision
+            .map_or(0.0, |decimals| 0.5 / 10f64.powi(decimals as i32));
+        let last_prices = self.last_history_prices.clone();
+        let errors = self.background_errors.clone();
+        let db = db.clone();
+        let span = info_span!("persist_events", events = events.len());
+        let task = async move {
+            let _permit = permit;
+
+            // 同一市场在一轮中可能产生多个事件，只保存一次
+            let mut markets: HashMap<String, Market> = HashMap::new();
+            let mut history = Vec::new();
+            for event in &events {
+                // 新市场和价格变化时记录价格历史（价格已按配置的精度归一化）；
+                // 保存已关闭市场时同时记录关闭时的最终价格
+                let record_history = policy.save_price_history
+                    && match event.event_type {
+                        EventType::NewMarket | EventType::PriceChange => {
+                            !mid_markets.contains(&event.market.condition_id)
+                        }
+                        EventType::MarketClosed => include_closed,
+                        _ => false,
+                    };
+                if record_history {
+                    history.push(event.market.clone());
+                }
+
+                // MalformedUpdate 和 MarketStale 携带的是已保存的数据，无需重复保存；
+                // 重新保存停滞的市场还会刷新其最后更新时间
+                let stored = matches!(
+                    event.event_type,
+                    EventType::MalformedUpdate { .. } | EventType::MarketStale
+                );
+                if policy.save_snapshots && !stored && should_persist(&event.market, include_closed)
+                {
+                    markets.insert(event.market.condition_id.clone(), event.market.clone());
+                }
+            }
+
+            // 先保存市场，价格历史引用市场记录
+            if !markets.is_empty() {
+                let span = debug_span!("save_markets", count = markets.len());
+                if let Err(e) = db
+                    .save_markets(markets.into_values().collect())
+                    .instrument(span)
+                    .await
+                {
+                    error!("保存市场数据失败: {}", e);
+                    ErrorGroup::record(&mut errors.lock(), "保存市场", e.to_string());
+                }
+            }
+            if policy.save_events {
+                let span = debug_span!("save_events", count = events.len());
+                if let Err(e) = db.save_events(events).instrument(span).await {
+                    error!("批量保存事件失败: {}", e);
+                    ErrorGroup::record(&mut errors.lock(), "保存事件", e.to_string());
+                }
+            }
+            for market in history {
+                let prices = market.prices();
+                if dedupe {
+                    if let (Some(new), Some(last)) =
+                        (&prices, last_prices.lock().get(&market.condition_id))
+                    {
+                        let unchanged = new.len() == last.len()
+                            && new
+                                .iter()
+                                .zip(last)
+                                .all(|(n, l)| (n - l).abs() <= tolerance);
+                        if unchanged {
+                            trace!("价格未变化，跳过价格历史: {}", market.condition_id);
+                            continue;
                         }
                     }
                 }
-            });
-        }
+                let span = trace_span!("save_price_history", condition_id = %market.condition_id);
+                if let Err(e) = db
+                    .save_price_history(
+                        &market.condition_id,
+                        market.outcome_prices.as_deref(),
+                        market.volume.as_deref(),
+                    )
+                    .instrument(span)
+                    .await
+                {
+                    error!("保存价格历史失败: {}", e);
+                    ErrorGroup::record(&mut errors.lock(), "保存价格历史", e.to_string());
+                } else if let (true, Some(prices)) = (dedupe, prices) {
+                    last_prices.lock().insert(market.condition_id, prices);
+                }
+            }
+        };
+        tokio::spawn(task.instrument(span));
     }
 
     /// 扫描所有市场并存储到数据库（流式处理）
     pub async fn scan_all_markets(&self, batch_size: u32) -> Result<()> {
-        info!("开始流式扫描所有市场...");
+        self.scan_all_markets_with(ScanOptions {
+            batch_size,
+            ..Default::default()
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// 按选项扫描所有市场，返回 API 返回的市场总数
+    ///
+    /// `persist` 为 false 时只统计和记录日志，不调用 `save_markets`，
+    /// 可用于在写入存储之前验证分页和过滤逻辑。
+    /// 某一批保存失败时立即停止并返回该错误，断点停在最后一批连续保存成功的位置，
+    /// 恢复扫描时从失败的那一批重新开始
+    pub async fn scan_all_markets_with(&self, options: ScanOptions) -> Result<usize> {
+        if options.persist {
+            info!("开始流式扫描所有市场...");
+        } else {
+            info!("开始流式扫描所有市场（dry-run，不写入数据库）...");
+        }
 
         let db = self.database.clone();
+        let persist = options.persist;
+        let save_snapshots = self.config.persist_policy.save_snapshots;
+        if persist && !save_snapshots {
+            warn!("持久化策略不保存市场快照，全量扫描只推进断点，不写入市场");
+        }
+        let include_closed = options.include_closed || self.config.include_closed;
+        // 不同的过滤条件使用不同的断点
+        let checkpoint_name = if include_closed {
+            "scan_all:include_closed"
+        } else {
+            "scan_all"
+        };
+        let would_save = Arc::new(AtomicUsize::new(0));
+        // 抽样扫描只覆盖开头的一部分市场，和 dry-run 一样不读写断点
+        let use_checkpoint = persist && options.max_total.is_none();
+
+        // 从断点恢复
+        let mut start_offset = 0;
+        if let (true, true, Some(db)) = (use_checkpoint, options.resume, &db) {
+            if let Some(offset) = db.get_scan_checkpoint(checkpoint_name).await? {
+                info!("从断点继续扫描: offset={}", offset);
+                start_offset = offset;
+            }
+        }
+
+        let stream_options = StreamOptions {
+            batch_size: options.batch_size,
+            query: status_query(include_closed),
+            start_offset,
+            max_retries: options.max_retries,
+            max_total: options.max_total,
+            inter_batch_delay: options.inter_batch_delay,
+        };
+        let mut next_offset = start_offset;
 
         // 使用流式处理，逐批保存数据
-        let total_count = self
-            .client
-            .get_all_markets_stream(batch_size, |markets| {
-                let db = db.clone();
-                async move {
-                    if let Some(db) = db {
-                        info!("正在保存 {} 个市场到数据库...", markets.len());
-
-                        let mut markets_to_save = Vec::new();
-                        for market in markets {
-                            // 用户要求：只存储 end=False (未关闭) 的市场
-                            if market.closed == Some(true) {
-                                continue;
-                            }
-                            markets_to_save.push(market);
+        let total_count = stream_markets(self.client.as_ref(), &stream_options, |mut markets| {
+            let invalid = retain_valid(&mut markets);
+            if invalid > 0 {
+                warn!("本批跳过 {} 个无效市场", invalid);
+            }
+            let db = db.clone();
+            let would_save = would_save.clone();
+            let span = info_span!("save_batch", offset = next_offset, count = markets.len());
+            next_offset += options.batch_size;
+            let checkpoint = next_offset;
+            async move {
+                if !persist {
+                    let count = markets
+                        .iter()
+                        .filter(|market| should_persist(market, include_closed))
+                        .count();
+                    would_save.fetch_add(count, Ordering::Relaxed);
+                    info!(
+                        "dry-run: 本批 {} 个市场中 {} 个将被保存",
+                        markets.len(),
+                        count
+                    );
+                } else if let Some(db) = db {
+                    info!("正在保存 {} 个市场到数据库...", markets.len());
+
+                    let markets_to_save: Vec<Market> = markets
+                        .into_iter()
+                        .filter(|market| save_snapshots && should_persist(market, include_closed))
+                        .collect();
+
+                    if !markets_to_save.is_empty() {
+                        // 失败时停止扫描：继续保存后面的批次会把断点推进到失败的批次之后，
+                        // 恢复时就会跳过未保存的数据
+                        if let Err(e) = db.save_markets(markets_to_save).await {
+                            error!("批量保存市场失败，停止扫描: {}", e);
+                            return Err(e);
                         }
+                        debug!("已批量保存市场");
+                    }
 
-                        if !markets_to_save.is_empty() {
-                            if let Err(e) = db.save_markets(markets_to_save).await {
-                                error!("批量保存市场失败: {}", e);
-                            } else {
-                                debug!("已批量保存市场");
-                            }
+                    if use_checkpoint {
+                        if let Err(e) = db.save_scan_checkpoint(checkpoint_name, checkpoint).await {
+                            warn!("保存扫描进度失败: {}", e);
                         }
-                    } else {
-                        warn!("未配置数据库，跳过保存");
                     }
-                    Ok(())
+                } else {
+                    warn!("未配置数据库，跳过保存");
                 }
-            })
-            .await?;
+                Ok(())
+            }
+            .instrument(span)
+        })
+        .await?;
 
-        info!("扫描完成！共处理 {} 个市场", total_count);
-        Ok(())
+        if persist {
+            if let (true, Some(db)) = (use_checkpoint, &self.database) {
+                db.clear_scan_checkpoint(checkpoint_name).await?;
+            }
+            info!("扫描完成！共处理 {} 个市场", total_count);
+        } else {
+            info!(
+                "dry-run 扫描完成！共获取 {} 个市场，其中 {} 个将被保存",
+                total_count,
+                would_save.load(Ordering::Relaxed)
+            );
+        }
+        Ok(total_count)
+    }
+
+    /// 对比存储与 API 的全部市场（含已关闭），检查数据是否与 API 脱节
+    ///
+    /// 只读操作，不修改存储。`stale_after` 内没有更新过的未关闭市场视为过期
+    pub async fn reconcile(&self, stale_after: Duration) -> Result<ReconcileReport> {
+        let db = self
+            .database
+            .as_ref()
+            .ok_or_else(|| ScannerError::ConfigError("reconcile 需要配置数据库".to_string()))?;
+
+        info!("开始检查存储与 API 的一致性...");
+        let include_closed = self.config.include_closed;
+
+        // condition_id -> API 中是否已关闭；只保留状态，不在内存中保存完整市场
+        let mut api_closed: HashMap<String, bool> = HashMap::new();
+        let mut missing_candidates = Vec::new();
+        let options = StreamOptions {
+            query: GammaQuery::all(),
+            ..Default::default()
+        };
+        stream_markets(self.client.as_ref(), &options, |markets| {
+            for market in markets {
+                if should_persist(&market, include_closed) {
+                    missing_candidates.push(market.condition_id.clone());
+                }
+                api_closed.insert(market.condition_id, market.closed == Some(true));
+            }
+            async { Ok(()) }
+        })
+        .await?;
+
+        let stored_ids: HashSet<String> = db.get_all_market_ids().await?.into_iter().collect();
+        let recently_updated: HashSet<String> = db
+            .get_markets_updated_since(window_start(stale_after))
+            .await?
+            .into_iter()
+            .map(|m| m.condition_id)
+            .collect();
+
+        let mut report = ReconcileReport {
+            api_markets: api_closed.len(),
+            stored_markets: stored_ids.len(),
+            missing_locally: missing_candidates
+                .into_iter()
+                .filter(|id| !stored_ids.contains(id))
+                .collect(),
+            ..Default::default()
+        };
+
+        for id in &stored_ids {
+            match api_closed.get(id) {
+                None => report.delisted.push(id.clone()),
+                Some(true) => {
+                    let stored_closed = db
+                        .get_market(id)
+                        .await?
+                        .is_some_and(|m| m.closed == Some(true));
+                    if !stored_closed {
+                        report.resolved.push(id.clone());
+                    }
+                }
+                Some(false) => {
+                    if !recently_updated.contains(id) {
+                        report.stale.push(id.clone());
+                    }
+                }
+            }
+        }
+
+        info!("一致性检查完成: {}", report);
+        Ok(report)
     }
 
     /// 打印市场信息
@@ -240,13 +2165,19 @@ impl MarketScanner {
             println!("描述: {}", desc);
         }
         println!("───────────────────────────────────────────");
-        if let Ok(outcomes) = serde_json::from_str::<Vec<String>>(&market.outcomes) {
-            if let Some(prices_str) = &market.outcome_prices {
-                if let Ok(outcome_prices) = serde_json::from_str::<Vec<String>>(prices_str) {
-                    for (i, outcome) in outcomes.iter().enumerate() {
-                        if i < outcome_prices.len() {
-                            println!("  {} - 价格: {}", outcome, outcome_prices[i]);
-                        }
+        if let Some(outcomes) = market.outcome_list() {
+            // 兼容字符串和数字两种价格格式，结果数量不限
+            if let Some(outcome_prices) = market.prices() {
+                let probabilities = market.implied_probabilities();
+                for (i, (outcome, price)) in outcomes.iter().zip(&outcome_prices).enumerate() {
+                    match probabilities.as_ref().and_then(|p| p.get(i)) {
+                        Some(probability) => println!(
+                            "  {} - 价格: {} (隐含概率 {:.1}%)",
+                            outcome,
+                            price,
+                            probability * 100.0
+                        ),
+                        None => println!("  {} - 价格: {}", outcome, price),
                     }
                 }
             } else {
@@ -271,17 +2202,96 @@ impl MarketScanner {
     /// 打印价格变化
     fn print_price_change(&self, market: &Market) {
         println!("\n🔔 {} - 价格更新:", market.question);
-        if let Ok(outcomes) = serde_json::from_str::<Vec<String>>(&market.outcomes) {
-            if let Some(prices_str) = &market.outcome_prices {
-                if let Ok(outcome_prices) = serde_json::from_str::<Vec<f64>>(prices_str) {
-                    for (i, outcome) in outcomes.iter().enumerate() {
-                        if i < outcome_prices.len() {
-                            println!("  {} → {}", outcome, outcome_prices[i]);
-                        }
-                    }
-                }
+        if let (Some(outcomes), Some(outcome_prices)) = (market.outcome_list(), market.prices()) {
+            for (outcome, price) in outcomes.iter().zip(&outcome_prices) {
+                println!("  {} → {}", outcome, price);
             }
         }
         println!();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemoryStorage;
+    use crate::source::MockMarketSource;
+
+    fn market(id: &str, prices: &str) -> Market {
+        Market {
+            condition_id: id.to_string(),
+            question: format!("Market {}", id),
+            outcomes: r#"["Yes","No"]"#.to_string(),
+            outcome_prices: Some(prices.to_string()),
+            active: Some(true),
+            closed: Some(false),
+            ..Default::default()
+        }
+    }
+
+    fn event_types(events: &[MarketEvent]) -> Vec<EventType> {
+        events.iter().map(|e| e.event_type.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn scan_once_reports_new_market_then_price_change_then_error() {
+        let source = Arc::new(MockMarketSource::with_markets(vec![market(
+            "0x01",
+            r#"["0.5","0.5"]"#,
+        )]));
+        let scanner = MarketScanner::new(source.clone());
+
+        let (events, summary) = scanner.scan_once().await.unwrap();
+        assert_eq!(event_types(&events), [EventType::NewMarket]);
+        assert_eq!(summary.new_markets, 1);
+
+        source.set_markets(vec![market("0x01", r#"["0.6","0.4"]"#)]);
+        let (events, summary) = scanner.scan_once().await.unwrap();
+        assert_eq!(event_types(&events), [EventType::PriceChange]);
+        assert_eq!(summary.price_changes, 1);
+
+        source.fail_next("connection reset");
+        match scanner.scan_once().await {
+            Err(ScannerError::NetworkError(message)) => assert_eq!(message, "connection reset"),
+            other => panic!("expected the queued error, got {:?}", other.map(|(e, _)| e)),
+        }
+    }
+
+    /// 等待 `persist_events` 启动的后台保存全部完成
+    async fn wait_for_saves(scanner: &MarketScanner) {
+        let permits = scanner.config.max_concurrent_saves.max(1) as u32;
+        drop(scanner.save_permits.acquire_many(permits).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn market_closed_mid_scan_is_reported_but_not_resaved() {
+        let source = Arc::new(MockMarketSource::with_markets(vec![market(
+            "0x01",
+            r#"["0.5","0.5"]"#,
+        )]));
+        let storage = Arc::new(InMemoryStorage::new());
+        let config = ScannerConfig {
+            include_closed: false,
+            ..Default::default()
+        };
+        let scanner = MarketScanner::with_config(source.clone(), storage.clone(), config);
+
+        scanner.scan_once().await.unwrap();
+        wait_for_saves(&scanner).await;
+        assert!(storage.get_market("0x01").await.unwrap().is_some());
+
+        // 在两轮扫描之间关闭，活跃市场查询仍会返回一次
+        let mut closed = market("0x01", r#"["0.9","0.1"]"#);
+        closed.closed = Some(true);
+        source.set_markets(vec![closed]);
+        let (events, _) = scanner.scan_once().await.unwrap();
+        assert!(events.iter().any(|e| e.event_type == EventType::MarketClosed));
+        wait_for_saves(&scanner).await;
+
+        let stored = storage.get_market("0x01").await.unwrap().unwrap();
+        assert_eq!(stored.closed, Some(false));
+        assert_eq!(stored.outcome_prices.as_deref(), Some(r#"["0.5","0.5"]"#));
+        let logged = storage.events().await;
+        assert!(logged.iter().any(|e| e.event_type == EventType::MarketClosed));
+    }
+}
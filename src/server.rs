@@ -0,0 +1,105 @@
+use crate::error::{Result, ScannerError};
+use crate::scanner::ScanControl;
+use crate::types::MarketEvent;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{get, post};
+use axum::{extract::State, Json, Router};
+use futures::stream::{Stream, StreamExt};
+use serde::Serialize;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tracing::{info, warn};
+
+/// 构建事件推送路由
+///
+/// - `GET /events/stream`: Server-Sent Events，每个 `MarketEvent` 以 JSON 发送，
+///   SSE 事件名为事件类型（如 `PriceChange`）
+///
+/// 每个连接独立订阅 `events`。连接断开时订阅随之释放；接收过慢的连接会跳过
+/// 落后的事件，不会阻塞扫描器
+pub fn router(events: broadcast::Sender<MarketEvent>) -> Router {
+    Router::new()
+        .route("/events/stream", get(events_stream))
+        .with_state(events)
+}
+
+/// 构建扫描控制路由，返回 `{"paused": bool}`
+///
+/// - `GET /control/status`: 当前是否暂停
+/// - `POST /control/pause`: 暂停持续扫描，正在进行的一轮扫描会先完成
+/// - `POST /control/resume`: 恢复持续扫描
+///
+/// 路由本身不做认证，只应监听在内网地址
+pub fn control_router(control: Arc<ScanControl>) -> Router {
+    Router::new()
+        .route("/control/status", get(control_status))
+        .route("/control/pause", post(control_pause))
+        .route("/control/resume", post(control_resume))
+        .with_state(control)
+}
+
+/// 在 `addr` 上启动事件推送和扫描控制服务，直到出错才返回
+pub async fn serve(
+    addr: SocketAddr,
+    events: broadcast::Sender<MarketEvent>,
+    control: Arc<ScanControl>,
+) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| ScannerError::ConfigError(format!("监听 {} 失败: {}", addr, e)))?;
+    info!("事件推送服务已启动: http://{}/events/stream", addr);
+
+    axum::serve(listener, router(events).merge(control_router(control)))
+        .await
+        .map_err(|e| ScannerError::NetworkError(format!("事件推送服务异常退出: {}", e)))
+}
+
+#[derive(Serialize)]
+struct ControlStatus {
+    paused: bool,
+}
+
+async fn control_status(State(control): State<Arc<ScanControl>>) -> Json<ControlStatus> {
+    Json(ControlStatus {
+        paused: control.is_paused(),
+    })
+}
+
+async fn control_pause(State(control): State<Arc<ScanControl>>) -> Json<ControlStatus> {
+    control.pause();
+    Json(ControlStatus { paused: true })
+}
+
+async fn control_resume(State(control): State<Arc<ScanControl>>) -> Json<ControlStatus> {
+    control.resume();
+    Json(ControlStatus { paused: false })
+}
+
+async fn events_stream(
+    State(events): State<broadcast::Sender<MarketEvent>>,
+) -> Sse<impl Stream<Item = std::result::Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(events.subscribe()).filter_map(|item| async move {
+        match item {
+            Ok(event) => match Event::default()
+                .event(event.event_type.name())
+                .json_data(&event)
+            {
+                Ok(sse_event) => Some(Ok(sse_event)),
+                Err(e) => {
+                    warn!("事件序列化失败: {}", e);
+                    None
+                }
+            },
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                warn!("SSE 客户端接收过慢，跳过 {} 个事件", skipped);
+                None
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,423 @@
+use crate::api::{CircuitState, GammaQuery, MarketPage, StreamOptions};
+use crate::error::{Result, ScannerError};
+use crate::types::{Market, OrderBook};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// 扫描器获取市场数据的接口
+///
+/// `PolymarketClient` 是真实实现；`MockMarketSource` 返回预设的数据，
+/// 用于在没有网络的情况下驱动扫描器并检查产生的事件
+#[async_trait]
+pub trait MarketSource: Send + Sync {
+    /// 按过滤条件分页获取市场列表，同时返回因格式错误被跳过的市场数
+    async fn get_markets_page(
+        &self,
+        query: &GammaQuery,
+        limit: u32,
+        offset: u32,
+    ) -> Result<MarketPage>;
+
+    /// 按 condition_id 批量获取市场，只返回匹配到的市场
+    async fn get_markets_by_ids(&self, ids: &[String]) -> Result<Vec<Market>>;
+
+    /// 获取指定 token 的订单簿
+    async fn get_order_book(&self, token_id: &str) -> Result<OrderBook>;
+
+    /// 按过滤条件分页获取市场列表
+    async fn get_markets_paginated_with(
+        &self,
+        query: &GammaQuery,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Market>> {
+        Ok(self.get_markets_page(query, limit, offset).await?.markets)
+    }
+
+    /// 获取 `since` 之后创建的活跃市场，None 表示不支持按创建时间获取（默认），
+    /// 调用方应退回全量扫描。见 `PolymarketClient::get_recent_markets`
+    async fn get_recent_markets(&self, _since: DateTime<Utc>) -> Result<Option<Vec<Market>>> {
+        Ok(None)
+    }
+
+    /// 熔断器状态，没有熔断器的实现始终为 `Closed`
+    fn circuit_state(&self) -> CircuitState {
+        CircuitState::Closed
+    }
+}
+
+/// 共享同一个数据源，例如测试中保留 `Arc<MockMarketSource>` 以便在两轮扫描之间修改数据
+#[async_trait]
+impl<T: MarketSource + ?Sized> MarketSource for Arc<T> {
+    async fn get_markets_page(
+        &self,
+        query: &GammaQuery,
+        limit: u32,
+        offset: u32,
+    ) -> Result<MarketPage> {
+        (**self).get_markets_page(query, limit, offset).await
+    }
+
+    async fn get_markets_by_ids(&self, ids: &[String]) -> Result<Vec<Market>> {
+        (**self).get_markets_by_ids(ids).await
+    }
+
+    async fn get_order_book(&self, token_id: &str) -> Result<OrderBook> {
+        (**self).get_order_book(token_id).await
+    }
+
+    async fn get_recent_markets(&self, since: DateTime<Utc>) -> Result<Option<Vec<Market>>> {
+        (**self).get_recent_markets(since).await
+    }
+
+    fn circuit_state(&self) -> CircuitState {
+        (**self).circuit_state()
+    }
+}
+
+/// 分页获取市场，可重试的错误（见 `ScannerError::is_retryable`）按指数退避重试
+pub(crate) async fn get_markets_page_with_retry<S: MarketSource + ?Sized>(
+    source: &S,
+    query: &GammaQuery,
+    limit: u32,
+    offset: u32,
+    max_retries: u32,
+) -> Result<MarketPage> {
+    let mut attempt = 0;
+    loop {
+        match source.get_markets_page(query, limit, offset).await {
+            Ok(page) => return Ok(page),
+            Err(e) if attempt < max_retries && e.is_retryable() => {
+                let delay = std::time::Duration::from_secs(1 << attempt.min(5));
+                attempt += 1;
+                warn!(
+                    "获取市场失败 (offset={}): {}，{:?} 后第 {}/{} 次重试",
+                    offset, e, delay, attempt, max_retries
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// `get_recent_markets` 每页请求的数量，新市场通常很少，一页就能越过截止时间
+pub(crate) const RECENT_MARKETS_PAGE_SIZE: u32 = 100;
+
+/// 从按创建时间倒序的分页结果中取出 `since` 之后创建的市场，`fetch_page` 按 offset 获取一页
+///
+/// 只有确认了顺序才能在遇到早于 `since` 的市场时提前停止：第一页至少要有两个市场，
+/// 且创建时间不增、第一个严格晚于最后一个；之后所有市场也必须保持不增。
+/// 无法确认（API 忽略了排序参数、按正序返回、市场缺少创建时间）时返回 None，
+/// 否则按正序返回的 API 在第一个市场就会越过截止时间，新市场会被漏掉
+pub(crate) async fn collect_recent_markets<F, Fut>(
+    since: DateTime<Utc>,
+    page_size: u32,
+    mut fetch_page: F,
+) -> Result<Option<Vec<Market>>>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<MarketPage>>,
+{
+    let mut recent = Vec::new();
+    let mut previous: Option<DateTime<Utc>> = None;
+    let mut offset = 0;
+    loop {
+        let page = fetch_page(offset).await?;
+        let received = page.received();
+        let mut created = Vec::with_capacity(page.markets.len());
+        for market in &page.markets {
+            let Some(created_at) = market.created_at_parsed() else {
+                debug!("市场 {} 缺少创建时间，无法按创建时间获取", market.condition_id);
+                return Ok(None);
+            };
+            created.push(created_at);
+        }
+        if offset == 0 {
+            match (created.first(), created.last()) {
+                (None, _) if received == 0 => return Ok(Some(recent)),
+                (Some(first), Some(last)) if created.len() >= 2 && first > last => {}
+                _ => {
+                    debug!("无法确认 API 按创建时间倒序返回市场");
+                    return Ok(None);
+                }
+            }
+        }
+
+        for (market, created_at) in page.markets.into_iter().zip(created) {
+            if previous.is_some_and(|previous| created_at > previous) {
+                debug!("API 返回的市场没有按创建时间倒序排列");
+                return Ok(None);
+            }
+            previous = Some(created_at);
+            if created_at < since {
+                return Ok(Some(recent));
+            }
+            recent.push(market);
+        }
+        if received < page_size as usize {
+            return Ok(Some(recent));
+        }
+        offset += page_size;
+    }
+}
+
+/// 已获取 `total` 个市场时下一页的请求数量，达到 `max_total` 时返回 None
+pub(crate) fn page_limit(options: &StreamOptions, total: usize) -> Option<u32> {
+    match options.max_total {
+        Some(max) if total >= max => None,
+        Some(max) => Some(options.batch_size.min((max - total) as u32)),
+        None => Some(options.batch_size),
+    }
+}
+
+/// 截断一批市场，使累计数量不超过 `max_total`
+pub(crate) fn truncate_to_total(markets: &mut Vec<Market>, options: &StreamOptions, total: usize) {
+    if let Some(max) = options.max_total {
+        markets.truncate(max.saturating_sub(total));
+    }
+}
+
+/// 按选项逐页获取所有市场并交给 `callback` 处理，返回获取到的市场数
+///
+/// 每两页之间等待 `inter_batch_delay`，设置 `max_total` 时累计达到该数量后停止。
+/// 见 `PolymarketClient::get_all_markets_stream_with`
+pub async fn stream_markets<S, F, Fut>(
+    source: &S,
+    options: &StreamOptions,
+    mut callback: F,
+) -> Result<usize>
+where
+    S: MarketSource + ?Sized,
+    F: FnMut(Vec<Market>) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let batch_size = options.batch_size;
+    info!(
+        "开始流式获取所有市场数据，批次大小: {}, 起始偏移: {}, 过滤条件: {:?}",
+        batch_size, options.start_offset, options.query
+    );
+    let mut offset = options.start_offset;
+    let mut total_count = 0;
+
+    while let Some(limit) = page_limit(options, total_count) {
+        let page =
+            get_markets_page_with_retry(source, &options.query, limit, offset, options.max_retries)
+                .await?;
+        // 按原始条目数判断是否为最后一页，跳过的市场不能让分页提前结束
+        let received = page.received();
+
+        info!(
+            "获取到第 {} - {} 个市场",
+            offset + 1,
+            offset + received as u32
+        );
+        let mut markets = page.markets;
+        truncate_to_total(&mut markets, options, total_count);
+        total_count += markets.len();
+
+        // 调用回调函数处理当前批次，处理完后这批数据就可以被释放
+        callback(markets).await?;
+
+        if received < limit as usize {
+            break; // 最后一页
+        }
+
+        offset += batch_size;
+        if !options.inter_batch_delay.is_zero() && page_limit(options, total_count).is_some() {
+            tokio::time::sleep(options.inter_batch_delay).await;
+        }
+    }
+
+    info!("总共获取 {} 个市场", total_count);
+    Ok(total_count)
+}
+
+/// 返回预设数据的 `MarketSource`，用于测试
+///
+/// `set_markets` 设置"API 当前返回的市场"，分页请求按 `GammaQuery` 过滤后
+/// 按 offset/limit 切片（`active` 缺失视为 true，`closed` 缺失视为 false）。
+/// 每次调用 `set_markets` 相当于 API 数据发生了变化，可以在两轮扫描之间调用
+#[derive(Default)]
+pub struct MockMarketSource {
+    markets: Mutex<Vec<Market>>,
+    order_books: Mutex<HashMap<String, OrderBook>>,
+    /// 接下来的请求依次返回这些错误
+    failures: Mutex<VecDeque<String>>,
+    requests: Mutex<usize>,
+}
+
+impl MockMarketSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 以给定的市场创建
+    pub fn with_markets(markets: Vec<Market>) -> Self {
+        let source = Self::default();
+        source.set_markets(markets);
+        source
+    }
+
+    /// 替换 API 返回的市场
+    pub fn set_markets(&self, markets: Vec<Market>) {
+        *self.markets.lock() = markets;
+    }
+
+    /// 设置 token 的订单簿
+    pub fn set_order_book(&self, token_id: impl Into<String>, book: OrderBook) {
+        self.order_books.lock().insert(token_id.into(), book);
+    }
+
+    /// 下一次请求返回 `NetworkError(message)`，多次调用时按顺序生效
+    pub fn fail_next(&self, message: impl Into<String>) {
+        self.failures.lock().push_back(message.into());
+    }
+
+    /// 已收到的请求数（包括返回错误的请求）
+    pub fn request_count(&self) -> usize {
+        *self.requests.lock()
+    }
+
+    /// 计数并取出预设的错误
+    fn begin_request(&self) -> Result<()> {
+        *self.requests.lock() += 1;
+        match self.failures.lock().pop_front() {
+            Some(message) => Err(ScannerError::NetworkError(message)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl MarketSource for MockMarketSource {
+    async fn get_markets_page(
+        &self,
+        query: &GammaQuery,
+        limit: u32,
+        offset: u32,
+    ) -> Result<MarketPage> {
+        self.begin_request()?;
+        let markets = self
+            .markets
+            .lock()
+            .iter()
+            .filter(|m| query.active.is_none_or(|a| m.active.unwrap_or(true) == a))
+            .filter(|m| query.closed.is_none_or(|c| m.closed.unwrap_or(false) == c))
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect();
+        Ok(MarketPage {
+            markets,
+            skipped: 0,
+        })
+    }
+
+    async fn get_markets_by_ids(&self, ids: &[String]) -> Result<Vec<Market>> {
+        self.begin_request()?;
+        Ok(self
+            .markets
+            .lock()
+            .iter()
+            .filter(|m| ids.contains(&m.condition_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_order_book(&self, token_id: &str) -> Result<OrderBook> {
+        self.begin_request()?;
+        self.order_books
+            .lock()
+            .get(token_id)
+            .cloned()
+            .ok_or_else(|| ScannerError::NotFound(format!("order book {}", token_id)))
+    }
+
+    /// 按 `set_markets` 的顺序分页返回活跃市场，模拟 API 对排序参数的处理：
+    /// 按创建时间倒序设置时相当于支持排序，按其他顺序设置时相当于忽略了排序参数
+    async fn get_recent_markets(&self, since: DateTime<Utc>) -> Result<Option<Vec<Market>>> {
+        let query = GammaQuery::active();
+        collect_recent_markets(since, RECENT_MARKETS_PAGE_SIZE, |offset| {
+            self.get_markets_page(&query, RECENT_MARKETS_PAGE_SIZE, offset)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn market(id: u32, created_at: DateTime<Utc>) -> Market {
+        Market {
+            condition_id: format!("0x{:064x}", id),
+            question: format!("Market {}", id),
+            outcomes: r#"["Yes","No"]"#.to_string(),
+            created_at: Some(created_at.to_rfc3339()),
+            ..Default::default()
+        }
+    }
+
+    fn questions(markets: &[Market]) -> Vec<String> {
+        markets.iter().map(|m| m.question.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn recent_markets_stop_at_cutoff_when_descending() {
+        let now = Utc::now();
+        let source = MockMarketSource::with_markets(vec![
+            market(3, now),
+            market(2, now - Duration::hours(1)),
+            market(1, now - Duration::days(3)),
+        ]);
+
+        let recent = source
+            .get_recent_markets(now - Duration::days(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(questions(&recent), ["Market 3", "Market 2"]);
+        assert_eq!(source.request_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn recent_markets_unsupported_when_ascending() {
+        // API 忽略了排序参数、按正序返回时，第一个市场就早于截止时间
+        let now = Utc::now();
+        let source = MockMarketSource::with_markets(vec![
+            market(1, now - Duration::days(3)),
+            market(2, now - Duration::hours(1)),
+            market(3, now),
+        ]);
+
+        let recent = source.get_recent_markets(now - Duration::days(1)).await.unwrap();
+        assert!(recent.is_none());
+    }
+
+    #[tokio::test]
+    async fn recent_markets_unsupported_without_created_at() {
+        let now = Utc::now();
+        let mut undated = market(1, now);
+        undated.created_at = None;
+        let source = MockMarketSource::with_markets(vec![market(2, now), undated]);
+
+        assert!(source.get_recent_markets(now).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn recent_markets_unconfirmed_with_single_market() {
+        let now = Utc::now();
+        let source = MockMarketSource::with_markets(vec![market(1, now)]);
+        assert!(source.get_recent_markets(now).await.unwrap().is_none());
+
+        let empty = MockMarketSource::new();
+        assert_eq!(empty.get_recent_markets(now).await.unwrap().unwrap().len(), 0);
+    }
+}
@@ -1,30 +1,184 @@
-use crate::error::Result;
-use crate::types::Market;
+use crate::error::{Result, ScannerError};
+use crate::types::{parse_prices, Market, MarketEvent, ParsedMarket, PriceAlert};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Write};
+use std::time::Duration;
+use tracing::warn;
+
+/// One price history entry of a `MarketTimeline`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelinePoint {
+    pub timestamp: DateTime<Utc>,
+    pub outcome_prices: String,
+    pub volume: String,
+}
+
+/// Everything stored about one market, see `Storage::export_market_timeline`
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketTimeline {
+    pub market: Market,
+    /// Most recent events, oldest first
+    pub events: Vec<MarketEvent>,
+    /// Most recent price history, oldest first
+    pub price_history: Vec<TimelinePoint>,
+    pub exported_at: DateTime<Utc>,
+}
+
+/// Events read after a cursor, returned by `Storage::get_events_after`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EventBatch {
+    /// Events newer than the cursor, oldest first
+    pub events: Vec<MarketEvent>,
+    /// Cursor to pass to the next call; the given cursor is returned
+    /// unchanged when there are no newer events
+    pub cursor: Option<String>,
+}
+
+/// Aggregate figures about a store, returned by `Storage::get_stats`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DatabaseStats {
+    pub market_count: i64,
+    /// Events currently stored; backends that cap their event log only count
+    /// the retained events
+    pub event_count: i64,
+    /// Stored events keyed by `EventType::name()`
+    pub events_by_type: BTreeMap<String, i64>,
+    /// Earliest "last saved" time across all markets
+    pub oldest_market_update: Option<DateTime<Utc>>,
+    /// Latest "last saved" time across all markets
+    pub newest_market_update: Option<DateTime<Utc>>,
+    /// Price history entries across all markets
+    pub price_history_points: i64,
+}
 
 #[async_trait]
 pub trait Storage: Send + Sync {
     /// Initialize the storage (e.g. create tables, ensure directories exist)
     async fn init(&self) -> Result<()>;
 
+    /// Verify the backend is reachable and writable
+    async fn health_check(&self) -> Result<()>;
+
     /// Save or update a market
     async fn save_market(&self, market: &Market) -> Result<()>;
 
     /// Save or update multiple markets
     async fn save_markets(&self, markets: Vec<Market>) -> Result<()>;
 
-    /// Save price history for a market
+    /// Append a market event to the event log
+    async fn save_event(&self, event: &MarketEvent) -> Result<()>;
+
+    /// Save several events at once; backends override this to batch the
+    /// writes, the default saves them one by one
+    async fn save_events(&self, events: Vec<MarketEvent>) -> Result<()> {
+        for event in &events {
+            self.save_event(event).await?;
+        }
+        Ok(())
+    }
+
+    /// Get the most recent events, newest first
+    async fn get_recent_events(&self, limit: i32) -> Result<Vec<MarketEvent>>;
+
+    /// Get up to `limit` events stored after `cursor`, oldest first, for
+    /// tailing the event store without re-reading events already seen
+    ///
+    /// `None` starts at the oldest stored event. Cursors are opaque and
+    /// backend specific (row id for SQLite, stream id for Redis), so only
+    /// pass back a cursor returned by the same backend.
+    async fn get_events_after(&self, cursor: Option<&str>, limit: i32) -> Result<EventBatch>;
+
+    /// Get the most recent events of one market, newest first
+    ///
+    /// `event_type` optionally restricts the result to one type, matched
+    /// against `EventType::name()` (e.g. `"PriceChange"`).
+    async fn get_recent_events_for(
+        &self,
+        condition_id: &str,
+        event_type: Option<&str>,
+        limit: i32,
+    ) -> Result<Vec<MarketEvent>>;
+
+    /// Save price history for a market, timestamped now
     async fn save_price_history(
         &self,
         condition_id: &str,
         outcome_prices: Option<&str>,
         volume: Option<&str>,
+    ) -> Result<()> {
+        self.save_price_history_at(condition_id, outcome_prices, volume, Utc::now())
+            .await
+    }
+
+    /// Save a price history entry with the given timestamp, used when
+    /// restoring history recorded elsewhere (see `restore`)
+    async fn save_price_history_at(
+        &self,
+        condition_id: &str,
+        outcome_prices: Option<&str>,
+        volume: Option<&str>,
+        timestamp: DateTime<Utc>,
     ) -> Result<()>;
 
     /// Get total number of tracked markets
     async fn get_market_count(&self) -> Result<i64>;
 
+    /// Get market, event and price history counts in one call
+    async fn get_stats(&self) -> Result<DatabaseStats>;
+
+    /// Count stored markets by status
+    ///
+    /// Always returns the keys `"total"`, `"active"`, `"inactive"`,
+    /// `"closed"` and `"resolved"`. A closed market counts as `"closed"`
+    /// whatever its `active` flag; one that is not closed but has
+    /// `active = false` counts as `"inactive"`; every other market is
+    /// `"active"`, so those three add up to `"total"`. `"resolved"` counts
+    /// markets with a `resolved_outcome` and overlaps `"closed"`.
+    async fn get_market_status_counts(&self) -> Result<HashMap<String, i64>>;
+
+    /// Collect a market with its latest `event_limit` events and
+    /// `history_limit` price history points, both in chronological order;
+    /// fails with `NotFound` if the market is not stored
+    async fn export_market_timeline(
+        &self,
+        condition_id: &str,
+        history_limit: i32,
+        event_limit: i32,
+    ) -> Result<MarketTimeline> {
+        let market = self
+            .get_market(condition_id)
+            .await?
+            .ok_or_else(|| ScannerError::NotFound(format!("market {}", condition_id)))?;
+
+        let mut events = self
+            .get_recent_events_for(condition_id, None, event_limit)
+            .await?;
+        events.sort_by_key(|e| e.timestamp);
+
+        // backends differ in the order they return history, so sort here
+        let mut price_history: Vec<TimelinePoint> = self
+            .get_price_history(condition_id, history_limit)
+            .await?
+            .into_iter()
+            .map(|(outcome_prices, volume, timestamp)| TimelinePoint {
+                timestamp,
+                outcome_prices,
+                volume,
+            })
+            .collect();
+        price_history.sort_by_key(|p| p.timestamp);
+
+        Ok(MarketTimeline {
+            market,
+            events,
+            price_history,
+            exported_at: Utc::now(),
+        })
+    }
+
     /// Get price history for a market
     async fn get_price_history(
         &self,
@@ -32,9 +186,576 @@ pub trait Storage: Send + Sync {
         limit: i32,
     ) -> Result<Vec<(String, String, DateTime<Utc>)>>;
 
+    /// Standard deviation of the first-outcome price over the history stored
+    /// within `window`; `None` if there are fewer than two points
+    async fn get_price_volatility(
+        &self,
+        condition_id: &str,
+        window: Duration,
+    ) -> Result<Option<f64>>;
+
     /// Get a specific market
     async fn get_market(&self, condition_id: &str) -> Result<Option<Market>>;
 
+    /// Get a market with both its raw API strings and their parsed numeric
+    /// forms
+    ///
+    /// Backends that store the parsed forms (SQLite, Redis) return what was
+    /// derived at save time; the default parses the raw strings on read.
+    async fn get_market_parsed(&self, condition_id: &str) -> Result<Option<ParsedMarket>> {
+        Ok(self.get_market(condition_id).await?.map(ParsedMarket::from))
+    }
+
+    /// Get markets saved or updated strictly after `since`
+    async fn get_markets_updated_since(&self, since: DateTime<Utc>) -> Result<Vec<Market>>;
+
+    /// Get resolved markets (`resolved_outcome` set) saved or updated
+    /// strictly after `since`, for collecting resolutions incrementally
+    ///
+    /// The default filters `get_markets_updated_since`; backends that can
+    /// filter natively override it.
+    async fn get_resolved_markets_since(&self, since: DateTime<Utc>) -> Result<Vec<Market>> {
+        Ok(self
+            .get_markets_updated_since(since)
+            .await?
+            .into_iter()
+            .filter(|m| m.resolved_outcome.is_some())
+            .collect())
+    }
+
+    /// Get markets whose last save is at least `older_than` ago, oldest first
+    ///
+    /// The scanner saves a market only when it produces an event, so these
+    /// are markets that stopped changing or that the scanner stopped
+    /// receiving; closed markets are included.
+    async fn get_stale_markets(&self, older_than: Duration) -> Result<Vec<Market>>;
+
     /// Get all market IDs
     async fn get_all_market_ids(&self) -> Result<Vec<String>>;
+
+    /// Get all markets carrying the given tag (compared case-insensitively)
+    async fn get_markets_by_tag(&self, tag: &str) -> Result<Vec<Market>>;
+
+    /// Get the markets with the highest volume (missing volume counts as zero)
+    async fn get_top_markets_by_volume(&self, limit: i32) -> Result<Vec<Market>>;
+
+    /// Save the offset a named full scan should resume from
+    async fn save_scan_checkpoint(&self, name: &str, offset: u32) -> Result<()>;
+
+    /// Get the saved offset of a named full scan, if any
+    async fn get_scan_checkpoint(&self, name: &str) -> Result<Option<u32>>;
+
+    /// Clear the checkpoint of a named full scan (called after it completes)
+    async fn clear_scan_checkpoint(&self, name: &str) -> Result<()>;
+
+    /// Save a price alert, replacing a stored alert with the same `id()`
+    async fn save_price_alert(&self, alert: &PriceAlert) -> Result<()>;
+
+    /// Get all saved price alerts
+    async fn get_price_alerts(&self) -> Result<Vec<PriceAlert>>;
+
+    /// Delete the price alert with the given `id()`; unknown ids are ignored
+    async fn delete_price_alert(&self, id: &str) -> Result<()>;
+
+    /// Add a market to the watchlist; adding a watched market again is a no-op
+    async fn add_to_watchlist(&self, condition_id: &str) -> Result<()>;
+
+    /// Remove a market from the watchlist; unknown ids are ignored
+    async fn remove_from_watchlist(&self, condition_id: &str) -> Result<()>;
+
+    /// Get the condition ids of all watched markets, sorted
+    async fn get_watchlist(&self) -> Result<Vec<String>>;
+}
+
+/// Number of events read per `get_events_after` call while replaying
+const REPLAY_PAGE_SIZE: i32 = 500;
+
+/// Feed every stored event to `handler`, oldest first, and return how many
+/// events were replayed
+///
+/// Events are read in pages through `Storage::get_events_after`, so only one
+/// page is held in memory at a time, and come out in the order they were
+/// stored, which is chronological (row id for SQLite, stream id for Redis).
+/// With `from` set, events older than it are skipped. Only events the
+/// backend still retains can be replayed.
+pub async fn replay_events<S, F>(
+    storage: &S,
+    from: Option<DateTime<Utc>>,
+    mut handler: F,
+) -> Result<u64>
+where
+    S: Storage + ?Sized,
+    F: FnMut(MarketEvent),
+{
+    let mut cursor: Option<String> = None;
+    let mut replayed = 0;
+    loop {
+        let batch = storage
+            .get_events_after(cursor.as_deref(), REPLAY_PAGE_SIZE)
+            .await?;
+        if batch.events.is_empty() {
+            return Ok(replayed);
+        }
+        for event in batch.events {
+            if from.is_some_and(|from| event.timestamp < from) {
+                continue;
+            }
+            handler(event);
+            replayed += 1;
+        }
+        cursor = batch.cursor;
+    }
+}
+
+/// Number of markets passed to each `save_markets` call while importing
+const IMPORT_BATCH_SIZE: usize = 500;
+
+/// Result of `import_markets_ndjson`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ImportReport {
+    /// Markets saved to the storage
+    pub imported: usize,
+    /// Non-empty lines that were not a valid `Market`
+    pub skipped: usize,
+}
+
+/// Load markets from NDJSON (one `Market` JSON object per line) into
+/// `storage` and report how many were imported and skipped
+///
+/// Lines that fail to parse or fail `Market::validate` are logged with their
+/// line number and skipped; blank lines are ignored. Markets are saved in
+/// batches of `IMPORT_BATCH_SIZE` through `save_markets`, so a dump can be
+/// larger than memory. A read error or a failed save aborts the import;
+/// batches saved before it are kept.
+pub async fn import_markets_ndjson<S, R>(storage: &S, reader: R) -> Result<ImportReport>
+where
+    S: Storage + ?Sized,
+    R: BufRead,
+{
+    let mut report = ImportReport::default();
+    let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| {
+            ScannerError::StorageError(format!("Failed to read line {}: {}", index + 1, e))
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let market = serde_json::from_str::<Market>(&line)
+            .map_err(ScannerError::from)
+            .and_then(|market| market.validate().map(|_| market));
+        match market {
+            Ok(market) => batch.push(market),
+            Err(e) => {
+                warn!("Skipping line {}: {}", index + 1, e);
+                report.skipped += 1;
+                continue;
+            }
+        }
+        if batch.len() >= IMPORT_BATCH_SIZE {
+            let count = batch.len();
+            storage.save_markets(std::mem::take(&mut batch)).await?;
+            report.imported += count;
+        }
+    }
+    if !batch.is_empty() {
+        report.imported += batch.len();
+        storage.save_markets(batch).await?;
+    }
+    Ok(report)
+}
+
+/// Value of the `format` field in the first line of a snapshot
+const SNAPSHOT_FORMAT: &str = "polymarket_scanner_snapshot";
+
+/// Snapshot format version written by `snapshot`; `restore` accepts this
+/// version and older ones
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// First line of a snapshot
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotHeader {
+    format: String,
+    version: u32,
+    created_at: DateTime<Utc>,
+}
+
+/// One line of a snapshot after the header
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+enum SnapshotRecord {
+    Market(Market),
+    PriceHistory {
+        condition_id: String,
+        #[serde(flatten)]
+        point: TimelinePoint,
+    },
+    Event(MarketEvent),
+    Watchlist(String),
+    PriceAlert(PriceAlert),
+}
+
+/// Records written by `snapshot` or read by `restore`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SnapshotReport {
+    pub markets: usize,
+    pub price_history_points: usize,
+    pub events: usize,
+    pub watchlist: usize,
+    pub price_alerts: usize,
+}
+
+fn write_snapshot_line<W: Write, T: Serialize>(writer: &mut W, value: &T) -> Result<()> {
+    serde_json::to_writer(&mut *writer, value)?;
+    writer
+        .write_all(b"\n")
+        .map_err(|e| ScannerError::StorageError(format!("Failed to write snapshot: {}", e)))
+}
+
+/// Write everything in `storage` (markets, price history, events, watchlist
+/// and price alerts) to `writer` as a snapshot that `restore` can load into
+/// any backend
+///
+/// A snapshot is NDJSON: a header line carrying the format name and
+/// `SNAPSHOT_VERSION`, then one `{"type": ..., "data": ...}` record per line,
+/// markets first. Markets are read one at a time and events in pages, so
+/// apart from the list of market ids only one market's price history is
+/// held in memory at once. Only what the backend still retains is written
+/// (e.g. capped event logs or history limits). The writer is flushed at the
+/// end; wrap files in a `BufWriter`.
+pub async fn snapshot<S, W>(storage: &S, mut writer: W) -> Result<SnapshotReport>
+where
+    S: Storage + ?Sized,
+    W: Write,
+{
+    let mut report = SnapshotReport::default();
+    write_snapshot_line(
+        &mut writer,
+        &SnapshotHeader {
+            format: SNAPSHOT_FORMAT.to_string(),
+            version: SNAPSHOT_VERSION,
+            created_at: Utc::now(),
+        },
+    )?;
+
+    let ids = storage.get_all_market_ids().await?;
+    for id in &ids {
+        // the market may have been removed since the ids were listed
+        if let Some(market) = storage.get_market(id).await? {
+            write_snapshot_line(&mut writer, &SnapshotRecord::Market(market))?;
+            report.markets += 1;
+        }
+    }
+
+    for id in ids {
+        // backends differ in the order they return history, so sort here
+        let mut history = storage.get_price_history(&id, i32::MAX).await?;
+        history.sort_by_key(|(_, _, timestamp)| *timestamp);
+        for (outcome_prices, volume, timestamp) in history {
+            let record = SnapshotRecord::PriceHistory {
+                condition_id: id.clone(),
+                point: TimelinePoint {
+                    timestamp,
+                    outcome_prices,
+                    volume,
+                },
+            };
+            write_snapshot_line(&mut writer, &record)?;
+            report.price_history_points += 1;
+        }
+    }
+
+    let mut cursor: Option<String> = None;
+    loop {
+        let batch = storage
+            .get_events_after(cursor.as_deref(), REPLAY_PAGE_SIZE)
+            .await?;
+        if batch.events.is_empty() {
+            break;
+        }
+        for event in batch.events {
+            write_snapshot_line(&mut writer, &SnapshotRecord::Event(event))?;
+            report.events += 1;
+        }
+        cursor = batch.cursor;
+    }
+
+    for id in storage.get_watchlist().await? {
+        write_snapshot_line(&mut writer, &SnapshotRecord::Watchlist(id))?;
+        report.watchlist += 1;
+    }
+    for alert in storage.get_price_alerts().await? {
+        write_snapshot_line(&mut writer, &SnapshotRecord::PriceAlert(alert))?;
+        report.price_alerts += 1;
+    }
+
+    writer
+        .flush()
+        .map_err(|e| ScannerError::StorageError(format!("Failed to write snapshot: {}", e)))?;
+    Ok(report)
+}
+
+/// Load a snapshot written by `snapshot` into `storage`
+///
+/// The snapshot is read line by line; markets and events are saved in
+/// batches of `IMPORT_BATCH_SIZE`, and pending markets are always saved
+/// before any record that refers to them. Price history keeps its original
+/// timestamps (`Storage::save_price_history_at`); the markets' own "last
+/// saved" times become the time of the restore. Existing data is not
+/// cleared: markets are overwritten and everything else is added, so
+/// restore into an empty store to get an exact copy.
+///
+/// Unlike `import_markets_ndjson`, any malformed line aborts the restore,
+/// as does a header with an unknown format or a newer version. Records
+/// saved before the error are kept.
+pub async fn restore<S, R>(storage: &S, reader: R) -> Result<SnapshotReport>
+where
+    S: Storage + ?Sized,
+    R: BufRead,
+{
+    let invalid = |line: usize, reason: String| {
+        ScannerError::StorageError(format!("Invalid snapshot at line {}: {}", line, reason))
+    };
+
+    let mut lines = reader.lines().enumerate();
+    let header = match lines.next() {
+        Some((_, line)) => line
+            .map_err(|e| invalid(1, e.to_string()))
+            .and_then(|line| {
+                serde_json::from_str::<SnapshotHeader>(&line).map_err(|e| invalid(1, e.to_string()))
+            })?,
+        None => return Err(invalid(1, "empty input".to_string())),
+    };
+    if header.format != SNAPSHOT_FORMAT {
+        return Err(invalid(1, format!("unknown format \"{}\"", header.format)));
+    }
+    if header.version > SNAPSHOT_VERSION {
+        return Err(invalid(
+            1,
+            format!(
+                "version {} is newer than the supported version {}",
+                header.version, SNAPSHOT_VERSION
+            ),
+        ));
+    }
+
+    let mut report = SnapshotReport::default();
+    let mut markets = Vec::with_capacity(IMPORT_BATCH_SIZE);
+    let mut events = Vec::with_capacity(IMPORT_BATCH_SIZE);
+    for (index, line) in lines {
+        let line = line.map_err(|e| invalid(index + 1, e.to_string()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str::<SnapshotRecord>(&line)
+            .map_err(|e| invalid(index + 1, e.to_string()))?;
+
+        if !matches!(record, SnapshotRecord::Market(_)) && !markets.is_empty() {
+            report.markets += markets.len();
+            storage.save_markets(std::mem::take(&mut markets)).await?;
+        }
+        match record {
+            SnapshotRecord::Market(market) => {
+                markets.push(market);
+                if markets.len() >= IMPORT_BATCH_SIZE {
+                    report.markets += markets.len();
+                    storage.save_markets(std::mem::take(&mut markets)).await?;
+                }
+            }
+            SnapshotRecord::PriceHistory { condition_id, point } => {
+                storage
+                    .save_price_history_at(
+                        &condition_id,
+                        Some(&point.outcome_prices),
+                        Some(&point.volume),
+                        point.timestamp,
+                    )
+                    .await?;
+                report.price_history_points += 1;
+            }
+            SnapshotRecord::Event(event) => {
+                events.push(event);
+                if events.len() >= IMPORT_BATCH_SIZE {
+                    report.events += events.len();
+                    storage.save_events(std::mem::take(&mut events)).await?;
+                }
+            }
+            SnapshotRecord::Watchlist(condition_id) => {
+                storage.add_to_watchlist(&condition_id).await?;
+                report.watchlist += 1;
+            }
+            SnapshotRecord::PriceAlert(alert) => {
+                storage.save_price_alert(&alert).await?;
+                report.price_alerts += 1;
+            }
+        }
+    }
+    if !markets.is_empty() {
+        report.markets += markets.len();
+        storage.save_markets(markets).await?;
+    }
+    if !events.is_empty() {
+        report.events += events.len();
+        storage.save_events(events).await?;
+    }
+    Ok(report)
+}
+
+/// Whether an event belongs to the given market and, if set, has the given type
+pub(crate) fn event_matches(
+    event: &MarketEvent,
+    condition_id: &str,
+    event_type: Option<&str>,
+) -> bool {
+    event.market.condition_id == condition_id
+        && event_type.is_none_or(|t| event.event_type.name() == t)
+}
+
+/// Build the stats of a backend that keeps everything in maps, from the
+/// retained events, the per-market "last saved" times and the history sizes
+pub(crate) fn collect_stats<'a>(
+    events: impl IntoIterator<Item = &'a MarketEvent>,
+    updated_at: impl IntoIterator<Item = &'a DateTime<Utc>>,
+    market_count: usize,
+    price_history_points: usize,
+) -> DatabaseStats {
+    let mut stats = DatabaseStats {
+        market_count: market_count as i64,
+        price_history_points: price_history_points as i64,
+        ..Default::default()
+    };
+    for event in events {
+        stats.event_count += 1;
+        *stats
+            .events_by_type
+            .entry(event.event_type.name().to_string())
+            .or_default() += 1;
+    }
+    for ts in updated_at {
+        stats.oldest_market_update = Some(stats.oldest_market_update.map_or(*ts, |t| t.min(*ts)));
+        stats.newest_market_update = Some(stats.newest_market_update.map_or(*ts, |t| t.max(*ts)));
+    }
+    stats
+}
+
+/// Status bucket of a market, see `Storage::get_market_status_counts`
+pub(crate) fn market_status(market: &Market) -> &'static str {
+    if market.closed == Some(true) {
+        "closed"
+    } else if market.active == Some(false) {
+        "inactive"
+    } else {
+        "active"
+    }
+}
+
+/// Build the status counts of a backend that can iterate its markets
+pub(crate) fn status_counts<'a>(
+    markets: impl IntoIterator<Item = &'a Market>,
+) -> HashMap<String, i64> {
+    let mut counts: HashMap<String, i64> = ["total", "active", "inactive", "closed", "resolved"]
+        .into_iter()
+        .map(|key| (key.to_string(), 0))
+        .collect();
+    for market in markets {
+        for key in ["total", market_status(market)] {
+            *counts.entry(key.to_string()).or_default() += 1;
+        }
+        if market.resolved_outcome.is_some() {
+            *counts.entry("resolved".to_string()).or_default() += 1;
+        }
+    }
+    counts
+}
+
+/// Sort markets by volume descending and keep the first `limit`,
+/// for backends that cannot order by volume natively
+pub(crate) fn top_by_volume(mut markets: Vec<Market>, limit: i32) -> Vec<Market> {
+    markets.sort_by(|a, b| {
+        let a = a.volume_value().unwrap_or(0.0);
+        let b = b.volume_value().unwrap_or(0.0);
+        b.total_cmp(&a)
+    });
+    markets.truncate(limit.max(0) as usize);
+    markets
+}
+
+/// Start of a look-back window ending now
+pub(crate) fn window_start(window: Duration) -> DateTime<Utc> {
+    let window = chrono::Duration::from_std(window).unwrap_or(chrono::Duration::MAX);
+    Utc::now()
+        .checked_sub_signed(window)
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+/// Population standard deviation of the first-outcome price across the given
+/// `outcome_prices` strings; unparsable entries are skipped
+pub(crate) fn price_volatility<'a>(
+    outcome_prices: impl IntoIterator<Item = &'a str>,
+) -> Option<f64> {
+    let prices: Vec<f64> = outcome_prices
+        .into_iter()
+        .filter_map(|raw| parse_prices(raw)?.first().copied())
+        .collect();
+    if prices.len() < 2 {
+        return None;
+    }
+
+    let n = prices.len() as f64;
+    let mean = prices.iter().sum::<f64>() / n;
+    let variance = prices.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / n;
+    Some(variance.sqrt())
+}
+
+/// Parse a cursor that is a sequence number or row id
+pub(crate) fn parse_sequence_cursor(cursor: Option<&str>) -> Result<u64> {
+    cursor.map_or(Ok(0), |c| {
+        c.parse()
+            .map_err(|_| ScannerError::StorageError(format!("Invalid event cursor: {}", c)))
+    })
+}
+
+/// `Storage::get_events_after` for backends keeping events in a `Vec`
+///
+/// Events are numbered from 1 in insertion order and the cursor is the
+/// number of the last event returned. `dropped` is how many of the oldest
+/// events have been removed from the front of `events`; a consumer that fell
+/// further behind than that continues at the oldest event still kept.
+pub(crate) fn events_after(
+    events: &[MarketEvent],
+    dropped: u64,
+    cursor: Option<&str>,
+    limit: i32,
+) -> Result<EventBatch> {
+    let after = parse_sequence_cursor(cursor)?;
+    let start = (after.saturating_sub(dropped) as usize).min(events.len());
+    let end = start
+        .saturating_add(limit.max(0) as usize)
+        .min(events.len());
+    if start == end {
+        return Ok(EventBatch {
+            events: Vec::new(),
+            cursor: cursor.map(str::to_string),
+        });
+    }
+    Ok(EventBatch {
+        events: events[start..end].to_vec(),
+        cursor: Some((dropped + end as u64).to_string()),
+    })
+}
+
+/// `n` price change events for distinct markets, oldest first
+#[cfg(test)]
+pub(crate) fn sample_events(n: usize) -> Vec<MarketEvent> {
+    (0..n)
+        .map(|i| {
+            let market = Market {
+                condition_id: format!("0x{:04x}", i),
+                question: format!("Market {}", i),
+                outcomes: r#"["Yes","No"]"#.to_string(),
+                ..Default::default()
+            };
+            MarketEvent::new(market, crate::types::EventType::PriceChange)
+        })
+        .collect()
 }
@@ -0,0 +1,245 @@
+use crate::error::{Result, ScannerError};
+use crate::storage::{DatabaseStats, EventBatch, MarketTimeline, Storage};
+use crate::types::{Market, MarketEvent, ParsedMarket, PriceAlert};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::{join_all, BoxFuture};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+/// Storage that writes to several backends at once
+///
+/// The first backend is the primary: every read is served by it and its
+/// write errors are returned to the caller. Writes are sent to all backends
+/// concurrently; a failed write on a secondary is logged and otherwise
+/// ignored, so e.g. a Redis dashboard going down does not stop the scanner
+/// from persisting to SQLite. `init` and `health_check` must succeed on every
+/// backend, so a misconfigured secondary is still reported at startup.
+pub struct TeeStorage {
+    backends: Vec<Arc<dyn Storage + Send + Sync>>,
+}
+
+impl TeeStorage {
+    /// Fan out to `backends`, reading from the first one; fails with
+    /// `ConfigError` if `backends` is empty
+    pub fn new(backends: Vec<Arc<dyn Storage + Send + Sync>>) -> Result<Self> {
+        if backends.is_empty() {
+            return Err(ScannerError::ConfigError(
+                "TeeStorage needs at least one backend".to_string(),
+            ));
+        }
+        Ok(Self { backends })
+    }
+
+    fn primary(&self) -> &(dyn Storage + Send + Sync) {
+        self.backends[0].as_ref()
+    }
+
+    /// Run `write` against every backend concurrently and return the
+    /// primary's result, logging secondary failures
+    async fn fan_out<'a, F>(&'a self, op: &str, write: F) -> Result<()>
+    where
+        F: Fn(&'a (dyn Storage + Send + Sync)) -> BoxFuture<'a, Result<()>>,
+    {
+        let mut results = join_all(self.backends.iter().map(|b| write(b.as_ref()))).await;
+        for (index, result) in results.drain(1..).enumerate() {
+            if let Err(e) = result {
+                warn!("Secondary storage #{} failed to {}: {}", index + 1, op, e);
+            }
+        }
+        results.pop().unwrap_or(Ok(()))
+    }
+
+    /// Run `op` against every backend and fail on the first error
+    async fn all<'a, F>(&'a self, op: F) -> Result<()>
+    where
+        F: Fn(&'a (dyn Storage + Send + Sync)) -> BoxFuture<'a, Result<()>>,
+    {
+        for backend in &self.backends {
+            op(backend.as_ref()).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for TeeStorage {
+    async fn init(&self) -> Result<()> {
+        self.all(|s| s.init()).await
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.all(|s| s.health_check()).await
+    }
+
+    async fn save_market(&self, market: &Market) -> Result<()> {
+        self.fan_out("save market", |s| s.save_market(market)).await
+    }
+
+    async fn save_markets(&self, markets: Vec<Market>) -> Result<()> {
+        self.fan_out("save markets", |s| s.save_markets(markets.clone()))
+            .await
+    }
+
+    async fn save_event(&self, event: &MarketEvent) -> Result<()> {
+        self.fan_out("save event", |s| s.save_event(event)).await
+    }
+
+    async fn save_events(&self, events: Vec<MarketEvent>) -> Result<()> {
+        self.fan_out("save events", |s| s.save_events(events.clone()))
+            .await
+    }
+
+    async fn get_recent_events(&self, limit: i32) -> Result<Vec<MarketEvent>> {
+        self.primary().get_recent_events(limit).await
+    }
+
+    async fn get_events_after(&self, cursor: Option<&str>, limit: i32) -> Result<EventBatch> {
+        self.primary().get_events_after(cursor, limit).await
+    }
+
+    async fn get_recent_events_for(
+        &self,
+        condition_id: &str,
+        event_type: Option<&str>,
+        limit: i32,
+    ) -> Result<Vec<MarketEvent>> {
+        self.primary()
+            .get_recent_events_for(condition_id, event_type, limit)
+            .await
+    }
+
+    async fn save_price_history_at(
+        &self,
+        condition_id: &str,
+        outcome_prices: Option<&str>,
+        volume: Option<&str>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<()> {
+        self.fan_out("save price history", |s| {
+            s.save_price_history_at(condition_id, outcome_prices, volume, timestamp)
+        })
+        .await
+    }
+
+    async fn get_market_count(&self) -> Result<i64> {
+        self.primary().get_market_count().await
+    }
+
+    async fn get_stats(&self) -> Result<DatabaseStats> {
+        self.primary().get_stats().await
+    }
+
+    async fn get_market_status_counts(&self) -> Result<HashMap<String, i64>> {
+        self.primary().get_market_status_counts().await
+    }
+
+    async fn export_market_timeline(
+        &self,
+        condition_id: &str,
+        history_limit: i32,
+        event_limit: i32,
+    ) -> Result<MarketTimeline> {
+        self.primary()
+            .export_market_timeline(condition_id, history_limit, event_limit)
+            .await
+    }
+
+    async fn get_price_history(
+        &self,
+        condition_id: &str,
+        limit: i32,
+    ) -> Result<Vec<(String, String, DateTime<Utc>)>> {
+        self.primary().get_price_history(condition_id, limit).await
+    }
+
+    async fn get_price_volatility(
+        &self,
+        condition_id: &str,
+        window: Duration,
+    ) -> Result<Option<f64>> {
+        self.primary()
+            .get_price_volatility(condition_id, window)
+            .await
+    }
+
+    async fn get_market(&self, condition_id: &str) -> Result<Option<Market>> {
+        self.primary().get_market(condition_id).await
+    }
+
+    async fn get_market_parsed(&self, condition_id: &str) -> Result<Option<ParsedMarket>> {
+        self.primary().get_market_parsed(condition_id).await
+    }
+
+    async fn get_markets_updated_since(&self, since: DateTime<Utc>) -> Result<Vec<Market>> {
+        self.primary().get_markets_updated_since(since).await
+    }
+
+    async fn get_stale_markets(&self, older_than: Duration) -> Result<Vec<Market>> {
+        self.primary().get_stale_markets(older_than).await
+    }
+
+    async fn get_resolved_markets_since(&self, since: DateTime<Utc>) -> Result<Vec<Market>> {
+        self.primary().get_resolved_markets_since(since).await
+    }
+
+    async fn get_all_market_ids(&self) -> Result<Vec<String>> {
+        self.primary().get_all_market_ids().await
+    }
+
+    async fn get_markets_by_tag(&self, tag: &str) -> Result<Vec<Market>> {
+        self.primary().get_markets_by_tag(tag).await
+    }
+
+    async fn get_top_markets_by_volume(&self, limit: i32) -> Result<Vec<Market>> {
+        self.primary().get_top_markets_by_volume(limit).await
+    }
+
+    async fn save_scan_checkpoint(&self, name: &str, offset: u32) -> Result<()> {
+        self.fan_out("save scan checkpoint", |s| {
+            s.save_scan_checkpoint(name, offset)
+        })
+        .await
+    }
+
+    async fn get_scan_checkpoint(&self, name: &str) -> Result<Option<u32>> {
+        self.primary().get_scan_checkpoint(name).await
+    }
+
+    async fn clear_scan_checkpoint(&self, name: &str) -> Result<()> {
+        self.fan_out("clear scan checkpoint", |s| s.clear_scan_checkpoint(name))
+            .await
+    }
+
+    async fn save_price_alert(&self, alert: &PriceAlert) -> Result<()> {
+        self.fan_out("save price alert", |s| s.save_price_alert(alert))
+            .await
+    }
+
+    async fn get_price_alerts(&self) -> Result<Vec<PriceAlert>> {
+        self.primary().get_price_alerts().await
+    }
+
+    async fn delete_price_alert(&self, id: &str) -> Result<()> {
+        self.fan_out("delete price alert", |s| s.delete_price_alert(id))
+            .await
+    }
+
+    async fn add_to_watchlist(&self, condition_id: &str) -> Result<()> {
+        self.fan_out("add to watchlist", |s| s.add_to_watchlist(condition_id))
+            .await
+    }
+
+    async fn remove_from_watchlist(&self, condition_id: &str) -> Result<()> {
+        self.fan_out("remove from watchlist", |s| {
+            s.remove_from_watchlist(condition_id)
+        })
+        .await
+    }
+
+    async fn get_watchlist(&self) -> Result<Vec<String>> {
+        self.primary().get_watchlist().await
+    }
+}
@@ -1,7 +1,8 @@
-use chrono::{DateTime, Utc};
+use crate::error::ScannerError;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Market {
     #[serde(rename = "conditionId")]
     pub condition_id: String,
@@ -31,21 +32,738 @@ pub struct Market {
     pub active: Option<bool>,
 
     pub closed: Option<bool>,
+
+    #[serde(rename = "clobTokenIds")]
+    pub clob_token_ids: Option<String>,
+
+    /// 分类标签（如 "Sports"、"Crypto"），API 返回的标签对象只保留 label
+    #[serde(
+        default,
+        deserialize_with = "deserialize_tags",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub tags: Vec<String>,
+
+    /// UMA 结算状态（如 "proposed"、"resolved"），未进入结算流程时为空
+    #[serde(
+        rename = "umaResolutionStatus",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub uma_resolution_status: Option<String>,
+
+    /// 已结算市场获胜结果的下标，见 `Market::winning_outcome`
+    #[serde(
+        rename = "resolvedOutcome",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub resolved_outcome: Option<usize>,
+
+    /// API 中市场的创建时间，用于 `PolymarketClient::get_recent_markets` 按创建时间增量获取新市场；
+    /// SQLite、Redis 和 MongoDB 存储不保存该字段
+    #[serde(rename = "createdAt", default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+/// 判断结算价格时允许的误差，结算后获胜结果的价格为 1，其余为 0
+const RESOLVED_PRICE_EPSILON: f64 = 1e-3;
+
+/// 兼容 Gamma API 的标签对象 `[{"label": "Sports", "slug": "sports"}]` 和字符串数组
+fn deserialize_tags<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let values: Option<Vec<serde_json::Value>> = Option::deserialize(deserializer)?;
+    Ok(values
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|v| match v {
+            serde_json::Value::String(s) => Some(s),
+            serde_json::Value::Object(o) => o
+                .get("label")
+                .or_else(|| o.get("slug"))
+                .and_then(|l| l.as_str())
+                .map(str::to_string),
+            _ => None,
+        })
+        .collect())
+}
+
+impl Market {
+    /// 检查市场数据是否满足存储和比较所需的基本约束
+    ///
+    /// - condition_id 和 question 不能为空
+    /// - outcomes 必须是非空的 JSON 数组
+    /// - 有价格时价格必须可解析，且数量与 outcomes 一致
+    pub fn validate(&self) -> crate::error::Result<()> {
+        let invalid = |reason: &str| {
+            Err(ScannerError::InvalidMarket(format!(
+                "{} ({})",
+                reason,
+                if self.condition_id.is_empty() {
+                    "<无 condition_id>"
+                } else {
+                    &self.condition_id
+                }
+            )))
+        };
+
+        if self.condition_id.trim().is_empty() {
+            return invalid("condition_id 为空");
+        }
+        if self.question.trim().is_empty() {
+            return invalid("question 为空");
+        }
+        let outcomes = match self.outcome_list() {
+            Some(outcomes) if !outcomes.is_empty() => outcomes,
+            _ => return invalid("outcomes 无法解析或为空"),
+        };
+        if let Some(raw) = self
+            .outcome_prices
+            .as_deref()
+            .filter(|p| !p.trim().is_empty())
+        {
+            match parse_prices(raw) {
+                None => return invalid("outcome_prices 无法解析"),
+                Some(prices) if prices.len() != outcomes.len() => {
+                    return invalid(&format!(
+                        "价格数量 {} 与结果数量 {} 不一致",
+                        prices.len(),
+                        outcomes.len()
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// 解析结果选项列表
+    pub fn outcome_list(&self) -> Option<Vec<String>> {
+        serde_json::from_str(&self.outcomes).ok()
+    }
+
+    /// 归一化的结果名称（去掉首尾空白、合并连续空白、转为小写），用于按名称匹配结果
+    ///
+    /// API 中同一结果可能写作 "Yes"、"YES" 或 "Yes "，归一化后都是 "yes"。
+    /// 结果列表无法解析时返回空列表
+    pub fn normalized_outcomes(&self) -> Vec<String> {
+        self.outcome_list()
+            .unwrap_or_default()
+            .iter()
+            .map(|o| normalize_outcome(o))
+            .collect()
+    }
+
+    /// 按名称查找结果下标，忽略大小写和多余空白
+    pub fn outcome_index(&self, name: &str) -> Option<usize> {
+        let name = normalize_outcome(name);
+        self.normalized_outcomes().iter().position(|o| *o == name)
+    }
+
+    /// 二元 Yes/No 市场中 "Yes" 的下标，其他市场返回 None
+    pub fn binary_yes_index(&self) -> Option<usize> {
+        match self.normalized_outcomes().as_slice() {
+            [a, b] if a == "yes" && b == "no" => Some(0),
+            [a, b] if a == "no" && b == "yes" => Some(1),
+            _ => None,
+        }
+    }
+
+    /// 解析结果价格列表，兼容 `["0.5","0.5"]` 和 `[0.5,0.5]` 两种格式
+    pub fn prices(&self) -> Option<Vec<f64>> {
+        parse_prices(self.outcome_prices.as_deref()?)
+    }
+
+    /// 将价格归一化为隐含概率（和为 1.0），多结果市场按全部结果的价格总和归一化
+    ///
+    /// 原始价格因买卖价差通常不严格等于 1。价格无法解析、数量与结果选项不一致
+    /// 或价格总和不为正时返回 None
+    pub fn implied_probabilities(&self) -> Option<Vec<f64>> {
+        let prices = self.prices()?;
+        let outcomes = self.outcome_list()?;
+        if prices.is_empty() || prices.len() != outcomes.len() {
+            return None;
+        }
+        let total: f64 = prices.iter().sum();
+        if total <= 0.0 || !total.is_finite() {
+            return None;
+        }
+        Some(prices.iter().map(|p| p / total).collect())
+    }
+
+    /// 二元 Yes/No 市场中 "Yes" 的隐含概率，其他市场返回 None
+    pub fn yes_probability(&self) -> Option<f64> {
+        let yes_index = self.binary_yes_index()?;
+        self.implied_probabilities()?.get(yes_index).copied()
+    }
+
+    /// 将结果价格四舍五入到 `decimals` 位小数并重写为统一格式（`["0.5","0.1235"]`）
+    ///
+    /// 避免 API 时而返回 "0.5" 时而返回 "0.50" 造成误报的变化。价格无法解析时保持原样
+    pub fn normalize_prices(&mut self, decimals: u32) {
+        let Some(prices) = self.prices() else {
+            return;
+        };
+        let factor = 10f64.powi(decimals as i32);
+        let normalized: Vec<String> = prices
+            .iter()
+            .map(|p| ((p * factor).round() / factor).to_string())
+            .collect();
+        if let Ok(json) = serde_json::to_string(&normalized) {
+            self.outcome_prices = Some(json);
+        }
+    }
+
+    /// 解析成交量，空值或无法解析时返回 None
+    pub fn volume_value(&self) -> Option<f64> {
+        self.volume.as_deref()?.trim().parse().ok()
+    }
+
+    /// 解析流动性，空值或无法解析时返回 None
+    pub fn liquidity_value(&self) -> Option<f64> {
+        self.liquidity.as_deref()?.trim().parse().ok()
+    }
+
+    /// 解析结束时间，空值或无法解析时返回 None
+    ///
+    /// 支持 API 返回的几种 ISO-8601 写法：带时区的 RFC 3339（`2024-11-05T12:00:00Z`、
+    /// `2024-11-05T12:00:00.000+00:00`）、空格分隔且时区为 `+00` 的写法、
+    /// 不带时区的时间（按 UTC）以及只有日期的写法（按当天 00:00 UTC）
+    pub fn end_date_parsed(&self) -> Option<DateTime<Utc>> {
+        parse_end_date(self.end_date.as_deref()?)
+    }
+
+    /// 解析创建时间，支持的写法与 `end_date_parsed` 相同
+    pub fn created_at_parsed(&self) -> Option<DateTime<Utc>> {
+        parse_end_date(self.created_at.as_deref()?)
+    }
+
+    /// 从结算状态和价格推断获胜结果的下标
+    ///
+    /// 市场已关闭、`uma_resolution_status` 为 "resolved"（API 未返回该字段时不检查），
+    /// 且恰好一个结果的价格为 1、其余为 0 时返回该结果的下标。
+    /// 尚未结算或按比例结算（如 50/50）时返回 None
+    pub fn winning_outcome(&self) -> Option<usize> {
+        if self.closed != Some(true) {
+            return None;
+        }
+        if let Some(status) = &self.uma_resolution_status {
+            if !status.trim().eq_ignore_ascii_case("resolved") {
+                return None;
+            }
+        }
+        let prices = self.prices()?;
+        let winner = prices
+            .iter()
+            .position(|p| (p - 1.0).abs() <= RESOLVED_PRICE_EPSILON)?;
+        let others_zero = prices
+            .iter()
+            .enumerate()
+            .all(|(i, p)| i == winner || p.abs() <= RESOLVED_PRICE_EPSILON);
+        others_zero.then_some(winner)
+    }
+
+    /// 未设置 `resolved_outcome` 时按 `winning_outcome` 填充，返回市场是否已结算
+    pub fn fill_resolution(&mut self) -> bool {
+        if self.resolved_outcome.is_none() {
+            self.resolved_outcome = self.winning_outcome();
+        }
+        self.resolved_outcome.is_some()
+    }
+
+    /// 比较两个快照，`self` 为旧快照，`new` 为新快照
+    ///
+    /// 数值字段按解析后的数值比较，"0.5" 与 "0.50"、"1000" 与 "1000.0" 不算变化；
+    /// 无法解析时按原始字符串比较
+    pub fn diff(&self, new: &Market) -> MarketDiff {
+        let mut diff = MarketDiff::default();
+        match (self.prices(), new.prices()) {
+            (Some(old_prices), Some(new_prices)) if old_prices.len() != new_prices.len() => {
+                diff.outcome_count = Some((old_prices.len(), new_prices.len()));
+            }
+            (Some(old_prices), Some(new_prices)) => {
+                diff.prices = OutcomeDelta::between(self, new);
+                // 结果名称无法解析时没有逐项变化，但价格仍可能不同
+                diff.raw_prices_changed = diff.prices.is_empty() && old_prices != new_prices;
+            }
+            _ => diff.raw_prices_changed = self.outcome_prices != new.outcome_prices,
+        }
+        diff.volume = NumericChange::between(self.volume.as_deref(), new.volume.as_deref());
+        diff.liquidity =
+            NumericChange::between(self.liquidity.as_deref(), new.liquidity.as_deref());
+        diff.closed = new.closed == Some(true) && self.closed != Some(true);
+        diff
+    }
+
+    /// 获胜结果的名称，未结算时返回 None
+    pub fn resolved_outcome_name(&self) -> Option<String> {
+        self.outcome_list()?.get(self.resolved_outcome?).cloned()
+    }
+
+    /// 是否带有指定标签（忽略大小写）
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// 解析 CLOB token id 列表（与 outcomes 顺序一致）
+    pub fn token_ids(&self) -> Vec<String> {
+        self.clob_token_ids
+            .as_deref()
+            .and_then(|s| serde_json::from_str::<Vec<String>>(s).ok())
+            .unwrap_or_default()
+    }
+}
+
+fn parse_end_date(raw: &str) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return None;
+    }
+    if let Ok(date) = DateTime::parse_from_rfc3339(raw) {
+        return Some(date.with_timezone(&Utc));
+    }
+    // `%#z` 同时接受 `+00`、`+0000` 和 `+00:00`
+    for format in ["%Y-%m-%d %H:%M:%S%.f%#z", "%Y-%m-%dT%H:%M:%S%.f%#z"] {
+        if let Ok(date) = DateTime::parse_from_str(raw, format) {
+            return Some(date.with_timezone(&Utc));
+        }
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(raw, format) {
+            return Some(date.and_utc());
+        }
+    }
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc())
+}
+
+/// 解析价格 JSON 数组，兼容 `["0.5","0.5"]` 和 `[0.5,0.5]` 两种格式
+pub fn parse_prices(raw: &str) -> Option<Vec<f64>> {
+    let values: Vec<serde_json::Value> = serde_json::from_str(raw).ok()?;
+    values
+        .iter()
+        .map(|v| match v {
+            serde_json::Value::String(s) => s.trim().parse::<f64>().ok(),
+            serde_json::Value::Number(n) => n.as_f64(),
+            _ => None,
+        })
+        .collect()
 }
 
+/// 事件 ID 使用的时间分桶长度（秒），同一分桶内的相同事件视为重复
+const EVENT_ID_BUCKET_SECS: i64 = 300;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketEvent {
+    /// 确定性的事件 ID，存储层据此去重（旧数据中可能为空）
+    #[serde(default)]
+    pub id: String,
     pub market: Market,
     pub timestamp: DateTime<Utc>,
     pub event_type: EventType,
+    /// 价格变化事件中各结果的新旧价格（仅包含发生变化的结果）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<OutcomeDelta>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 单个结果的价格变化
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeDelta {
+    pub outcome: String,
+    pub old: f64,
+    pub new: f64,
+    /// 归一化后的隐含概率（见 `Market::implied_probabilities`），无法计算时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_probability: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_probability: Option<f64>,
+}
+
+impl OutcomeDelta {
+    /// 计算两个市场快照之间各结果的价格和隐含概率变化
+    ///
+    /// 结果选项或价格无法解析、或数量不一致时返回空列表。
+    /// 价格或隐含概率任一发生变化的结果都会包含在内
+    pub fn between(old: &Market, new: &Market) -> Vec<OutcomeDelta> {
+        let (Some(outcomes), Some(old_prices), Some(new_prices)) =
+            (new.outcome_list(), old.prices(), new.prices())
+        else {
+            return Vec::new();
+        };
+        if outcomes.len() != old_prices.len() || outcomes.len() != new_prices.len() {
+            return Vec::new();
+        }
+        let old_probabilities = old.implied_probabilities().unwrap_or_default();
+        let new_probabilities = new.implied_probabilities().unwrap_or_default();
+
+        outcomes
+            .into_iter()
+            .zip(old_prices.into_iter().zip(new_prices))
+            .enumerate()
+            .map(|(i, (outcome, (old, new)))| OutcomeDelta {
+                outcome,
+                old,
+                new,
+                old_probability: old_probabilities.get(i).copied(),
+                new_probability: new_probabilities.get(i).copied(),
+            })
+            .filter(|d| d.old != d.new || d.old_probability != d.new_probability)
+            .collect()
+    }
+
+    /// 价格变化量
+    pub fn delta(&self) -> f64 {
+        self.new - self.old
+    }
+
+    /// 隐含概率变化量，任一侧无法计算时返回 None
+    pub fn probability_delta(&self) -> Option<f64> {
+        Some(self.new_probability? - self.old_probability?)
+    }
+}
+
+/// 数值字段的变化，无法解析的一侧为 None
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct NumericChange {
+    pub old: Option<f64>,
+    pub new: Option<f64>,
+}
+
+impl NumericChange {
+    /// 比较两个数值字符串，数值相同（或都为空）时返回 None
+    fn between(old: Option<&str>, new: Option<&str>) -> Option<NumericChange> {
+        fn present(v: Option<&str>) -> Option<&str> {
+            v.map(str::trim).filter(|v| !v.is_empty())
+        }
+        let (old_raw, new_raw) = (present(old), present(new));
+        let parse = |v: Option<&str>| v.and_then(|v| v.parse::<f64>().ok());
+        let (old_value, new_value) = (parse(old_raw), parse(new_raw));
+        let changed = match (old_value, new_value) {
+            (Some(o), Some(n)) => o != n,
+            _ => old_raw != new_raw,
+        };
+        changed.then_some(NumericChange {
+            old: old_value,
+            new: new_value,
+        })
+    }
+
+    /// 变化量，任一侧无法解析时返回 None
+    pub fn delta(&self) -> Option<f64> {
+        Some(self.new? - self.old?)
+    }
+}
+
+/// 两个市场快照之间的差异，见 `Market::diff`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MarketDiff {
+    /// 各结果的价格和隐含概率变化（仅包含发生变化的结果）
+    pub prices: Vec<OutcomeDelta>,
+    /// 价格发生了变化但无法逐项比较（价格或结果名称无法解析）
+    pub raw_prices_changed: bool,
+    /// 价格数量发生了变化 `(旧数量, 新数量)`，此时不做逐项比较
+    pub outcome_count: Option<(usize, usize)>,
+    pub volume: Option<NumericChange>,
+    pub liquidity: Option<NumericChange>,
+    /// 市场在两个快照之间关闭
+    pub closed: bool,
+}
+
+impl MarketDiff {
+    /// 没有任何变化
+    pub fn is_empty(&self) -> bool {
+        self.prices.is_empty()
+            && !self.raw_prices_changed
+            && self.outcome_count.is_none()
+            && self.volume.is_none()
+            && self.liquidity.is_none()
+            && !self.closed
+    }
+
+    /// 价格是否有任何变化
+    pub fn prices_changed(&self) -> bool {
+        !self.prices.is_empty() || self.raw_prices_changed || self.outcome_count.is_some()
+    }
+
+    /// 所有结果中价格变化的最大绝对值，没有逐项变化时返回 None
+    pub fn max_price_delta(&self) -> Option<f64> {
+        self.prices
+            .iter()
+            .map(|d| d.delta().abs())
+            .reduce(f64::max)
+    }
+
+    /// 所有结果中隐含概率变化的最大绝对值，任一结果无法计算隐含概率时返回 None
+    pub fn max_probability_delta(&self) -> Option<f64> {
+        self.prices
+            .iter()
+            .map(|d| d.probability_delta().map(f64::abs))
+            .collect::<Option<Vec<f64>>>()?
+            .into_iter()
+            .reduce(f64::max)
+    }
+}
+
+impl MarketEvent {
+    /// 以当前时间创建事件并计算事件 ID
+    pub fn new(market: Market, event_type: EventType) -> Self {
+        let timestamp = Utc::now();
+        let id = Self::compute_id(&market, &event_type, timestamp);
+        Self {
+            id,
+            market,
+            timestamp,
+            event_type,
+            changes: Vec::new(),
+        }
+    }
+
+    /// 附加各结果的价格变化
+    pub fn with_changes(mut self, changes: Vec<OutcomeDelta>) -> Self {
+        self.changes = changes;
+        self
+    }
+
+    /// 事件 ID：condition_id + 事件类型 + 归一化的事件内容 + 时间分桶 的 FNV-1a 哈希
+    ///
+    /// 重启后或多个扫描器实例在同一时间段内检测到同一变化时得到相同的 ID
+    pub fn compute_id(market: &Market, event_type: &EventType, timestamp: DateTime<Utc>) -> String {
+        let payload = match event_type {
+            EventType::PriceChange => match market.prices() {
+                Some(prices) => prices
+                    .iter()
+                    .map(|p| format!("{:.6}", p))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                None => market.outcome_prices.clone().unwrap_or_default(),
+            },
+            EventType::VolumeUpdate => match market.volume_value() {
+                Some(volume) => format!("{:.2}", volume),
+                None => market.volume.clone().unwrap_or_default(),
+            },
+            EventType::SpreadChange { old, new } => format!("{:.6},{:.6}", old, new),
+            EventType::HighActivity { score } => format!("{:.4}", score),
+            EventType::MalformedUpdate { reason } => reason.clone(),
+            EventType::ThresholdCrossed {
+                outcome_index,
+                threshold,
+                direction,
+                ..
+            } => format!("{}:{}:{}", outcome_index, direction.name(), threshold),
+            EventType::NewMarket
+            | EventType::MarketClosed
+            | EventType::MarketDelisted
+            | EventType::MarketStale => String::new(),
+        };
+        let bucket = timestamp.timestamp().div_euclid(EVENT_ID_BUCKET_SECS);
+
+        let key = format!(
+            "{}|{}|{}|{}",
+            market.condition_id,
+            event_type.name(),
+            payload,
+            bucket
+        );
+        format!("{:016x}", fnv1a(key.as_bytes()))
+    }
+}
+
+/// 64 位 FNV-1a 哈希，结果不依赖 Rust 版本或进程
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum EventType {
     NewMarket,
     PriceChange,
     VolumeUpdate,
     MarketClosed,
+    /// 市场连续多轮全量扫描都未出现在 API 返回中（已下架，而不只是关闭）
+    MarketDelisted,
+    /// 仍未关闭的市场超过 `ScannerConfig::stale_after` 没有保存过变化，
+    /// 可能是市场确实冷清，也可能是扫描器没能获取到它的最新数据
+    MarketStale,
+    /// 买卖价差变化超过阈值
+    SpreadChange {
+        old: f64,
+        new: f64,
+    },
+    /// 单轮扫描中成交量与价格变化的综合活跃度超过阈值
+    HighActivity {
+        score: f64,
+    },
+    /// 已追踪市场的新数据未通过校验（如结算过程中结果数量与价格数量不一致），
+    /// 不参与价格比较，也不会覆盖已保存的市场
+    MalformedUpdate {
+        reason: String,
+    },
+    /// 某个结果的价格穿越了 `PriceAlert` 设定的阈值
+    ThresholdCrossed {
+        outcome_index: usize,
+        threshold: f64,
+        direction: CrossDirection,
+        /// 穿越后的价格
+        price: f64,
+    },
+}
+
+impl EventType {
+    /// 事件类型名称（不含附带数据），用于过滤和存储
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventType::NewMarket => "NewMarket",
+            EventType::PriceChange => "PriceChange",
+            EventType::VolumeUpdate => "VolumeUpdate",
+            EventType::MarketClosed => "MarketClosed",
+            EventType::MarketDelisted => "MarketDelisted",
+            EventType::MarketStale => "MarketStale",
+            EventType::SpreadChange { .. } => "SpreadChange",
+            EventType::HighActivity { .. } => "HighActivity",
+            EventType::MalformedUpdate { .. } => "MalformedUpdate",
+            EventType::ThresholdCrossed { .. } => "ThresholdCrossed",
+        }
+    }
+}
+
+/// 价格穿越阈值的方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrossDirection {
+    /// 价格从阈值及以下升到阈值以上
+    Above,
+    /// 价格从阈值及以上跌到阈值以下
+    Below,
+}
+
+impl CrossDirection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CrossDirection::Above => "above",
+            CrossDirection::Below => "below",
+        }
+    }
+}
+
+/// 归一化结果名称，见 `Market::normalized_outcomes`
+pub fn normalize_outcome(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// 单个结果的价格阈值提醒，例如 "YES 价格向上穿越 0.80 时通知"
+///
+/// 同一市场可以设置多个提醒；市场、结果、方向和阈值都相同的提醒视为同一个
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceAlert {
+    pub condition_id: String,
+    /// 结果在 `outcomes` 中的下标，二元市场中 0 通常为 Yes
+    pub outcome_index: usize,
+    /// 按名称指定的结果（已归一化），设置时忽略 `outcome_index`，
+    /// 每次检查时在市场的结果中按名称查找，不受大小写和空白差异影响
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome_name: Option<String>,
+    pub threshold: f64,
+    pub direction: CrossDirection,
+}
+
+impl PriceAlert {
+    pub fn new(
+        condition_id: impl Into<String>,
+        outcome_index: usize,
+        threshold: f64,
+        direction: CrossDirection,
+    ) -> Self {
+        Self {
+            condition_id: condition_id.into(),
+            outcome_index,
+            outcome_name: None,
+            threshold,
+            direction,
+        }
+    }
+
+    /// 按结果名称创建提醒，例如 `PriceAlert::for_outcome(id, "YES", 0.8, CrossDirection::Above)`
+    pub fn for_outcome(
+        condition_id: impl Into<String>,
+        outcome: &str,
+        threshold: f64,
+        direction: CrossDirection,
+    ) -> Self {
+        Self {
+            outcome_name: Some(normalize_outcome(outcome)),
+            ..Self::new(condition_id, 0, threshold, direction)
+        }
+    }
+
+    /// 提醒 ID，用作存储中的键
+    pub fn id(&self) -> String {
+        let outcome = match &self.outcome_name {
+            Some(name) => name.clone(),
+            None => self.outcome_index.to_string(),
+        };
+        format!(
+            "{}:{}:{}:{}",
+            self.condition_id,
+            outcome,
+            self.direction.name(),
+            self.threshold
+        )
+    }
+
+    /// 提醒对应的结果在 `market` 中的下标，按名称指定且市场中没有该结果时返回 None
+    pub fn resolve_outcome(&self, market: &Market) -> Option<usize> {
+        match &self.outcome_name {
+            Some(name) => market.outcome_index(name),
+            None => Some(self.outcome_index),
+        }
+    }
+
+    /// 检查阈值是否为有效的价格（0 到 1 之间）
+    pub fn validate(&self) -> crate::error::Result<()> {
+        if self.condition_id.is_empty() {
+            return Err(ScannerError::ConfigError(
+                "价格提醒缺少 condition_id".to_string(),
+            ));
+        }
+        if self.outcome_name.as_deref() == Some("") {
+            return Err(ScannerError::ConfigError(
+                "价格提醒的结果名称为空".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.threshold) {
+            return Err(ScannerError::ConfigError(format!(
+                "价格提醒阈值必须在 0 到 1 之间: {}",
+                self.threshold
+            )));
+        }
+        Ok(())
+    }
+
+    /// 价格从 `old` 变为 `new` 时是否按设定方向穿越阈值
+    pub fn crossed(&self, old: f64, new: f64) -> bool {
+        match self.direction {
+            CrossDirection::Above => old <= self.threshold && new > self.threshold,
+            CrossDirection::Below => old >= self.threshold && new < self.threshold,
+        }
+    }
+
+    /// 比较两个市场快照，触发时返回穿越后的价格
+    ///
+    /// 价格无法解析、结果下标越界或找不到指定名称的结果时不触发
+    pub fn check(&self, old: &Market, new: &Market) -> Option<f64> {
+        let old_price = *old.prices()?.get(self.resolve_outcome(old)?)?;
+        let new_price = *new.prices()?.get(self.resolve_outcome(new)?)?;
+        self.crossed(old_price, new_price).then_some(new_price)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,8 +773,269 @@ pub struct MarketsResponse {
     pub next_cursor: Option<String>,
 }
 
+/// Gamma `/tags` 返回的标签（分类）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tag {
+    /// Gamma 以字符串返回，也兼容数字
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub slug: String,
+}
+
+impl Tag {
+    /// 名称是否与标签的 label 或 slug 相同（忽略大小写），与 `Market::has_tag` 的比较方式一致
+    pub fn matches(&self, name: &str) -> bool {
+        self.label.eq_ignore_ascii_case(name) || self.slug.eq_ignore_ascii_case(name)
+    }
+}
+
+fn deserialize_id<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => Ok(s),
+        serde_json::Value::Number(n) => Ok(n.to_string()),
+        other => Err(serde::de::Error::custom(format!("无效的标签 ID: {}", other))),
+    }
+}
+
+/// 市场的原始数据和解析后的数值形式，见 `Storage::get_market_parsed`
+///
+/// `raw` 保留 API 返回的原始字符串，便于审计，或在解析逻辑有误时重新解析
+#[derive(Debug, Clone, Serialize)]
+pub struct ParsedMarket {
+    pub raw: Market,
+    /// 结果名称，`outcomes` 无法解析时为空
+    pub outcomes: Vec<String>,
+    /// 各结果的价格，无法解析时为 None
+    pub prices: Option<Vec<f64>>,
+    pub volume: Option<f64>,
+    pub liquidity: Option<f64>,
+}
+
+impl ParsedMarket {
+    /// 使用存储中保存时派生的解析字段，缺失的字段（旧数据或未保存解析字段）从原始字符串解析
+    pub fn from_stored(
+        raw: Market,
+        outcomes: Option<Vec<String>>,
+        prices: Option<Vec<f64>>,
+        volume: Option<f64>,
+        liquidity: Option<f64>,
+    ) -> Self {
+        Self {
+            outcomes: outcomes.unwrap_or_else(|| raw.outcome_list().unwrap_or_default()),
+            prices: prices.or_else(|| raw.prices()),
+            volume: volume.or_else(|| raw.volume_value()),
+            liquidity: liquidity.or_else(|| raw.liquidity_value()),
+            raw,
+        }
+    }
+}
+
+impl From<Market> for ParsedMarket {
+    /// 从原始字符串解析，与保存时派生解析字段的规则相同
+    fn from(raw: Market) -> Self {
+        Self::from_stored(raw, None, None, None, None)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PriceHistory {
     pub t: i64, // timestamp
     pub p: f64, // price
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderLevel {
+    pub price: String,
+    pub size: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub market: Option<String>,
+    pub asset_id: Option<String>,
+    #[serde(default)]
+    pub bids: Vec<OrderLevel>,
+    #[serde(default)]
+    pub asks: Vec<OrderLevel>,
+}
+
+impl OrderBook {
+    /// 最高买价
+    pub fn best_bid(&self) -> Option<f64> {
+        self.bids
+            .iter()
+            .filter_map(|level| level.price.parse::<f64>().ok())
+            .reduce(f64::max)
+    }
+
+    /// 最低卖价
+    pub fn best_ask(&self) -> Option<f64> {
+        self.asks
+            .iter()
+            .filter_map(|level| level.price.parse::<f64>().ok())
+            .reduce(f64::min)
+    }
+
+    /// 买卖价差，单边为空时返回 None
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()? - self.best_bid()?)
+    }
+
+    /// 中间价（最高买价和最低卖价的平均值），单边为空时返回 None
+    ///
+    /// 成交稀少的市场最后成交价可能已经过时，中间价更接近当前的估值
+    pub fn mid_price(&self) -> Option<f64> {
+        Some((self.best_bid()? + self.best_ask()?) / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn market(outcomes: &str, prices: &str) -> Market {
+        Market {
+            condition_id: "0x01".to_string(),
+            question: "Will it rain?".to_string(),
+            outcomes: outcomes.to_string(),
+            outcome_prices: Some(prices.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn normalize_prices_stores_equal_prices_identically() {
+        let mut short = market(r#"["Yes","No"]"#, r#"["0.5","0.5"]"#);
+        let mut padded = market(r#"["Yes","No"]"#, r#"["0.50","0.500"]"#);
+        short.normalize_prices(4);
+        padded.normalize_prices(4);
+        assert_eq!(short.outcome_prices, padded.outcome_prices);
+        assert_eq!(short.outcome_prices.as_deref(), Some(r#"["0.5","0.5"]"#));
+        assert!(!short.diff(&padded).prices_changed());
+    }
+
+    fn invalid_reason(market: &Market) -> String {
+        match market.validate() {
+            Err(ScannerError::InvalidMarket(reason)) => reason,
+            other => panic!("expected InvalidMarket, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_accepts_well_formed_market() {
+        assert!(market(r#"["Yes","No"]"#, r#"["0.5","0.5"]"#).validate().is_ok());
+        // 没有价格的市场仍然有效
+        let mut unpriced = market(r#"["Yes","No"]"#, "");
+        assert!(unpriced.validate().is_ok());
+        unpriced.outcome_prices = None;
+        assert!(unpriced.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_each_failure_mode() {
+        let valid = market(r#"["Yes","No"]"#, r#"["0.5","0.5"]"#);
+
+        let mut m = valid.clone();
+        m.condition_id = " ".to_string();
+        assert!(invalid_reason(&m).starts_with("condition_id 为空"));
+
+        let mut m = valid.clone();
+        m.question = String::new();
+        assert!(invalid_reason(&m).starts_with("question 为空"));
+
+        for outcomes in ["not json", "[]"] {
+            let mut m = valid.clone();
+            m.outcomes = outcomes.to_string();
+            assert!(invalid_reason(&m).starts_with("outcomes 无法解析或为空"));
+        }
+
+        let mut m = valid.clone();
+        m.outcome_prices = Some(r#"["abc"]"#.to_string());
+        assert!(invalid_reason(&m).starts_with("outcome_prices 无法解析"));
+
+        let mut m = valid;
+        m.outcome_prices = Some(r#"["0.2","0.3","0.5"]"#.to_string());
+        assert!(invalid_reason(&m).starts_with("价格数量 3 与结果数量 2 不一致"));
+    }
+
+    #[test]
+    fn end_date_parses_each_api_format() {
+        let noon = Utc.with_ymd_and_hms(2024, 11, 5, 12, 0, 0).unwrap();
+        for raw in [
+            "2024-11-05T12:00:00Z",
+            "2024-11-05T12:00:00.000+00:00",
+            "2024-11-05 12:00:00+00",
+            "2024-11-05T12:00:00",
+        ] {
+            let m = Market {
+                end_date: Some(raw.to_string()),
+                ..Default::default()
+            };
+            assert_eq!(m.end_date_parsed(), Some(noon), "{}", raw);
+        }
+        assert_eq!(
+            parse_end_date("2024-11-05"),
+            Some(Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            parse_end_date("2024-11-05T14:00:00+02:00"),
+            Some(noon),
+            "非 UTC 时区换算为 UTC"
+        );
+    }
+
+    #[test]
+    fn end_date_rejects_empty_and_garbage() {
+        assert_eq!(parse_end_date(""), None);
+        assert_eq!(parse_end_date("  "), None);
+        assert_eq!(parse_end_date("next tuesday"), None);
+        assert_eq!(Market::default().end_date_parsed(), None);
+    }
+
+    #[test]
+    fn outcome_lookup_ignores_case_and_whitespace() {
+        let m = market(r#"["YES", " no "]"#, r#"["0.7","0.3"]"#);
+        assert_eq!(m.normalized_outcomes(), ["yes", "no"]);
+        assert_eq!(m.outcome_index("Yes"), Some(0));
+        assert_eq!(m.outcome_index("NO"), Some(1));
+        assert_eq!(m.outcome_index("maybe"), None);
+        assert_eq!(m.binary_yes_index(), Some(0));
+
+        let reversed = market(r#"["No ","yes"]"#, r#"["0.3","0.7"]"#);
+        assert_eq!(reversed.binary_yes_index(), Some(1));
+
+        let multi_word = market(r#"["Trump  Jr.","Other"]"#, r#"["0.1","0.9"]"#);
+        assert_eq!(multi_word.outcome_index(" trump jr. "), Some(0));
+        assert_eq!(multi_word.binary_yes_index(), None);
+    }
+
+    #[test]
+    fn five_outcome_market_probabilities() {
+        let m = market(
+            r#"["A","B","C","D","E"]"#,
+            r#"["0.40","0.25",0.15,"0.1","0.2"]"#,
+        );
+        assert_eq!(m.prices(), Some(vec![0.4, 0.25, 0.15, 0.1, 0.2]));
+
+        let probabilities = m.implied_probabilities().unwrap();
+        let expected = [0.4 / 1.1, 0.25 / 1.1, 0.15 / 1.1, 0.1 / 1.1, 0.2 / 1.1];
+        assert_eq!(probabilities.len(), 5);
+        for (p, e) in probabilities.iter().zip(expected) {
+            assert!((p - e).abs() < 1e-12, "{} != {}", p, e);
+        }
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(m.yes_probability(), None);
+
+        let mismatched = market(r#"["A","B","C","D","E"]"#, r#"["0.2","0.2","0.2","0.4"]"#);
+        assert_eq!(mismatched.implied_probabilities(), None);
+        let zero = market(r#"["A","B","C","D","E"]"#, r#"["0","0","0","0","0"]"#);
+        assert_eq!(zero.implied_probabilities(), None);
+    }
+}